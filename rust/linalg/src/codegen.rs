@@ -0,0 +1,288 @@
+// =============================================================================
+// Rust Code Generation
+// =============================================================================
+//
+// The JS `matInvert` was printed straight from the expression tree, so every
+// element repeats the full determinant and the shared 2x2 minors. Here the
+// trees are hash-consed first and any subexpression used more than once is
+// hoisted into a `let`.
+
+use std::collections::HashMap;
+use std::fmt::Write;
+
+use crate::expr::{simplify, smart_str_with, Expr, Operator, PrintStyle};
+use crate::matrix::{
+	mat_cofactor_matrix, mat_determinant, mat_symbolic, mat_transpose, MatrixError,
+};
+
+/// Straight-line code: temporaries in evaluation order followed by the
+/// requested expressions, all of which refer to temporaries by `Expr::Symbol`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Program {
+	pub temps: Vec<(String, Expr)>,
+	pub roots: Vec<Expr>,
+}
+
+impl Program {
+	/// Evaluate numerically, resolving matrix elements through `leaf`.
+	pub fn evaluate(&self, leaf: &dyn Fn(&Expr) -> f64) -> Vec<f64> {
+		let mut values = HashMap::new();
+		let lookup = |values: &HashMap<String, f64>, e: &Expr| match e {
+			Expr::Symbol(name) if values.contains_key(name) => values[name],
+			_ => leaf(e),
+		};
+		for (name, e) in &self.temps {
+			let v = e.evaluate(&|l| lookup(&values, l));
+			values.insert(name.clone(), v);
+		}
+		self.roots
+			.iter()
+			.map(|e| e.evaluate(&|l| lookup(&values, l)))
+			.collect()
+	}
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+enum Key {
+	Leaf(String),
+	Constant(u64),
+	Unary(Operator, usize),
+	Binary(Operator, usize, usize),
+}
+
+#[derive(Default)]
+struct Graph {
+	keys: Vec<Key>,
+	leaves: HashMap<usize, Expr>,
+	index: HashMap<Key, usize>,
+	uses: Vec<usize>,
+}
+
+impl Graph {
+	fn intern(&mut self, e: &Expr) -> usize {
+		let key = match e {
+			Expr::Constant(c) => Key::Constant(c.to_bits()),
+			Expr::Unary(op, lhs) => Key::Unary(*op, self.intern(lhs)),
+			Expr::Binary(op, lhs, rhs) => {
+				let lhs = self.intern(lhs);
+				let rhs = self.intern(rhs);
+				Key::Binary(*op, lhs, rhs)
+			}
+			_ => Key::Leaf(e.to_string()),
+		};
+		if let Some(&id) = self.index.get(&key) {
+			return id;
+		}
+		let id = self.keys.len();
+		// Count each edge once, when the parent is first created.
+		match key {
+			Key::Unary(_, lhs) => self.uses[lhs] += 1,
+			Key::Binary(_, lhs, rhs) => {
+				self.uses[lhs] += 1;
+				self.uses[rhs] += 1;
+			}
+			_ => {
+				self.leaves.insert(id, e.clone());
+			}
+		}
+		self.keys.push(key.clone());
+		self.uses.push(0);
+		self.index.insert(key, id);
+		id
+	}
+
+	fn build(&self, id: usize, names: &HashMap<usize, String>) -> Expr {
+		if let Some(name) = names.get(&id) {
+			return Expr::symbol(name.clone());
+		}
+		self.build_body(id, names)
+	}
+
+	fn build_body(&self, id: usize, names: &HashMap<usize, String>) -> Expr {
+		match self.keys[id] {
+			Key::Unary(op, lhs) => Expr::Unary(op, Box::new(self.build(lhs, names))),
+			Key::Binary(op, lhs, rhs) => {
+				Expr::binary(op, self.build(lhs, names), self.build(rhs, names))
+			}
+			_ => self.leaves[&id].clone(),
+		}
+	}
+}
+
+/// Hoist every non-leaf subexpression that occurs more than once into a
+/// temporary named `{prefix}{k}`.
+pub fn eliminate_common_subexpressions(roots: &[Expr], prefix: &str) -> Program {
+	let mut graph = Graph::default();
+	let ids: Vec<usize> = roots.iter().map(|e| graph.intern(e)).collect();
+	for &id in &ids {
+		graph.uses[id] += 1;
+	}
+	// Node ids are already in dependency order since children are interned first.
+	let mut names = HashMap::new();
+	let mut temps = Vec::new();
+	for id in 0..graph.keys.len() {
+		let shared =
+			graph.uses[id] > 1 && !matches!(graph.keys[id], Key::Leaf(_) | Key::Constant(_));
+		if shared {
+			let name = format!("{prefix}{}", temps.len());
+			temps.push((name.clone(), graph.build_body(id, &names)));
+			names.insert(id, name);
+		}
+	}
+	let roots = ids.iter().map(|&id| graph.build(id, &names)).collect();
+	Program { temps, roots }
+}
+
+/// Print as a Rust expression with the minimal parentheses that preserve the
+/// tree's evaluation order.
+pub fn rust_expr(e: &Expr) -> String {
+	let style = PrintStyle {
+		spaced: true,
+		float_literals: true,
+	};
+	smart_str_with(e, style)
+}
+
+fn write_temps(s: &mut String, program: &Program) {
+	for (name, e) in &program.temps {
+		writeln!(s, "\tlet {name} = {};", rust_expr(e)).unwrap();
+	}
+}
+
+/// Emit `fn {name}(n: &[f32; N*N]) -> f32` computing the determinant of a
+/// row-major `size` x `size` matrix.
+pub fn emit_determinant(name: &str, size: usize) -> Result<String, MatrixError> {
+	let det = simplify(&mat_determinant(&mat_symbolic(size, true))?);
+	let program = eliminate_common_subexpressions(&[det], "t");
+	let mut s = String::new();
+	writeln!(
+		s,
+		"pub(crate) fn {name}(n: &[f32; {}]) -> f32 {{",
+		size * size
+	)
+	.unwrap();
+	write_temps(&mut s, &program);
+	writeln!(s, "\t{}", rust_expr(&program.roots[0])).unwrap();
+	writeln!(s, "}}").unwrap();
+	Ok(s)
+}
+
+/// Emit `fn {name}(n: &[f32; N*N]) -> Option<[f32; N*N]>` computing the
+/// inverse by the adjugate method, with the determinant evaluated once.
+pub fn emit_inverse(name: &str, size: usize) -> Result<String, MatrixError> {
+	let program = inverse_program(size)?;
+	let mut s = String::new();
+	let n = size * size;
+	writeln!(
+		s,
+		"pub(crate) fn {name}(n: &[f32; {n}]) -> Option<[f32; {n}]> {{"
+	)
+	.unwrap();
+	write_temps(&mut s, &program);
+	writeln!(s, "\tlet det = {};", rust_expr(&program.roots[0])).unwrap();
+	writeln!(s, "\tif det == 0.0 {{\n\t\treturn None;\n\t}}").unwrap();
+	writeln!(s, "\tlet inv_det = 1.0 / det;").unwrap();
+	writeln!(s, "\tSome([").unwrap();
+	for e in &program.roots[1..] {
+		let scaled = e.clone() * Expr::symbol("inv_det");
+		writeln!(s, "\t\t{},", rust_expr(&scaled)).unwrap();
+	}
+	writeln!(s, "\t])").unwrap();
+	writeln!(s, "}}").unwrap();
+	Ok(s)
+}
+
+/// The determinant followed by the row-major adjugate, sharing temporaries.
+pub fn inverse_program(size: usize) -> Result<Program, MatrixError> {
+	let m = mat_symbolic(size, true);
+	let mut roots = vec![simplify(&mat_determinant(&m)?)];
+	let adjugate = mat_transpose(&mat_cofactor_matrix(&m)?);
+	roots.extend(adjugate.iter().flatten().map(simplify));
+	Ok(eliminate_common_subexpressions(&roots, "t"))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn leaf(values: &[f64]) -> impl Fn(&Expr) -> f64 + '_ {
+		move |e| match e {
+			Expr::FlatMatrixElement { i, j, size, .. } => values[i * size + j],
+			_ => panic!("unexpected leaf {e}"),
+		}
+	}
+
+	#[test]
+	fn rust_expr_parentheses() {
+		let (a, b, c) = (Expr::symbol("a"), Expr::symbol("b"), Expr::symbol("c"));
+		assert_eq!(
+			rust_expr(&(a.clone() - (b.clone() - c.clone()))),
+			"a - (b - c)"
+		);
+		assert_eq!(rust_expr(&(a.clone() - b.clone() - c.clone())), "a - b - c");
+		assert_eq!(
+			rust_expr(&(a.clone() * (b.clone() + c.clone()))),
+			"a * (b + c)"
+		);
+		assert_eq!(rust_expr(&(-(a.clone() * b.clone()))), "-(a * b)");
+		assert_eq!(rust_expr(&(-a.clone() * b.clone())), "-a * b");
+		assert_eq!(rust_expr(&(a / Expr::from(-2.0))), "a / (-2.0)");
+	}
+
+	#[test]
+	fn shared_subexpressions_are_hoisted() {
+		let (a, b) = (Expr::symbol("a"), Expr::symbol("b"));
+		let ab = a.clone() * b.clone();
+		let program =
+			eliminate_common_subexpressions(&[ab.clone() + a.clone(), ab.clone() - b], "t");
+		assert_eq!(program.temps, vec![("t0".to_string(), ab)]);
+		assert_eq!(rust_expr(&program.roots[0]), "t0 + a");
+	}
+
+	#[test]
+	fn inverse_evaluates_determinant_once() {
+		let code = emit_inverse("mat4_inverse", 4).unwrap();
+		assert_eq!(code.matches("let det").count(), 1);
+		assert_eq!(code.matches('/').count(), 1);
+	}
+
+	#[test]
+	fn inverse_program_is_correct() {
+		for size in 2..=5 {
+			// Diagonally dominant, so comfortably invertible.
+			let a: Vec<f64> = (0..size * size)
+				.map(|k| {
+					if k % (size + 1) == 0 {
+						10.0
+					} else {
+						((k * 7) % 5) as f64 - 2.0
+					}
+				})
+				.collect();
+			let values = inverse_program(size).unwrap().evaluate(&leaf(&a));
+			let det = values[0];
+			for i in 0..size {
+				for j in 0..size {
+					let dot: f64 = (0..size)
+						.map(|k| a[i * size + k] * values[1 + k * size + j] / det)
+						.sum();
+					let expected = if i == j { 1.0 } else { 0.0 };
+					assert!(
+						(dot - expected).abs() < 1e-9,
+						"size {size} [{i}][{j}] = {dot}"
+					);
+				}
+			}
+		}
+	}
+
+	#[test]
+	fn determinant_matches_expansion() {
+		let a = [2.0, 0.0, 1.0, 3.0, 1.0, 4.0, 0.0, 5.0, 2.0];
+		let code = emit_determinant("mat3_determinant", 3).unwrap();
+		assert!(code.starts_with("pub(crate) fn mat3_determinant(n: &[f32; 9]) -> f32 {"));
+		let det = simplify(&mat_determinant(&mat_symbolic(3, true)).unwrap()).evaluate(&leaf(&a));
+		// 2 * (1 * 2 - 4 * 5) - 0 * (3 * 2 - 4 * 0) + 1 * (3 * 5 - 1 * 0)
+		assert_eq!(det, -21.0);
+	}
+}
@@ -0,0 +1,598 @@
+// =============================================================================
+// Symbolic Expression Trees
+// =============================================================================
+
+use std::fmt;
+use std::ops;
+
+/// Operators that can appear in an expression tree.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Operator {
+	Negate,
+	Add,
+	Subtract,
+	Multiply,
+	Divide,
+}
+
+impl Operator {
+	pub fn symbol(self) -> &'static str {
+		match self {
+			Operator::Negate | Operator::Subtract => "-",
+			Operator::Add => "+",
+			Operator::Multiply => "*",
+			Operator::Divide => "/",
+		}
+	}
+
+	/// Precedence level of the operator (higher = tighter binding).
+	pub fn precedence(self) -> u8 {
+		match self {
+			Operator::Negate => 4,
+			Operator::Multiply | Operator::Divide => 3,
+			Operator::Add | Operator::Subtract => 2,
+		}
+	}
+
+	pub fn is_unary(self) -> bool {
+		self == Operator::Negate
+	}
+
+	// The algebraic properties below replace the `HasIdentity`,
+	// `HasZeroElement`, `IsCommutative`, `IsAssociative` and `IsAdditionLike`
+	// protocols of the Python version.
+
+	/// The identity element, if any: `x op e == x`.
+	pub fn identity_element(self) -> Option<f64> {
+		match self {
+			Operator::Negate | Operator::Add | Operator::Subtract => Some(0.0),
+			Operator::Multiply | Operator::Divide => Some(1.0),
+		}
+	}
+
+	pub fn has_identity(self) -> bool {
+		self.identity_element().is_some()
+	}
+
+	/// The zero element, if any. For addition-like operators it behaves like
+	/// the identity, otherwise it absorbs: `x * 0 == 0`.
+	pub fn zero_element(self) -> Option<f64> {
+		match self {
+			Operator::Add | Operator::Multiply => Some(0.0),
+			_ => None,
+		}
+	}
+
+	pub fn has_zero_element(self) -> bool {
+		self.zero_element().is_some()
+	}
+
+	pub fn is_associative(self) -> bool {
+		matches!(self, Operator::Add | Operator::Multiply)
+	}
+
+	pub fn is_commutative(self) -> bool {
+		matches!(self, Operator::Add | Operator::Multiply)
+	}
+
+	pub fn is_addition_like(self) -> bool {
+		self == Operator::Add
+	}
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Expr {
+	Constant(f64),
+	/// A symbolic matrix element printed as `m[i][j]`.
+	MatrixElement {
+		name: String,
+		i: usize,
+		j: usize,
+	},
+	/// A symbolic matrix element printed with flat indexing as `n[i * size + j]`.
+	FlatMatrixElement {
+		name: String,
+		i: usize,
+		j: usize,
+		size: usize,
+	},
+	/// A named scalar, such as a temporary introduced by code generation.
+	Symbol(String),
+	Unary(Operator, Box<Expr>),
+	Binary(Operator, Box<Expr>, Box<Expr>),
+}
+
+impl Expr {
+	pub fn element(i: usize, j: usize) -> Expr {
+		Expr::MatrixElement {
+			name: "m".into(),
+			i,
+			j,
+		}
+	}
+
+	pub fn flat_element(i: usize, j: usize, size: usize) -> Expr {
+		Expr::FlatMatrixElement {
+			name: "n".into(),
+			i,
+			j,
+			size,
+		}
+	}
+
+	pub fn symbol(name: impl Into<String>) -> Expr {
+		Expr::Symbol(name.into())
+	}
+
+	pub fn negate(lhs: Expr) -> Expr {
+		Expr::Unary(Operator::Negate, Box::new(lhs))
+	}
+
+	pub fn binary(op: Operator, lhs: Expr, rhs: Expr) -> Expr {
+		Expr::Binary(op, Box::new(lhs), Box::new(rhs))
+	}
+
+	pub fn as_constant(&self) -> Option<f64> {
+		match self {
+			Expr::Constant(c) => Some(*c),
+			_ => None,
+		}
+	}
+
+	pub fn is_leaf(&self) -> bool {
+		!matches!(self, Expr::Unary(..) | Expr::Binary(..))
+	}
+
+	/// Evaluate numerically, resolving every non-constant leaf through `leaf`.
+	pub fn evaluate(&self, leaf: &dyn Fn(&Expr) -> f64) -> f64 {
+		match self {
+			Expr::Constant(c) => *c,
+			Expr::Unary(op, lhs) => apply_unary(*op, lhs.evaluate(leaf)),
+			Expr::Binary(op, lhs, rhs) => apply_binary(*op, lhs.evaluate(leaf), rhs.evaluate(leaf)),
+			_ => leaf(self),
+		}
+	}
+}
+
+pub fn apply_unary(op: Operator, lhs: f64) -> f64 {
+	match op {
+		Operator::Negate => -lhs,
+		_ => unreachable!("{op:?} is not a unary operator"),
+	}
+}
+
+pub fn apply_binary(op: Operator, lhs: f64, rhs: f64) -> f64 {
+	match op {
+		Operator::Add => lhs + rhs,
+		Operator::Subtract => lhs - rhs,
+		Operator::Multiply => lhs * rhs,
+		Operator::Divide => lhs / rhs,
+		Operator::Negate => unreachable!("Negate is not a binary operator"),
+	}
+}
+
+impl From<f64> for Expr {
+	fn from(c: f64) -> Expr {
+		Expr::Constant(c)
+	}
+}
+
+impl ops::Neg for Expr {
+	type Output = Expr;
+	fn neg(self) -> Expr {
+		Expr::negate(self)
+	}
+}
+
+macro_rules! impl_binary_op {
+	($trait:ident, $fn:ident, $op:ident) => {
+		impl ops::$trait for Expr {
+			type Output = Expr;
+			fn $fn(self, rhs: Expr) -> Expr {
+				Expr::binary(Operator::$op, self, rhs)
+			}
+		}
+	};
+}
+
+impl_binary_op!(Add, add, Add);
+impl_binary_op!(Sub, sub, Subtract);
+impl_binary_op!(Mul, mul, Multiply);
+impl_binary_op!(Div, div, Divide);
+
+/// Fully parenthesized, like `__str__` in the Python version.
+impl fmt::Display for Expr {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Expr::Constant(c) => write!(f, "{c}"),
+			Expr::MatrixElement { name, i, j } => write!(f, "{name}[{i}][{j}]"),
+			Expr::FlatMatrixElement { name, i, j, size } => write!(f, "{name}[{}]", i * size + j),
+			Expr::Symbol(name) => write!(f, "{name}"),
+			Expr::Unary(op, lhs) => write!(f, "({}{lhs})", op.symbol()),
+			Expr::Binary(op, lhs, rhs) => write!(f, "({lhs}{}{rhs})", op.symbol()),
+		}
+	}
+}
+
+// =============================================================================
+// Operator Application
+// =============================================================================
+
+/// Apply a unary operator, folding a numeric operand.
+pub fn apply_unary_operator(op: Operator, operand: Expr) -> Expr {
+	// Apply identity simplifications: -0 -> 0
+	if op.has_identity() && operand.as_constant() == op.identity_element() {
+		return operand;
+	}
+	match operand {
+		Expr::Constant(c) => Expr::Constant(apply_unary(op, c)),
+		operand => Expr::Unary(op, Box::new(operand)),
+	}
+}
+
+/// Apply a binary operator, removing identity and zero elements and folding
+/// numeric operands.
+pub fn apply_binary_operator(op: Operator, lhs: Expr, rhs: Expr) -> Expr {
+	let (l, r) = (lhs.as_constant(), rhs.as_constant());
+	if let Some(identity) = op.identity_element() {
+		if op.is_commutative() && (l == Some(identity) || r == Some(identity)) {
+			return if r == Some(identity) { lhs } else { rhs };
+		}
+		if !op.is_commutative() && r == Some(identity) {
+			return lhs;
+		}
+	}
+	if let Some(zero) = op.zero_element() {
+		if l == Some(zero) || r == Some(zero) {
+			if op.is_addition_like() {
+				return if r == Some(zero) { lhs } else { rhs };
+			}
+			return Expr::Constant(zero);
+		}
+	}
+	match (l, r) {
+		(Some(l), Some(r)) => Expr::Constant(apply_binary(op, l, r)),
+		_ => Expr::binary(op, lhs, rhs),
+	}
+}
+
+// =============================================================================
+// Simplification
+// =============================================================================
+
+/// Simplify an expression bottom up using the algebraic properties of its
+/// operators.
+pub fn simplify(node: &Expr) -> Expr {
+	match node {
+		Expr::Unary(op, lhs) => simplify_unary(*op, lhs),
+		Expr::Binary(op, lhs, rhs) => simplify_binary(*op, lhs, rhs),
+		_ => node.clone(),
+	}
+}
+
+fn simplify_unary(op: Operator, lhs: &Expr) -> Expr {
+	let result = apply_unary_operator(op, simplify(lhs));
+	// Double negation: -(-x) -> x (for any unary operator that is its own inverse)
+	match result {
+		Expr::Unary(outer, inner) if outer == op => match *inner {
+			Expr::Unary(op2, x) if op2 == op => *x,
+			inner => Expr::Unary(outer, Box::new(inner)),
+		},
+		result => result,
+	}
+}
+
+fn simplify_binary(op: Operator, lhs: &Expr, rhs: &Expr) -> Expr {
+	let mut result = apply_binary_operator(op, simplify(lhs), simplify(rhs));
+	// If associative and commutative, flatten and combine constants
+	if let Expr::Binary(op, ..) = result {
+		if op.is_associative() && op.is_commutative() {
+			return combine_associative_commutative(op, &result);
+		}
+	}
+	// Apply binary-specific simplifications based on algebraic properties
+	while matches!(result, Expr::Binary(..)) {
+		let old = result.clone();
+		result = apply_identity_simplifications(result);
+		result = apply_zero_simplifications(result);
+		result = apply_constant_folding(result);
+		result = apply_associative_simplifications(result);
+		result = apply_commutative_simplifications(result);
+		if result == old {
+			break;
+		}
+	}
+	result
+}
+
+/// Flatten a tree of associative/commutative operators into a list of leaves.
+pub fn flatten_associative_commutative(node: &Expr, op: Operator) -> Vec<&Expr> {
+	fn recurse<'a>(n: &'a Expr, op: Operator, items: &mut Vec<&'a Expr>) {
+		match n {
+			Expr::Binary(o, lhs, rhs) if *o == op => {
+				recurse(lhs, op, items);
+				recurse(rhs, op, items);
+			}
+			_ => items.push(n),
+		}
+	}
+	let mut items = Vec::new();
+	recurse(node, op, &mut items);
+	items
+}
+
+// All constants are combined into one, placed last. The Python version sums
+// the constants whatever the operator is; here they are folded with the
+// operator itself so that products come out right.
+fn combine_associative_commutative(op: Operator, node: &Expr) -> Expr {
+	let Some(identity) = op.identity_element() else {
+		return node.clone();
+	};
+	let mut constant = identity;
+	let mut nonconst = Vec::new();
+	for leaf in flatten_associative_commutative(node, op) {
+		match leaf.as_constant() {
+			Some(c) => constant = apply_binary(op, constant, c),
+			None => nonconst.push(leaf.clone()),
+		}
+	}
+	if !op.is_addition_like() && op.zero_element() == Some(constant) {
+		return Expr::Constant(constant);
+	}
+	if constant != identity || nonconst.is_empty() {
+		nonconst.push(Expr::Constant(constant));
+	}
+	let mut leaves = nonconst.into_iter();
+	let first = leaves.next().unwrap();
+	leaves.fold(first, |acc, leaf| Expr::binary(op, acc, leaf))
+}
+
+/// x op identity -> x
+pub fn apply_identity_simplifications(node: Expr) -> Expr {
+	let Expr::Binary(op, lhs, rhs) = node else {
+		return node;
+	};
+	let Some(identity) = op.identity_element() else {
+		return Expr::Binary(op, lhs, rhs);
+	};
+	let (l, r) = (lhs.as_constant(), rhs.as_constant());
+	if op.is_commutative() && (l == Some(identity) || r == Some(identity)) {
+		return if r == Some(identity) { *lhs } else { *rhs };
+	}
+	// For non-commutative operators, only simplify on the appropriate side
+	if !op.is_commutative() && r == Some(identity) {
+		return *lhs;
+	}
+	Expr::Binary(op, lhs, rhs)
+}
+
+/// x + 0 -> x for addition-like operators, x * 0 -> 0 otherwise.
+pub fn apply_zero_simplifications(node: Expr) -> Expr {
+	let Expr::Binary(op, lhs, rhs) = node else {
+		return node;
+	};
+	let Some(zero) = op.zero_element() else {
+		return Expr::Binary(op, lhs, rhs);
+	};
+	let (l, r) = (lhs.as_constant(), rhs.as_constant());
+	if l == Some(zero) || r == Some(zero) {
+		if op.is_addition_like() {
+			return if r == Some(zero) { *lhs } else { *rhs };
+		}
+		return Expr::Constant(zero);
+	}
+	Expr::Binary(op, lhs, rhs)
+}
+
+/// Evaluate numeric expressions: (2+3) -> 5, (6*2) -> 12, etc.
+pub fn apply_constant_folding(node: Expr) -> Expr {
+	match node {
+		Expr::Binary(op, lhs, rhs) => match (lhs.as_constant(), rhs.as_constant()) {
+			(Some(l), Some(r)) => Expr::Constant(apply_binary(op, l, r)),
+			_ => Expr::Binary(op, lhs, rhs),
+		},
+		node => node,
+	}
+}
+
+/// Regroup constants across nested applications of an associative operator.
+pub fn apply_associative_simplifications(node: Expr) -> Expr {
+	let Expr::Binary(op, lhs, rhs) = node else {
+		return node;
+	};
+	if !op.is_associative() {
+		return Expr::Binary(op, lhs, rhs);
+	}
+	let c = |e: &Expr| e.as_constant();
+	if let Expr::Binary(inner, a, b) = &*lhs {
+		if *inner == op {
+			if let (Some(c1), Some(c2)) = (c(b), c(&rhs)) {
+				// (A op const1) op const2 -> A op (const1 op const2)
+				return Expr::binary(op, (**a).clone(), Expr::Constant(apply_binary(op, c1, c2)));
+			}
+			if let (Some(c1), Some(c2)) = (c(a), c(&rhs)) {
+				// (const1 op A) op const2 -> A op (const1 op const2)
+				return Expr::binary(op, (**b).clone(), Expr::Constant(apply_binary(op, c1, c2)));
+			}
+		}
+	}
+	if let Expr::Binary(inner, a, b) = &*rhs {
+		if *inner == op {
+			if let (Some(c1), Some(c2)) = (c(&lhs), c(a)) {
+				// const1 op (const2 op A) -> (const1 op const2) op A
+				return Expr::binary(op, Expr::Constant(apply_binary(op, c1, c2)), (**b).clone());
+			}
+			if c(a).is_some() {
+				// A op (const1 op B) -> (A op const1) op B
+				return Expr::binary(op, Expr::binary(op, *lhs, (**a).clone()), (**b).clone());
+			}
+		}
+	}
+	Expr::Binary(op, lhs, rhs)
+}
+
+/// Put constants on the right for all commutative operators.
+pub fn apply_commutative_simplifications(node: Expr) -> Expr {
+	match node {
+		Expr::Binary(op, lhs, rhs)
+			if op.is_commutative()
+				&& lhs.as_constant().is_some()
+				&& rhs.as_constant().is_none() =>
+		{
+			Expr::Binary(op, rhs, lhs)
+		}
+		node => node,
+	}
+}
+
+// =============================================================================
+// Printing
+// =============================================================================
+
+/// Layout options for `smart_str_with`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PrintStyle {
+	/// Surround binary operators with spaces.
+	pub spaced: bool,
+	/// Print constants as float literals (`1.0` rather than `1`).
+	pub float_literals: bool,
+}
+
+/// Convert to a string with minimal parentheses based on precedence.
+pub fn smart_str(node: &Expr) -> String {
+	smart_str_with(node, PrintStyle::default())
+}
+
+pub fn smart_str_with(node: &Expr, style: PrintStyle) -> String {
+	let mut s = String::new();
+	write_smart(&mut s, node, None, style);
+	s
+}
+
+/// Print a node with minimal parentheses based on operator precedence.
+pub fn smart_print(node: &Expr) {
+	println!("{}", smart_str(node));
+}
+
+// `parent` is the enclosing operator and whether this node is its rhs. Unlike
+// the Python version the parentheses always preserve the tree's evaluation
+// order, so `a+(b-c)` and `-(a-b)` keep theirs.
+fn needs_parentheses(node: &Expr, parent: Option<(Operator, bool)>) -> bool {
+	let Some((parent, is_rhs)) = parent else {
+		return false;
+	};
+	match node {
+		Expr::Unary(..) => parent == Operator::Negate,
+		Expr::Binary(op, ..) => {
+			op.precedence() < parent.precedence()
+				|| (op.precedence() == parent.precedence() && is_rhs)
+		}
+		Expr::Constant(c) => *c < 0.0,
+		_ => false,
+	}
+}
+
+fn write_smart(s: &mut String, node: &Expr, parent: Option<(Operator, bool)>, style: PrintStyle) {
+	let parens = needs_parentheses(node, parent);
+	if parens {
+		s.push('(');
+	}
+	match node {
+		Expr::Constant(c) if style.float_literals => s.push_str(&format!("{c:?}")),
+		Expr::Unary(op, lhs) => {
+			s.push_str(op.symbol());
+			write_smart(s, lhs, Some((*op, false)), style);
+		}
+		Expr::Binary(op, lhs, rhs) => {
+			write_smart(s, lhs, Some((*op, false)), style);
+			if style.spaced {
+				s.push_str(&format!(" {} ", op.symbol()));
+			} else {
+				s.push_str(op.symbol());
+			}
+			write_smart(s, rhs, Some((*op, true)), style);
+		}
+		_ => s.push_str(&node.to_string()),
+	}
+	if parens {
+		s.push(')');
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn display_is_fully_parenthesized() {
+		let e =
+			Expr::element(0, 0) * Expr::element(1, 1) - Expr::element(0, 1) * Expr::element(1, 0);
+		assert_eq!(e.to_string(), "((m[0][0]*m[1][1])-(m[0][1]*m[1][0]))");
+		assert_eq!(Expr::flat_element(2, 3, 4).to_string(), "n[11]");
+	}
+
+	#[test]
+	fn simplify_identities() {
+		let x = Expr::symbol("x");
+		assert_eq!(simplify(&(x.clone() * Expr::from(1.0))), x);
+		assert_eq!(simplify(&(Expr::from(0.0) + x.clone())), x);
+		assert_eq!(
+			simplify(&(x.clone() * Expr::from(0.0))),
+			Expr::Constant(0.0)
+		);
+		// Subtract is not commutative, so only a zero on the right is removed.
+		let zero_minus_x = Expr::from(0.0) - x.clone();
+		assert_eq!(simplify(&zero_minus_x), zero_minus_x);
+		assert_eq!(simplify(&(x.clone() - Expr::from(0.0))), x);
+		assert_eq!(simplify(&(x.clone() / Expr::from(1.0))), x);
+		assert_eq!(simplify(&-(-x.clone())), x);
+		assert_eq!(
+			simplify(&(Expr::from(2.0) * Expr::from(3.0) + Expr::from(1.0))),
+			Expr::Constant(7.0)
+		);
+	}
+
+	#[test]
+	fn simplify_combines_constants() {
+		let (x, y) = (Expr::symbol("x"), Expr::symbol("y"));
+		let sum = Expr::from(1.0) + x.clone() + Expr::from(2.0) + y.clone();
+		assert_eq!(smart_str(&simplify(&sum)), "x+y+3");
+		let product = Expr::from(2.0) * x.clone() * Expr::from(3.0);
+		assert_eq!(smart_str(&simplify(&product)), "x*6");
+		let zero = x.clone() * Expr::from(2.0) * Expr::from(0.0);
+		assert_eq!(simplify(&zero), Expr::Constant(0.0));
+	}
+
+	#[test]
+	fn operator_properties() {
+		assert!(Operator::Add.is_associative() && Operator::Add.is_commutative());
+		assert!(!Operator::Subtract.is_commutative());
+		assert_eq!(Operator::Multiply.identity_element(), Some(1.0));
+		assert_eq!(Operator::Multiply.zero_element(), Some(0.0));
+		assert!(Operator::Add.is_addition_like() && !Operator::Multiply.is_addition_like());
+		assert!(!Operator::Divide.has_zero_element());
+	}
+
+	#[test]
+	fn smart_str_minimal_parentheses() {
+		let (a, b, c) = (
+			Expr::element(0, 0),
+			Expr::element(0, 1),
+			Expr::element(1, 0),
+		);
+		assert_eq!(
+			smart_str(&(a.clone() * b.clone() - c.clone())),
+			"m[0][0]*m[0][1]-m[1][0]"
+		);
+		assert_eq!(
+			smart_str(&(a.clone() * (b.clone() - c.clone()))),
+			"m[0][0]*(m[0][1]-m[1][0])"
+		);
+		assert_eq!(
+			smart_str(&(a.clone() - (b.clone() + c.clone()))),
+			"m[0][0]-(m[0][1]+m[1][0])"
+		);
+		assert_eq!(smart_str(&-(a.clone() - b.clone())), "-(m[0][0]-m[0][1])");
+		assert_eq!(smart_str(&(-a / Expr::from(2.0))), "-m[0][0]/2");
+	}
+}
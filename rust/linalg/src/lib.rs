@@ -0,0 +1,8 @@
+//! Symbolic linear algebra, ported from `python/generate_linalg.py`.
+//!
+//! `renderwindow` runs this from its build script to emit the closed-form
+//! `Mat4` determinant and inverse instead of carrying a hand-copied version.
+
+pub mod codegen;
+pub mod expr;
+pub mod matrix;
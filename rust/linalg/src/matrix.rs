@@ -0,0 +1,291 @@
+// =============================================================================
+// Matrix Functions
+// =============================================================================
+
+use std::fmt;
+
+use crate::expr::{simplify, smart_str, Expr, Operator};
+
+/// A (possibly symbolic) matrix stored as a list of rows.
+pub type Matrix = Vec<Vec<Expr>>;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum MatrixError {
+	Empty,
+	NotSquare,
+	NotInvertible,
+	IncompatibleDimensions {
+		lhs: (usize, usize),
+		rhs: (usize, usize),
+	},
+	DivideByZero,
+}
+
+impl fmt::Display for MatrixError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			MatrixError::Empty => write!(f, "Cannot multiply empty matrices."),
+			MatrixError::NotSquare => write!(f, "Matrix is not square."),
+			MatrixError::NotInvertible => {
+				write!(f, "Matrix is not invertible (determinant is zero).")
+			}
+			MatrixError::IncompatibleDimensions { lhs, rhs } => write!(
+				f,
+				"Matrix dimensions incompatible: {}x{} and {}x{}",
+				lhs.0, lhs.1, rhs.0, rhs.1
+			),
+			MatrixError::DivideByZero => write!(f, "Cannot divide by zero."),
+		}
+	}
+}
+
+impl std::error::Error for MatrixError {}
+
+/// Compute the cofactor matrix (minors with cofactor signs applied).
+pub fn mat_cofactor_matrix(m: &Matrix) -> Result<Matrix, MatrixError> {
+	let mut cofactors = Vec::with_capacity(m.len());
+	for i in 0..m.len() {
+		let mut row = Vec::with_capacity(m[i].len());
+		for j in 0..m[i].len() {
+			let minor_det = mat_determinant(&mat_minor(m, i, j))?;
+			// Apply cofactor sign: (-1)^(i+j)
+			row.push(if (i + j) % 2 == 1 {
+				-minor_det
+			} else {
+				minor_det
+			});
+		}
+		cofactors.push(row);
+	}
+	Ok(cofactors)
+}
+
+/// Apply cofactor signs to a matrix (multiply by (-1)^(i+j)).
+pub fn mat_cofactor_signs(m: &Matrix) -> Matrix {
+	mat_visit_index(m, |v, i, j| {
+		if (i + j) % 2 == 0 {
+			v.clone()
+		} else {
+			-v.clone()
+		}
+	})
+}
+
+/// Build a numeric matrix from rows of constants.
+pub fn mat_constant<R: AsRef<[f64]>>(rows: &[R]) -> Matrix {
+	rows.iter()
+		.map(|row| row.as_ref().iter().map(|&c| Expr::Constant(c)).collect())
+		.collect()
+}
+
+/// Compute the determinant by cofactor expansion along the first row.
+pub fn mat_determinant(m: &Matrix) -> Result<Expr, MatrixError> {
+	match mat_rank(m) {
+		None => Err(MatrixError::NotSquare),
+		Some(0) => Ok(Expr::Constant(1.0)),
+		Some(1) => Ok(m[0][0].clone()),
+		Some(2) => Ok(m[0][0].clone() * m[1][1].clone() - m[0][1].clone() * m[1][0].clone()),
+		Some(size) => {
+			let mut result = m[0][0].clone() * mat_determinant(&mat_minor(m, 0, 0))?;
+			for j in 1..size {
+				let term = m[0][j].clone() * mat_determinant(&mat_minor(m, 0, j))?;
+				let op = if j % 2 == 0 {
+					Operator::Add
+				} else {
+					Operator::Subtract
+				};
+				result = Expr::binary(op, result, term);
+			}
+			Ok(result)
+		}
+	}
+}
+
+pub fn mat_identity(size: usize) -> Matrix {
+	(0..size)
+		.map(|i| {
+			(0..size)
+				.map(|j| Expr::Constant(if i == j { 1.0 } else { 0.0 }))
+				.collect()
+		})
+		.collect()
+}
+
+/// Compute the inverse using the adjugate method: `A^-1 = adj(A) / det(A)`.
+pub fn mat_inverse(m: &Matrix) -> Result<Matrix, MatrixError> {
+	let det = mat_determinant(m)?;
+	if simplify(&det) == Expr::Constant(0.0) {
+		return Err(MatrixError::NotInvertible);
+	}
+	let adjugate = mat_transpose(&mat_cofactor_matrix(m)?);
+	Ok(mat_visit(&adjugate, |e| e.clone() / det.clone()))
+}
+
+/// Check whether two numeric matrices are approximately equal. Symbolic
+/// elements must match exactly.
+pub fn mat_is_equal(lhs: &Matrix, rhs: &Matrix, tolerance: f64) -> bool {
+	lhs.len() == rhs.len()
+		&& lhs.iter().zip(rhs).all(|(l, r)| {
+			l.len() == r.len()
+				&& l.iter()
+					.zip(r)
+					.all(|(a, b)| match (a.as_constant(), b.as_constant()) {
+						(Some(a), Some(b)) => {
+							(a - b).abs() <= tolerance.max(tolerance * a.abs().max(b.abs()))
+						}
+						_ => a == b,
+					})
+		})
+}
+
+/// Print each element on its own line.
+pub fn mat_lines(m: &Matrix) {
+	for e in m.iter().flatten() {
+		println!("{e}");
+	}
+}
+
+/// Compute the minor matrix by removing row `i` and column `j`.
+pub fn mat_minor(m: &Matrix, i: usize, j: usize) -> Matrix {
+	m.iter()
+		.enumerate()
+		.filter(|(r, _)| *r != i)
+		.map(|(_, row)| {
+			row.iter()
+				.enumerate()
+				.filter(|(c, _)| *c != j)
+				.map(|(_, e)| e.clone())
+				.collect()
+		})
+		.collect()
+}
+
+/// Multiply an `m x p` matrix by a `p x n` matrix.
+pub fn mat_multiply(m: &Matrix, n: &Matrix) -> Result<Matrix, MatrixError> {
+	if m.is_empty() || n.is_empty() {
+		return Err(MatrixError::Empty);
+	}
+	if m[0].len() != n.len() {
+		return Err(MatrixError::IncompatibleDimensions {
+			lhs: (m.len(), m[0].len()),
+			rhs: (n.len(), n[0].len()),
+		});
+	}
+	Ok(m.iter()
+		.map(|row| {
+			(0..n[0].len())
+				.map(|j| {
+					let mut terms = row.iter().zip(n).map(|(a, b)| a.clone() * b[j].clone());
+					let first = terms.next().unwrap();
+					terms.fold(first, |sum, term| sum + term)
+				})
+				.collect()
+		})
+		.collect())
+}
+
+pub fn mat_print(m: &Matrix) {
+	for row in m {
+		let row: Vec<String> = row.iter().map(Expr::to_string).collect();
+		println!("{}", row.join(" "));
+	}
+}
+
+/// Get the size of a square matrix, or `None` if it is not square.
+pub fn mat_rank(m: &Matrix) -> Option<usize> {
+	let size = m.len();
+	m.iter().all(|row| row.len() == size).then_some(size)
+}
+
+/// Divide each element of a matrix by a scalar.
+pub fn mat_scalar_divide(m: &Matrix, scalar: f64) -> Result<Matrix, MatrixError> {
+	if scalar == 0.0 {
+		return Err(MatrixError::DivideByZero);
+	}
+	Ok(mat_visit(m, |x| x.clone() / Expr::Constant(scalar)))
+}
+
+/// Multiply each element of a matrix by a scalar.
+pub fn mat_scalar_multiply(m: &Matrix, scalar: f64) -> Matrix {
+	mat_visit(m, |x| x.clone() * Expr::Constant(scalar))
+}
+
+/// Simplify every element of a matrix.
+pub fn mat_simplify(m: &Matrix) -> Matrix {
+	mat_visit(m, simplify)
+}
+
+/// Print a matrix using `smart_str` for each element.
+pub fn mat_smart_print(m: &Matrix) {
+	for row in m {
+		let row: Vec<String> = row.iter().map(smart_str).collect();
+		println!("{}", row.join(" "));
+	}
+}
+
+/// Create a symbolic matrix, using flat indexing (`n[k]`) when `flat` is set.
+pub fn mat_symbolic(size: usize, flat: bool) -> Matrix {
+	(0..size)
+		.map(|i| {
+			(0..size)
+				.map(|j| {
+					if flat {
+						Expr::flat_element(i, j, size)
+					} else {
+						Expr::element(i, j)
+					}
+				})
+				.collect()
+		})
+		.collect()
+}
+
+pub fn mat_transpose(m: &Matrix) -> Matrix {
+	let cols = m.first().map_or(0, Vec::len);
+	(0..cols)
+		.map(|j| m.iter().map(|row| row[j].clone()).collect())
+		.collect()
+}
+
+/// Apply a function to each element of a matrix.
+pub fn mat_visit(m: &Matrix, f: impl Fn(&Expr) -> Expr) -> Matrix {
+	m.iter().map(|row| row.iter().map(&f).collect()).collect()
+}
+
+/// Apply a function to each element of a matrix with its indices.
+pub fn mat_visit_index(m: &Matrix, f: impl Fn(&Expr, usize, usize) -> Expr) -> Matrix {
+	m.iter()
+		.enumerate()
+		.map(|(i, row)| row.iter().enumerate().map(|(j, e)| f(e, i, j)).collect())
+		.collect()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn determinant_2() {
+		let det = mat_determinant(&mat_symbolic(2, false)).unwrap();
+		assert_eq!(det.to_string(), "((m[0][0]*m[1][1])-(m[0][1]*m[1][0]))");
+	}
+
+	#[test]
+	fn minor() {
+		let minor = mat_minor(&mat_symbolic(3, false), 0, 1);
+		assert_eq!(minor[0][0], Expr::element(1, 0));
+		assert_eq!(minor[1][1], Expr::element(2, 2));
+	}
+
+	#[test]
+	fn not_square() {
+		let m = vec![vec![Expr::from(1.0), Expr::from(2.0)]];
+		assert_eq!(mat_determinant(&m), Err(MatrixError::NotSquare));
+	}
+
+	#[test]
+	fn singular() {
+		let m = mat_visit(&mat_symbolic(2, false), |_| Expr::from(1.0));
+		assert_eq!(mat_inverse(&m), Err(MatrixError::NotInvertible));
+	}
+}
@@ -0,0 +1,185 @@
+// The test suite from python/generate_linalg.py, run against the Rust port.
+
+use renderwindow_linalg::expr::{simplify, Expr};
+use renderwindow_linalg::matrix::*;
+
+fn constant(e: &Expr) -> f64 {
+	simplify(e)
+		.as_constant()
+		.expect("expression did not fold to a constant")
+}
+
+fn det(m: &Matrix) -> f64 {
+	constant(&mat_determinant(m).unwrap())
+}
+
+fn add(a: &Matrix, b: &Matrix) -> Matrix {
+	mat_visit_index(a, |v, i, j| v.clone() + b[i][j].clone())
+}
+
+#[test]
+fn test_mat_determinant_of_identity_is_one() {
+	for i in 2..6 {
+		assert_eq!(det(&mat_identity(i)), 1.0);
+	}
+}
+
+#[test]
+fn test_mat_determinant_multiplication() {
+	// det(A x B) = det(A) x det(B)
+	let a = mat_constant(&[[1.0, 2.0], [3.0, 4.0]]);
+	let b = mat_constant(&[[5.0, 6.0], [7.0, 8.0]]);
+	let ab = mat_multiply(&a, &b).unwrap();
+	assert!((det(&ab) - det(&a) * det(&b)).abs() < 1e-10);
+}
+
+#[test]
+fn test_mat_determinant_properties() {
+	let a = mat_constant(&[[1.0, 2.0], [3.0, 4.0]]);
+	// det(kA) = k^n * det(A) where n is the size of the matrix
+	let k = 3.0;
+	assert!((det(&mat_scalar_multiply(&a, k)) - det(&a) * k * k).abs() < 1e-10);
+	// det(A^T) = det(A)
+	assert!((det(&mat_transpose(&a)) - det(&a)).abs() < 1e-10);
+}
+
+#[test]
+fn test_mat_identity_n() {
+	for i in 2..6 {
+		let m = mat_identity(i);
+		assert_eq!(mat_rank(&m), Some(i));
+		assert_eq!(m[i - 1][i - 1], Expr::Constant(1.0));
+		assert_eq!(m[0][i - 1], Expr::Constant(0.0));
+	}
+}
+
+#[test]
+fn test_mat_inverse_n() {
+	// The inverse of identity is still identity.
+	for i in 2..6 {
+		let identity = mat_identity(i);
+		let inv = mat_simplify(&mat_inverse(&identity).unwrap());
+		assert!(mat_is_equal(&identity, &inv, 1e-10));
+	}
+}
+
+#[test]
+fn test_mat_inverse_properties() {
+	// (A x B)^-1 = B^-1 x A^-1 and (A^-1)^-1 = A
+	let a = mat_constant(&[[4.0, 7.0], [2.0, 6.0]]);
+	let b = mat_constant(&[[3.0, 1.0], [1.0, 2.0]]);
+	let ab_inverse = mat_simplify(&mat_inverse(&mat_multiply(&a, &b).unwrap()).unwrap());
+	let a_inverse = mat_inverse(&a).unwrap();
+	let b_inverse = mat_inverse(&b).unwrap();
+	let b_inverse_a_inverse = mat_simplify(&mat_multiply(&b_inverse, &a_inverse).unwrap());
+	assert!(mat_is_equal(&ab_inverse, &b_inverse_a_inverse, 1e-10));
+	let a_inverse_inverse = mat_simplify(&mat_inverse(&a_inverse).unwrap());
+	assert!(mat_is_equal(&a_inverse_inverse, &a, 1e-10));
+}
+
+#[test]
+fn test_mat_inverse_scale() {
+	let mut m = mat_identity(4);
+	m[1][1] = Expr::Constant(10.0);
+	let inv = mat_simplify(&mat_inverse(&m).unwrap());
+	assert_eq!(constant(&inv[0][0]), 1.0);
+	assert!((constant(&inv[1][1]) - 0.1).abs() < 1e-10);
+	assert_eq!(constant(&inv[2][2]), 1.0);
+	assert_eq!(constant(&inv[3][3]), 1.0);
+}
+
+#[test]
+fn test_mat_inverse_identity() {
+	// A matrix times its inverse equals identity.
+	let mut m = mat_identity(4);
+	m[1][1] = Expr::Constant(10.0);
+	let inv = mat_simplify(&mat_inverse(&m).unwrap());
+	let result = mat_simplify(&mat_multiply(&m, &inv).unwrap());
+	assert!(mat_is_equal(&result, &mat_identity(4), 1e-10));
+}
+
+#[test]
+fn test_mat_minor() {
+	let minor = mat_minor(&mat_identity(3), 0, 1);
+	assert!(mat_is_equal(
+		&minor,
+		&mat_constant(&[[0.0, 0.0], [0.0, 1.0]]),
+		0.0
+	));
+}
+
+#[test]
+fn test_mat_multiply() {
+	let symbolic = mat_symbolic(3, false);
+	let product = mat_simplify(&mat_multiply(&mat_identity(3), &symbolic).unwrap());
+	assert_eq!(product, symbolic);
+}
+
+#[test]
+fn test_mat_multiply_associativity() {
+	// (A x B) x C = A x (B x C)
+	let a = mat_constant(&[[1.0, 2.0], [3.0, 4.0]]);
+	let b = mat_constant(&[[5.0, 6.0], [7.0, 8.0]]);
+	let c = mat_constant(&[[9.0, 10.0], [11.0, 12.0]]);
+	let left = mat_multiply(&mat_multiply(&a, &b).unwrap(), &c).unwrap();
+	let right = mat_multiply(&a, &mat_multiply(&b, &c).unwrap()).unwrap();
+	assert!(mat_is_equal(
+		&mat_simplify(&left),
+		&mat_simplify(&right),
+		1e-10
+	));
+}
+
+#[test]
+fn test_mat_multiply_distributivity() {
+	// A x (B + C) = A x B + A x C
+	let a = mat_constant(&[[1.0, 2.0], [3.0, 4.0]]);
+	let b = mat_constant(&[[5.0, 6.0], [7.0, 8.0]]);
+	let c = mat_constant(&[[9.0, 10.0], [11.0, 12.0]]);
+	let left = mat_multiply(&a, &add(&b, &c)).unwrap();
+	let right = add(
+		&mat_multiply(&a, &b).unwrap(),
+		&mat_multiply(&a, &c).unwrap(),
+	);
+	assert!(mat_is_equal(
+		&mat_simplify(&left),
+		&mat_simplify(&right),
+		1e-10
+	));
+}
+
+#[test]
+fn test_mat_multiply_identity_left_and_right() {
+	let matrices = [
+		mat_constant(&[[1.0, 2.0], [3.0, 4.0]]),
+		mat_constant(&[[1.0, 2.0, 3.0], [4.0, 5.0, 6.0]]),
+		mat_constant(&[[1.0], [2.0], [3.0]]),
+	];
+	for m in &matrices {
+		let left = mat_multiply(&mat_identity(m.len()), m).unwrap();
+		assert!(mat_is_equal(&mat_simplify(&left), m, 1e-10));
+		let right = mat_multiply(m, &mat_identity(m[0].len())).unwrap();
+		assert!(mat_is_equal(&mat_simplify(&right), m, 1e-10));
+	}
+}
+
+#[test]
+fn test_mat_multiply_non_commutativity() {
+	let a = mat_constant(&[[1.0, 2.0], [3.0, 4.0]]);
+	let b = mat_constant(&[[5.0, 6.0], [7.0, 8.0]]);
+	let ab = mat_simplify(&mat_multiply(&a, &b).unwrap());
+	let ba = mat_simplify(&mat_multiply(&b, &a).unwrap());
+	assert!(!mat_is_equal(&ab, &ba, 1e-10));
+}
+
+#[test]
+fn test_mat_multiply_incompatible() {
+	let a = mat_constant(&[[1.0, 2.0, 3.0]]);
+	assert_eq!(
+		mat_multiply(&a, &a),
+		Err(MatrixError::IncompatibleDimensions {
+			lhs: (1, 3),
+			rhs: (1, 3)
+		})
+	);
+}
@@ -0,0 +1,17 @@
+// Generate the closed-form Mat4 determinant and inverse.
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+use renderwindow_linalg::codegen::{emit_determinant, emit_inverse};
+
+fn main() {
+	let mut code =
+		String::from("// @generated by build.rs using renderwindow-linalg. Do not edit.\n\n");
+	code += &emit_determinant("mat4_determinant", 4).unwrap();
+	code += "\n";
+	code += &emit_inverse("mat4_inverse", 4).unwrap();
+	let out = PathBuf::from(env::var_os("OUT_DIR").unwrap()).join("linalg.rs");
+	fs::write(out, code).unwrap();
+	println!("cargo:rerun-if-changed=build.rs");
+}
@@ -0,0 +1,29 @@
+//! Render one frame of the demo scene without a window.
+//!
+//! `cargo run --example headless -- [frame] [size] [out_dir]` writes
+//! `frame_NNNN.png`, `frame_NNNN.ppm` and `frame_NNNN_depth.pgm`.
+
+use std::env;
+use std::path::PathBuf;
+
+use renderwindow::image::{save_depth_pgm, save_png, save_ppm};
+use renderwindow::scene::{render_demo_frame, Scene};
+
+fn main() -> std::io::Result<()> {
+	let args: Vec<String> = env::args().skip(1).collect();
+	let frame = args
+		.first()
+		.map_or(0, |a| a.parse().expect("frame must be a number"));
+	let size = args
+		.get(1)
+		.map_or(512, |a| a.parse().expect("size must be a number"));
+	let dir = PathBuf::from(args.get(2).map_or(".", String::as_str));
+
+	let fb = render_demo_frame(&Scene::demo(), size, size, frame);
+	let stem = format!("frame_{frame:04}");
+	save_png(&fb, dir.join(format!("{stem}.png")))?;
+	save_ppm(&fb, dir.join(format!("{stem}.ppm")))?;
+	save_depth_pgm(&fb, dir.join(format!("{stem}_depth.pgm")))?;
+	println!("Wrote {stem} ({size}x{size}) to {}", dir.display());
+	Ok(())
+}
@@ -0,0 +1,40 @@
+//! Precompute a relaxed cone map for `ShaderFragmentRelaxedCone`.
+//!
+//! `cargo run --release --bin conemap -- [height.pgm|height.ppm] [out.png]`
+//! reads the red (or gray) channel as depth and writes the RGBA cone map as
+//! PNG. Without an input it uses the demo height canvas.
+
+use std::env;
+use std::time::Instant;
+
+use renderwindow::image::{encode_png, load_pnm};
+use renderwindow::relief::relaxed_cone_map;
+use renderwindow::scene::demo_height;
+use renderwindow::texture::Texture2D;
+
+fn main() -> std::io::Result<()> {
+	let args: Vec<String> = env::args().skip(1).collect();
+	let height = match args.first() {
+		Some(path) => {
+			let pnm = load_pnm(path)?;
+			let rgba: Vec<u8> = pnm
+				.data
+				.chunks(pnm.channels as usize)
+				.flat_map(|c| [c[0], c[0], c[0], 255])
+				.collect();
+			Texture2D::from_rgba8(pnm.width, pnm.height, &rgba)
+		}
+		None => demo_height().to_texture(),
+	};
+	let out = args.get(1).map_or("conemap.png", String::as_str);
+
+	let start = Instant::now();
+	let cone_map = relaxed_cone_map(&height);
+	let (width, rows) = (cone_map.width(), cone_map.height());
+	std::fs::write(out, encode_png(width, rows, &cone_map.to_rgba8()))?;
+	println!(
+		"Wrote {out} ({width}x{rows}) in {:.2}s",
+		start.elapsed().as_secs_f32()
+	);
+	Ok(())
+}
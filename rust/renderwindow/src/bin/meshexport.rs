@@ -0,0 +1,63 @@
+//! Write generated geometry as Wavefront OBJ, glTF, PLY or STL for viewing in
+//! other tools.
+//!
+//! `cargo run --release --bin meshexport -- [plane|sphere|torus|demo] [out]`
+//! tessellates the shape as the demo scene does; `demo` is the whole scene and
+//! needs a `.gltf` or `.glb` output. The output extension picks the format.
+//! Without arguments it writes the torus to `torus.obj`.
+
+use std::env;
+use std::io;
+use std::path::Path;
+use std::sync::Arc;
+
+use renderwindow::gltf::save_gltf;
+use renderwindow::math::Mat4;
+use renderwindow::mesh::obj::{save_obj, Obj};
+use renderwindow::mesh::ply::{save_ply, Ply, PlyFormat};
+use renderwindow::mesh::stl::{save_stl, StlFormat};
+use renderwindow::parametric::{create_parametric, Plane, Sphere, Torus};
+use renderwindow::scene::Scene;
+
+fn main() -> io::Result<()> {
+	let args: Vec<String> = env::args().skip(1).collect();
+	let shape = args.first().map_or("torus", String::as_str);
+	let default = if shape == "demo" {
+		"demo.glb".to_string()
+	} else {
+		format!("{shape}.obj")
+	};
+	let out = args.get(1).unwrap_or(&default);
+	let extension = Path::new(out)
+		.extension()
+		.map(|e| e.to_string_lossy().to_ascii_lowercase());
+	let gltf = matches!(extension.as_deref(), Some("gltf" | "glb"));
+	let unknown = |message: String| io::Error::new(io::ErrorKind::InvalidInput, message);
+
+	let mesh = match shape {
+		"plane" => create_parametric(20, 20, &Plane),
+		"sphere" => create_parametric(20, 20, &Sphere),
+		"torus" => create_parametric(50, 50, &Torus::new(10.0, 1.0)),
+		"demo" if gltf => {
+			let scene = Scene::demo();
+			save_gltf(&scene, out)?;
+			println!("Wrote {out} ({} instances)", scene.instances.len());
+			return Ok(());
+		}
+		"demo" => return Err(unknown("The demo scene needs a .gltf or .glb file.".into())),
+		_ => return Err(unknown(format!("Unknown shape {shape:?}."))),
+	};
+	let (vertices, triangles) = (mesh.vertex_count(), mesh.triangle_count());
+	match extension.as_deref() {
+		_ if gltf => {
+			let mut scene = Scene::default();
+			scene.add(&Arc::new(mesh), Mat4::IDENTITY);
+			save_gltf(&scene, out)?;
+		}
+		Some("ply") => save_ply(&Ply::new(mesh), out, PlyFormat::BinaryLittleEndian)?,
+		Some("stl") => save_stl(&mesh, out, StlFormat::Binary)?,
+		_ => save_obj(&Obj::new(mesh), out)?,
+	}
+	println!("Wrote {out} ({vertices} vertices, {triangles} triangles)");
+	Ok(())
+}
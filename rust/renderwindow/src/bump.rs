@@ -0,0 +1,255 @@
+////////////////////////////////////////////////////////////////////////////////
+// Bump Mapping
+////////////////////////////////////////////////////////////////////////////////
+//
+// Tangent-space normal maps derived from height maps, and lit programs that
+// read them through the screen-space TBN frame of the WebGL shaders. Tangent
+// space follows `derivative_tangent_frame`: x along +s, y along +t (down the
+// texture rows) and z out of the surface.
+
+use crate::math::{Vec3, Vec4};
+use crate::shader::{derivative_tangent_frame, Fragment, FragmentShader, StandardVaryings};
+use crate::texture::{Sampler2D, Texture2D, Wrap};
+use crate::uniform::Uniforms;
+
+/// Finite-difference kernel for the height gradient.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Gradient {
+	/// 3x3 Sobel, normalized to the slope of a linear ramp.
+	#[default]
+	Sobel,
+	CentralDifference,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct NormalMapOptions {
+	pub gradient: Gradient,
+	/// Height units per texel of slope; negative for depth maps such as the
+	/// relief programs read.
+	pub strength: f32,
+	/// How neighbours past the edge are fetched; `Repeat` for tiling textures.
+	pub wrap_s: Wrap,
+	pub wrap_t: Wrap,
+}
+
+impl Default for NormalMapOptions {
+	fn default() -> Self {
+		Self {
+			gradient: Gradient::Sobel,
+			strength: 1.0,
+			wrap_s: Wrap::Repeat,
+			wrap_t: Wrap::Repeat,
+		}
+	}
+}
+
+/// Normal map of the red channel of `height`'s level 0, encoded as
+/// `n * 0.5 + 0.5` in RGB with alpha 1.
+pub fn normal_map(height: &Texture2D, options: &NormalMapOptions) -> Texture2D {
+	let (width, rows) = (height.width(), height.height());
+	let h = |x: i64, y: i64| {
+		let x = options.wrap_s.apply(x, width);
+		let y = options.wrap_t.apply(y, rows);
+		height.texel(0, x, y).x
+	};
+	let mut texels = Vec::with_capacity((width * rows) as usize);
+	for y in 0..rows as i64 {
+		for x in 0..width as i64 {
+			let (dx, dy) = match options.gradient {
+				Gradient::Sobel => {
+					let column = |x| h(x, y - 1) + 2.0 * h(x, y) + h(x, y + 1);
+					let row = |y| h(x - 1, y) + 2.0 * h(x, y) + h(x + 1, y);
+					(
+						(column(x + 1) - column(x - 1)) / 8.0,
+						(row(y + 1) - row(y - 1)) / 8.0,
+					)
+				}
+				Gradient::CentralDifference => (
+					(h(x + 1, y) - h(x - 1, y)) / 2.0,
+					(h(x, y + 1) - h(x, y - 1)) / 2.0,
+				),
+			};
+			let s = options.strength;
+			let n = Vec3::new(-dx * s, -dy * s, 1.0).normalize();
+			texels.push((n * 0.5 + Vec3::new(0.5, 0.5, 0.5)).extend(1.0));
+		}
+	}
+	Texture2D::new(width, rows, texels)
+}
+
+// `glShaderFragmentLit` lighting: a fixed overhead light with a floor of 0.25.
+fn illumination(normal: Vec3) -> f32 {
+	let dot_l = normal.dot(Vec3::new(0.0, 1.0, 0.0)).clamp(0.0, 1.0);
+	dot_l.clamp(0.25, 1.0)
+}
+
+/// `glShaderFragmentLit`: `Albedo` lit with the interpolated normal.
+#[derive(Clone, Debug)]
+pub struct ShaderFragmentLit {
+	pub albedo: Sampler2D,
+}
+
+impl FragmentShader<StandardVaryings> for ShaderFragmentLit {
+	fn fragment(&self, _: &Uniforms, f: &Fragment<StandardVaryings>) -> Option<Vec4> {
+		let albedo = self.albedo.sample(f, |v| v.st0).xyz();
+		Some((albedo * illumination(f.varyings.nor)).extend(1.0))
+	}
+}
+
+/// [`ShaderFragmentLit`] with the normal taken from a tangent-space
+/// `normal_map` such as [`normal_map`] makes.
+#[derive(Clone, Debug)]
+pub struct ShaderFragmentLitNormalMap {
+	pub albedo: Sampler2D,
+	pub normal_map: Sampler2D,
+}
+
+impl FragmentShader<StandardVaryings> for ShaderFragmentLitNormalMap {
+	fn fragment(&self, _: &Uniforms, f: &Fragment<StandardVaryings>) -> Option<Vec4> {
+		let (tangent, bitangent) = derivative_tangent_frame(f);
+		let n = self.normal_map.sample(f, |v| v.st0).xyz() * 2.0 - Vec3::new(1.0, 1.0, 1.0);
+		let normal =
+			(tangent * n.x + bitangent * n.y + f.varyings.nor.normalize() * n.z).normalize();
+		let albedo = self.albedo.sample(f, |v| v.st0).xyz();
+		Some((albedo * illumination(normal)).extend(1.0))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::sync::Arc;
+
+	use crate::math::Vec2;
+	use crate::mesh::MeshGen;
+	use crate::raster::{draw, Framebuffer};
+	use crate::shader::ShaderVertex;
+
+	fn heights(width: u32, h: impl Fn(u32, u32) -> f32) -> Texture2D {
+		let texels = (0..width * width)
+			.map(|i| {
+				let v = h(i % width, i / width);
+				Vec4::new(v, v, v, 1.0)
+			})
+			.collect();
+		Texture2D::new(width, width, texels)
+	}
+
+	fn decode(t: &Texture2D, x: u32, y: u32) -> Vec3 {
+		t.texel(0, x, y).xyz() * 2.0 - Vec3::new(1.0, 1.0, 1.0)
+	}
+
+	fn assert_near(a: Vec3, b: Vec3) {
+		assert!((a - b).length() < 1e-5, "{a:?} != {b:?}");
+	}
+
+	#[test]
+	fn flat_is_straight_up() {
+		let map = normal_map(&heights(4, |_, _| 0.3), &NormalMapOptions::default());
+		assert!(map
+			.level(0)
+			.texels
+			.iter()
+			.all(|&t| t == Vec4::new(0.5, 0.5, 1.0, 1.0)));
+	}
+
+	#[test]
+	fn ramp_slopes_away_from_uphill() {
+		// Rising by 0.25 per texel along s: both kernels give slope 0.25.
+		let ramp = heights(8, |x, _| x as f32 * 0.25);
+		for gradient in [Gradient::Sobel, Gradient::CentralDifference] {
+			let options = NormalMapOptions {
+				gradient,
+				strength: 2.0,
+				..NormalMapOptions::default()
+			};
+			let map = normal_map(&ramp, &options);
+			assert_near(decode(&map, 3, 5), Vec3::new(-0.5, 0.0, 1.0).normalize());
+			// Repeating wraps the 1.75 -> 0 drop into the edge texels.
+			assert!(decode(&map, 0, 0).x > 0.0 && decode(&map, 7, 0).x > 0.0);
+			let clamped = normal_map(
+				&ramp,
+				&NormalMapOptions {
+					wrap_s: Wrap::ClampToEdge,
+					..options
+				},
+			);
+			// One-sided at the edge: half the slope.
+			assert_near(
+				decode(&clamped, 0, 0),
+				Vec3::new(-0.25, 0.0, 1.0).normalize(),
+			);
+		}
+		// Rows run along +t, so heights rising down the texture tilt towards -y.
+		let rows = normal_map(
+			&heights(8, |_, y| y as f32 * 0.25),
+			&NormalMapOptions::default(),
+		);
+		assert_near(decode(&rows, 3, 3), Vec3::new(0.0, -0.25, 1.0).normalize());
+	}
+
+	// The z = 0 quad of the relief tests, with st0 over [0, 1] and the normal
+	// facing the light.
+	fn render(fs: &impl FragmentShader<StandardVaryings>) -> Framebuffer {
+		let mesh = MeshGen {
+			vtx: vec![
+				Vec3::new(-1.0, -1.0, 0.0),
+				Vec3::new(1.0, -1.0, 0.0),
+				Vec3::new(1.0, 1.0, 0.0),
+				Vec3::new(-1.0, 1.0, 0.0),
+			],
+			nor: vec![Vec3::new(0.0, 1.0, 0.0); 4],
+			st0: vec![
+				Vec2::new(0.0, 0.0),
+				Vec2::new(1.0, 0.0),
+				Vec2::new(1.0, 1.0),
+				Vec2::new(0.0, 1.0),
+			],
+			tan: Vec::new(),
+			idx: vec![0, 1, 2, 2, 3, 0],
+		};
+		let mut fb = Framebuffer::new(8, 8);
+		let vp = fb.viewport();
+		draw(&mut fb, &vp, &mesh, &Uniforms::default(), &ShaderVertex, fs);
+		fb
+	}
+
+	#[test]
+	fn lit_programs() {
+		let white = Sampler2D::new(Arc::new(heights(1, |_, _| 1.0)));
+		let lit = render(&ShaderFragmentLit {
+			albedo: white.clone(),
+		});
+		assert_eq!(lit.color(4, 4), Vec4::new(1.0, 1.0, 1.0, 1.0));
+		// A flat normal map leaves the lighting alone.
+		let flat = Sampler2D::new(Arc::new(normal_map(
+			&heights(4, |_, _| 0.0),
+			&NormalMapOptions::default(),
+		)));
+		let mapped = render(&ShaderFragmentLitNormalMap {
+			albedo: white.clone(),
+			normal_map: flat,
+		});
+		assert_eq!(mapped.color(4, 4), lit.color(4, 4));
+		// Tilting the normal 45 degrees towards -s turns it off the light.
+		let tilted = Texture2D::new(
+			4,
+			4,
+			vec![
+				(Vec3::new(-1.0, 0.0, 1.0).normalize() * 0.5 + Vec3::new(0.5, 0.5, 0.5))
+					.extend(1.0);
+				16
+			],
+		);
+		let mapped = render(&ShaderFragmentLitNormalMap {
+			albedo: white,
+			normal_map: Sampler2D::new(Arc::new(tilted)),
+		});
+		let expected = std::f32::consts::FRAC_1_SQRT_2;
+		assert!(
+			(mapped.color(4, 4).x - expected).abs() < 1e-5,
+			"{:?}",
+			mapped.color(4, 4)
+		);
+	}
+}
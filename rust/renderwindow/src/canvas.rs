@@ -0,0 +1,742 @@
+////////////////////////////////////////////////////////////////////////////////
+// 2D Canvas
+////////////////////////////////////////////////////////////////////////////////
+//
+// The part of the HTML5 canvas 2D context that `module_main.js` uses to draw
+// its textures. Paths are filled with the nonzero rule from 4x4 samples per
+// pixel; a stroke is the union of one quad per segment, with butt caps and no
+// joins. Text uses a built-in 5x7 bitmap font scaled to the font size.
+
+use std::f32::consts::PI;
+
+use crate::math::{Vec2, Vec4};
+use crate::texture::Texture2D;
+
+/// Parse `#rgb`, `#rrggbb` or one of the basic CSS color keywords.
+pub fn css_color(s: &str) -> Option<Vec4> {
+	let rgb = |r: u32, g: u32, b: u32| Some(Vec4::new(r as f32, g as f32, b as f32, 255.0) / 255.0);
+	let hex = |s: &str| u32::from_str_radix(s, 16).ok();
+	match s.trim().to_ascii_lowercase().as_str() {
+		"black" => rgb(0, 0, 0),
+		"white" => rgb(255, 255, 255),
+		"red" => rgb(255, 0, 0),
+		"green" => rgb(0, 128, 0),
+		"lime" => rgb(0, 255, 0),
+		"blue" => rgb(0, 0, 255),
+		"yellow" => rgb(255, 255, 0),
+		"cyan" | "aqua" => rgb(0, 255, 255),
+		"magenta" | "fuchsia" => rgb(255, 0, 255),
+		"gray" | "grey" => rgb(128, 128, 128),
+		"transparent" => Some(Vec4::default()),
+		s if s.starts_with('#') && s.len() == 4 => {
+			let c = hex(&s[1..])?;
+			rgb(((c >> 8) & 15) * 17, ((c >> 4) & 15) * 17, (c & 15) * 17)
+		}
+		s if s.starts_with('#') && s.len() == 7 => {
+			let c = hex(&s[1..])?;
+			rgb((c >> 16) & 255, (c >> 8) & 255, c & 255)
+		}
+		_ => None,
+	}
+}
+
+fn premultiply(c: Vec4) -> Vec4 {
+	Vec4::new(c.x * c.w, c.y * c.w, c.z * c.w, c.w)
+}
+
+/// `createRadialGradient(x0, y0, r0, x1, y1, r1)` with pad spreading.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RadialGradient {
+	c0: Vec2,
+	r0: f32,
+	c1: Vec2,
+	r1: f32,
+	stops: Vec<(f32, Vec4)>,
+}
+
+impl RadialGradient {
+	pub fn new(x0: f32, y0: f32, r0: f32, x1: f32, y1: f32, r1: f32) -> Self {
+		Self {
+			c0: Vec2::new(x0, y0),
+			r0,
+			c1: Vec2::new(x1, y1),
+			r1,
+			stops: Vec::new(),
+		}
+	}
+
+	/// `addColorStop`; stops at equal offsets keep their insertion order.
+	pub fn add_color_stop(&mut self, offset: f32, color: Vec4) {
+		let offset = offset.clamp(0.0, 1.0);
+		let at = self.stops.partition_point(|&(o, _)| o <= offset);
+		self.stops.insert(at, (offset, color));
+	}
+
+	// Premultiplied color at `p`, following the canvas specification: the
+	// largest ω whose circle passes through `p` with a non-negative radius.
+	fn color_at(&self, p: Vec2) -> Vec4 {
+		let (cd, pd, dr) = (self.c1 - self.c0, p - self.c0, self.r1 - self.r0);
+		let a = cd.dot(cd) - dr * dr;
+		let b = pd.dot(cd) + self.r0 * dr;
+		let c = pd.dot(pd) - self.r0 * self.r0;
+		let radius = |w: f32| self.r0 + w * dr;
+		let omega = if a == 0.0 {
+			Some(c / (2.0 * b)).filter(|&w| b != 0.0 && radius(w) >= 0.0)
+		} else {
+			let disc = b * b - a * c;
+			if disc < 0.0 {
+				None
+			} else {
+				let s = disc.sqrt();
+				let (w0, w1) = ((b + s) / a, (b - s) / a);
+				[w0.max(w1), w0.min(w1)]
+					.into_iter()
+					.find(|&w| radius(w) >= 0.0)
+			}
+		};
+		match omega {
+			Some(w) => self.stop_color(w),
+			None => Vec4::default(),
+		}
+	}
+
+	fn stop_color(&self, t: f32) -> Vec4 {
+		let (Some(first), Some(last)) = (self.stops.first(), self.stops.last()) else {
+			return Vec4::default();
+		};
+		if t <= first.0 {
+			return premultiply(first.1);
+		}
+		if t >= last.0 {
+			return premultiply(last.1);
+		}
+		let i = self.stops.partition_point(|&(o, _)| o <= t);
+		let ((o0, c0), (o1, c1)) = (self.stops[i - 1], self.stops[i]);
+		let f = (t - o0) / (o1 - o0);
+		premultiply(c0) * (1.0 - f) + premultiply(c1) * f
+	}
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Paint {
+	Color(Vec4),
+	RadialGradient(RadialGradient),
+}
+
+impl Paint {
+	fn at(&self, p: Vec2) -> Vec4 {
+		match self {
+			Paint::Color(c) => premultiply(*c),
+			Paint::RadialGradient(g) => g.color_at(p),
+		}
+	}
+}
+
+impl From<Vec4> for Paint {
+	fn from(c: Vec4) -> Self {
+		Paint::Color(c)
+	}
+}
+
+impl From<RadialGradient> for Paint {
+	fn from(g: RadialGradient) -> Self {
+		Paint::RadialGradient(g)
+	}
+}
+
+/// Font size in pixels and weight; the family is always the built-in font.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Font {
+	pub size: f32,
+	pub bold: bool,
+}
+
+impl Font {
+	/// Read the size and weight from a CSS font such as `"bold 48px serif"`.
+	pub fn parse(css: &str) -> Option<Font> {
+		let mut font = Font {
+			size: 0.0,
+			bold: false,
+		};
+		for word in css.split_whitespace() {
+			if word == "bold" {
+				font.bold = true;
+			} else if let Some(px) = word.strip_suffix("px") {
+				font.size = px.parse().ok()?;
+			}
+		}
+		(font.size > 0.0).then_some(font)
+	}
+}
+
+impl Default for Font {
+	/// `10px sans-serif`
+	fn default() -> Self {
+		Font {
+			size: 10.0,
+			bold: false,
+		}
+	}
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TextAlign {
+	#[default]
+	Left,
+	Center,
+	Right,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TextBaseline {
+	Top,
+	Middle,
+	#[default]
+	Alphabetic,
+	Bottom,
+}
+
+#[derive(Clone, Debug, Default)]
+struct Subpath {
+	points: Vec<Vec2>,
+	closed: bool,
+}
+
+/// An RGBA drawing surface with canvas-style state. Pixels are stored
+/// premultiplied, top row first.
+#[derive(Clone, Debug)]
+pub struct Canvas {
+	width: u32,
+	height: u32,
+	pixels: Vec<Vec4>,
+	path: Vec<Subpath>,
+	pub fill_style: Paint,
+	pub stroke_style: Paint,
+	pub line_width: f32,
+	pub font: Font,
+	pub text_align: TextAlign,
+	pub text_baseline: TextBaseline,
+}
+
+impl Canvas {
+	/// A transparent black canvas with the default context state.
+	pub fn new(width: u32, height: u32) -> Self {
+		let black = Paint::Color(Vec4::new(0.0, 0.0, 0.0, 1.0));
+		Self {
+			width,
+			height,
+			pixels: vec![Vec4::default(); (width * height) as usize],
+			path: Vec::new(),
+			fill_style: black.clone(),
+			stroke_style: black,
+			line_width: 1.0,
+			font: Font::default(),
+			text_align: TextAlign::default(),
+			text_baseline: TextBaseline::default(),
+		}
+	}
+
+	pub fn width(&self) -> u32 {
+		self.width
+	}
+
+	pub fn height(&self) -> u32 {
+		self.height
+	}
+
+	/// Set `fill_style` from a CSS color; unknown colors are ignored as in JS.
+	pub fn set_fill_color(&mut self, css: &str) {
+		if let Some(c) = css_color(css) {
+			self.fill_style = Paint::Color(c);
+		}
+	}
+
+	/// Set `stroke_style` from a CSS color; unknown colors are ignored as in JS.
+	pub fn set_stroke_color(&mut self, css: &str) {
+		if let Some(c) = css_color(css) {
+			self.stroke_style = Paint::Color(c);
+		}
+	}
+
+	pub fn fill_rect(&mut self, x: f32, y: f32, w: f32, h: f32) {
+		let rect = vec![
+			Vec2::new(x, y),
+			Vec2::new(x + w, y),
+			Vec2::new(x + w, y + h),
+			Vec2::new(x, y + h),
+		];
+		let paint = self.fill_style.clone();
+		self.fill_polygons(&[rect], &paint);
+	}
+
+	pub fn begin_path(&mut self) {
+		self.path.clear();
+	}
+
+	pub fn move_to(&mut self, x: f32, y: f32) {
+		self.path.push(Subpath {
+			points: vec![Vec2::new(x, y)],
+			closed: false,
+		});
+	}
+
+	pub fn line_to(&mut self, x: f32, y: f32) {
+		match self.path.last_mut() {
+			Some(subpath) if !subpath.closed => subpath.points.push(Vec2::new(x, y)),
+			_ => self.move_to(x, y),
+		}
+	}
+
+	pub fn close_path(&mut self) {
+		if let Some(subpath) = self.path.last_mut() {
+			subpath.closed = true;
+			let start = subpath.points[0];
+			self.move_to(start.x, start.y);
+		}
+	}
+
+	/// Clockwise arc (in y-down canvas space) from `start` to `end` radians,
+	/// joined to the current subpath by a straight line.
+	pub fn arc(&mut self, x: f32, y: f32, radius: f32, start: f32, end: f32) {
+		let mut sweep = end - start;
+		if sweep >= 2.0 * PI {
+			sweep = 2.0 * PI;
+		} else {
+			sweep = sweep.rem_euclid(2.0 * PI);
+		}
+		// About half a pixel per segment, at least eight per full turn.
+		let segments = ((sweep * radius * 2.0).ceil() as usize)
+			.max((sweep * 4.0 / PI).ceil() as usize)
+			.max(1);
+		for k in 0..=segments {
+			let angle = start + sweep * k as f32 / segments as f32;
+			self.line_to(x + radius * angle.cos(), y + radius * angle.sin());
+		}
+	}
+
+	/// Fill every subpath of the current path, closing each implicitly.
+	pub fn fill(&mut self) {
+		let polygons: Vec<Vec<Vec2>> = self
+			.path
+			.iter()
+			.filter(|s| s.points.len() >= 3)
+			.map(|s| s.points.clone())
+			.collect();
+		let paint = self.fill_style.clone();
+		self.fill_polygons(&polygons, &paint);
+	}
+
+	pub fn stroke(&mut self) {
+		let half = self.line_width * 0.5;
+		let mut quads = Vec::new();
+		for subpath in &self.path {
+			let p = &subpath.points;
+			let mut segments: Vec<(Vec2, Vec2)> = p.windows(2).map(|w| (w[0], w[1])).collect();
+			if subpath.closed && p.len() > 2 {
+				segments.push((p[p.len() - 1], p[0]));
+			}
+			for (a, b) in segments {
+				let d = b - a;
+				if d.length() == 0.0 {
+					continue;
+				}
+				let d = d * (1.0 / d.length());
+				let n = Vec2::new(-d.y, d.x) * half;
+				quads.push(oriented(vec![a + n, b + n, b - n, a - n]));
+			}
+		}
+		let paint = self.stroke_style.clone();
+		self.fill_polygons(&quads, &paint);
+	}
+
+	/// Width of `text` in pixels in the current font.
+	pub fn measure_text(&self, text: &str) -> f32 {
+		let n = text.chars().count() as f32;
+		let scale = self.font.size / GLYPH_EM as f32;
+		(n * GLYPH_ADVANCE as f32 - 1.0).max(0.0) * scale
+	}
+
+	pub fn fill_text(&mut self, text: &str, x: f32, y: f32) {
+		let scale = self.font.size / GLYPH_EM as f32;
+		let width = self.measure_text(text);
+		let left = match self.text_align {
+			TextAlign::Left => x,
+			TextAlign::Center => x - width * 0.5,
+			TextAlign::Right => x - width,
+		};
+		let top = match self.text_baseline {
+			TextBaseline::Top => y,
+			TextBaseline::Middle => y - GLYPH_EM as f32 * 0.5 * scale,
+			TextBaseline::Alphabetic => y - GLYPH_BASELINE as f32 * scale,
+			TextBaseline::Bottom => y - GLYPH_EM as f32 * scale,
+		};
+		// Bold widens every dot by half a dot to the right.
+		let dot_width = if self.font.bold { 1.5 } else { 1.0 } * scale;
+		let mut dots = Vec::new();
+		for (i, c) in text.chars().enumerate() {
+			let glyph = glyph(c);
+			let x0 = left + (i * GLYPH_ADVANCE) as f32 * scale;
+			for (row, bits) in glyph.iter().enumerate() {
+				for column in 0..5 {
+					if bits & (0x10 >> column) != 0 {
+						let (dx, dy) = (x0 + column as f32 * scale, top + row as f32 * scale);
+						dots.push(vec![
+							Vec2::new(dx, dy),
+							Vec2::new(dx + dot_width, dy),
+							Vec2::new(dx + dot_width, dy + scale),
+							Vec2::new(dx, dy + scale),
+						]);
+					}
+				}
+			}
+		}
+		let paint = self.fill_style.clone();
+		self.fill_polygons(&dots, &paint);
+	}
+
+	/// Straight (not premultiplied) color at `(x, y)`.
+	pub fn pixel(&self, x: u32, y: u32) -> Vec4 {
+		let c = self.pixels[(y * self.width + x) as usize];
+		if c.w == 0.0 {
+			Vec4::default()
+		} else {
+			Vec4::new(c.x / c.w, c.y / c.w, c.z / c.w, c.w)
+		}
+	}
+
+	/// `getImageData`: straight RGBA8, top row first.
+	pub fn to_rgba8(&self) -> Vec<u8> {
+		let mut out = Vec::with_capacity(self.pixels.len() * 4);
+		for y in 0..self.height {
+			for x in 0..self.width {
+				let c = self.pixel(x, y);
+				out.extend(
+					c.to_array()
+						.map(|v| (v.clamp(0.0, 1.0) * 255.0).round() as u8),
+				);
+			}
+		}
+		out
+	}
+
+	/// Upload as `texImage2D` would, with canvas row 0 at `t = 0`.
+	pub fn to_texture(&self) -> Texture2D {
+		Texture2D::from_rgba8(self.width, self.height, &self.to_rgba8())
+	}
+
+	// Nonzero fill of the union of `polygons`, composited source-over.
+	fn fill_polygons(&mut self, polygons: &[Vec<Vec2>], paint: &Paint) {
+		const N: usize = 4;
+		let points = polygons.iter().flatten();
+		let min_x = points
+			.clone()
+			.map(|p| p.x)
+			.fold(f32::INFINITY, f32::min)
+			.floor()
+			.max(0.0);
+		let max_x = points
+			.clone()
+			.map(|p| p.x)
+			.fold(f32::NEG_INFINITY, f32::max)
+			.ceil()
+			.min(self.width as f32);
+		let min_y = points
+			.clone()
+			.map(|p| p.y)
+			.fold(f32::INFINITY, f32::min)
+			.floor()
+			.max(0.0);
+		let max_y = points
+			.map(|p| p.y)
+			.fold(f32::NEG_INFINITY, f32::max)
+			.ceil()
+			.min(self.height as f32);
+		if !(min_x < max_x && min_y < max_y) {
+			return;
+		}
+		let (x0, x1, y0, y1) = (
+			min_x as usize,
+			max_x as usize,
+			min_y as usize,
+			max_y as usize,
+		);
+		let edges: Vec<(Vec2, Vec2)> = polygons
+			.iter()
+			.filter(|p| p.len() >= 3)
+			.flat_map(|p| (0..p.len()).map(move |i| (p[i], p[(i + 1) % p.len()])))
+			.filter(|(a, b)| a.y != b.y)
+			.collect();
+		let mut coverage = vec![0.0f32; x1 - x0];
+		let mut crossings: Vec<(f32, i32)> = Vec::new();
+		for py in y0..y1 {
+			coverage.fill(0.0);
+			for k in 0..N {
+				let sy = py as f32 + (k as f32 + 0.5) / N as f32;
+				crossings.clear();
+				for &(a, b) in &edges {
+					let (lo, hi) = if a.y < b.y { (a.y, b.y) } else { (b.y, a.y) };
+					if sy >= lo && sy < hi {
+						let x = a.x + (sy - a.y) * (b.x - a.x) / (b.y - a.y);
+						crossings.push((x, if b.y > a.y { 1 } else { -1 }));
+					}
+				}
+				crossings.sort_by(|a, b| a.0.total_cmp(&b.0));
+				let mut winding = 0;
+				for i in 0..crossings.len() {
+					winding += crossings[i].1;
+					if winding == 0 || i + 1 == crossings.len() {
+						continue;
+					}
+					// Samples at (s + 0.5) / N inside [xa, xb).
+					let first = (crossings[i].0 * N as f32 - 0.5)
+						.ceil()
+						.max((x0 * N) as f32);
+					let last = (crossings[i + 1].0 * N as f32 - 0.5)
+						.ceil()
+						.min((x1 * N) as f32);
+					let mut s = first as usize;
+					while (s as f32) < last {
+						coverage[s / N - x0] += 1.0 / (N * N) as f32;
+						s += 1;
+					}
+				}
+			}
+			for px in x0..x1 {
+				let a = coverage[px - x0].min(1.0);
+				if a > 0.0 {
+					let src = paint.at(Vec2::new(px as f32 + 0.5, py as f32 + 0.5)) * a;
+					let dst = &mut self.pixels[py * self.width as usize + px];
+					*dst = src + *dst * (1.0 - src.w);
+				}
+			}
+		}
+	}
+}
+
+// Counter-clockwise in y-down space, so overlapping quads add up under the
+// nonzero rule instead of cancelling.
+fn oriented(mut polygon: Vec<Vec2>) -> Vec<Vec2> {
+	let n = polygon.len();
+	let area: f32 = (0..n)
+		.map(|i| {
+			let (a, b) = (polygon[i], polygon[(i + 1) % n]);
+			a.x * b.y - b.x * a.y
+		})
+		.sum();
+	if area < 0.0 {
+		polygon.reverse();
+	}
+	polygon
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// Bitmap Font
+////////////////////////////////////////////////////////////////////////////////
+
+// Glyphs are 5 dots wide in an 8 row em: 7 rows above the baseline and one for
+// descenders, with one dot of spacing between characters.
+const GLYPH_EM: usize = 8;
+const GLYPH_BASELINE: usize = 7;
+const GLYPH_ADVANCE: usize = 6;
+
+fn glyph(c: char) -> &'static [u8; 8] {
+	let i = match c {
+		' '..='~' => c as usize - ' ' as usize,
+		_ => '?' as usize - ' ' as usize,
+	};
+	&FONT[i]
+}
+
+// ASCII 32 to 126, one byte per row from the top, bit 4 leftmost.
+#[rustfmt::skip]
+const FONT: [[u8; 8]; 95] = [
+	[0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00], // ' '
+	[0x04, 0x04, 0x04, 0x04, 0x04, 0x00, 0x04, 0x00], // '!'
+	[0x0a, 0x0a, 0x0a, 0x00, 0x00, 0x00, 0x00, 0x00], // '"'
+	[0x0a, 0x0a, 0x1f, 0x0a, 0x1f, 0x0a, 0x0a, 0x00], // '#'
+	[0x04, 0x0f, 0x14, 0x0e, 0x05, 0x1e, 0x04, 0x00], // '$'
+	[0x18, 0x19, 0x02, 0x04, 0x08, 0x13, 0x03, 0x00], // '%'
+	[0x0c, 0x12, 0x14, 0x08, 0x15, 0x12, 0x0d, 0x00], // '&'
+	[0x04, 0x04, 0x08, 0x00, 0x00, 0x00, 0x00, 0x00], // '\''
+	[0x02, 0x04, 0x08, 0x08, 0x08, 0x04, 0x02, 0x00], // '('
+	[0x08, 0x04, 0x02, 0x02, 0x02, 0x04, 0x08, 0x00], // ')'
+	[0x00, 0x04, 0x15, 0x0e, 0x15, 0x04, 0x00, 0x00], // '*'
+	[0x00, 0x04, 0x04, 0x1f, 0x04, 0x04, 0x00, 0x00], // '+'
+	[0x00, 0x00, 0x00, 0x00, 0x0c, 0x04, 0x08, 0x00], // ','
+	[0x00, 0x00, 0x00, 0x1f, 0x00, 0x00, 0x00, 0x00], // '-'
+	[0x00, 0x00, 0x00, 0x00, 0x00, 0x0c, 0x0c, 0x00], // '.'
+	[0x00, 0x01, 0x02, 0x04, 0x08, 0x10, 0x00, 0x00], // '/'
+	[0x0e, 0x11, 0x13, 0x15, 0x19, 0x11, 0x0e, 0x00], // '0'
+	[0x04, 0x0c, 0x04, 0x04, 0x04, 0x04, 0x0e, 0x00], // '1'
+	[0x0e, 0x11, 0x01, 0x02, 0x04, 0x08, 0x1f, 0x00], // '2'
+	[0x1f, 0x02, 0x04, 0x02, 0x01, 0x11, 0x0e, 0x00], // '3'
+	[0x02, 0x06, 0x0a, 0x12, 0x1f, 0x02, 0x02, 0x00], // '4'
+	[0x1f, 0x10, 0x1e, 0x01, 0x01, 0x11, 0x0e, 0x00], // '5'
+	[0x06, 0x08, 0x10, 0x1e, 0x11, 0x11, 0x0e, 0x00], // '6'
+	[0x1f, 0x01, 0x02, 0x04, 0x08, 0x08, 0x08, 0x00], // '7'
+	[0x0e, 0x11, 0x11, 0x0e, 0x11, 0x11, 0x0e, 0x00], // '8'
+	[0x0e, 0x11, 0x11, 0x0f, 0x01, 0x02, 0x0c, 0x00], // '9'
+	[0x00, 0x0c, 0x0c, 0x00, 0x0c, 0x0c, 0x00, 0x00], // ':'
+	[0x00, 0x0c, 0x0c, 0x00, 0x0c, 0x04, 0x08, 0x00], // ';'
+	[0x02, 0x04, 0x08, 0x10, 0x08, 0x04, 0x02, 0x00], // '<'
+	[0x00, 0x00, 0x1f, 0x00, 0x1f, 0x00, 0x00, 0x00], // '='
+	[0x08, 0x04, 0x02, 0x01, 0x02, 0x04, 0x08, 0x00], // '>'
+	[0x0e, 0x11, 0x01, 0x02, 0x04, 0x00, 0x04, 0x00], // '?'
+	[0x0e, 0x11, 0x01, 0x0d, 0x15, 0x15, 0x0e, 0x00], // '@'
+	[0x0e, 0x11, 0x11, 0x1f, 0x11, 0x11, 0x11, 0x00], // 'A'
+	[0x1e, 0x11, 0x11, 0x1e, 0x11, 0x11, 0x1e, 0x00], // 'B'
+	[0x0e, 0x11, 0x10, 0x10, 0x10, 0x11, 0x0e, 0x00], // 'C'
+	[0x1c, 0x12, 0x11, 0x11, 0x11, 0x12, 0x1c, 0x00], // 'D'
+	[0x1f, 0x10, 0x10, 0x1e, 0x10, 0x10, 0x1f, 0x00], // 'E'
+	[0x1f, 0x10, 0x10, 0x1e, 0x10, 0x10, 0x10, 0x00], // 'F'
+	[0x0e, 0x11, 0x10, 0x17, 0x11, 0x11, 0x0f, 0x00], // 'G'
+	[0x11, 0x11, 0x11, 0x1f, 0x11, 0x11, 0x11, 0x00], // 'H'
+	[0x0e, 0x04, 0x04, 0x04, 0x04, 0x04, 0x0e, 0x00], // 'I'
+	[0x07, 0x02, 0x02, 0x02, 0x02, 0x12, 0x0c, 0x00], // 'J'
+	[0x11, 0x12, 0x14, 0x18, 0x14, 0x12, 0x11, 0x00], // 'K'
+	[0x10, 0x10, 0x10, 0x10, 0x10, 0x10, 0x1f, 0x00], // 'L'
+	[0x11, 0x1b, 0x15, 0x15, 0x11, 0x11, 0x11, 0x00], // 'M'
+	[0x11, 0x11, 0x19, 0x15, 0x13, 0x11, 0x11, 0x00], // 'N'
+	[0x0e, 0x11, 0x11, 0x11, 0x11, 0x11, 0x0e, 0x00], // 'O'
+	[0x1e, 0x11, 0x11, 0x1e, 0x10, 0x10, 0x10, 0x00], // 'P'
+	[0x0e, 0x11, 0x11, 0x11, 0x15, 0x12, 0x0d, 0x00], // 'Q'
+	[0x1e, 0x11, 0x11, 0x1e, 0x14, 0x12, 0x11, 0x00], // 'R'
+	[0x0f, 0x10, 0x10, 0x0e, 0x01, 0x01, 0x1e, 0x00], // 'S'
+	[0x1f, 0x04, 0x04, 0x04, 0x04, 0x04, 0x04, 0x00], // 'T'
+	[0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x0e, 0x00], // 'U'
+	[0x11, 0x11, 0x11, 0x11, 0x11, 0x0a, 0x04, 0x00], // 'V'
+	[0x11, 0x11, 0x11, 0x15, 0x15, 0x15, 0x0a, 0x00], // 'W'
+	[0x11, 0x11, 0x0a, 0x04, 0x0a, 0x11, 0x11, 0x00], // 'X'
+	[0x11, 0x11, 0x11, 0x0a, 0x04, 0x04, 0x04, 0x00], // 'Y'
+	[0x1f, 0x01, 0x02, 0x04, 0x08, 0x10, 0x1f, 0x00], // 'Z'
+	[0x0e, 0x08, 0x08, 0x08, 0x08, 0x08, 0x0e, 0x00], // '['
+	[0x00, 0x10, 0x08, 0x04, 0x02, 0x01, 0x00, 0x00], // '\\'
+	[0x0e, 0x02, 0x02, 0x02, 0x02, 0x02, 0x0e, 0x00], // ']'
+	[0x04, 0x0a, 0x11, 0x00, 0x00, 0x00, 0x00, 0x00], // '^'
+	[0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x1f, 0x00], // '_'
+	[0x08, 0x04, 0x02, 0x00, 0x00, 0x00, 0x00, 0x00], // '`'
+	[0x00, 0x00, 0x0e, 0x01, 0x0f, 0x11, 0x0f, 0x00], // 'a'
+	[0x10, 0x10, 0x16, 0x19, 0x11, 0x11, 0x1e, 0x00], // 'b'
+	[0x00, 0x00, 0x0e, 0x10, 0x10, 0x11, 0x0e, 0x00], // 'c'
+	[0x01, 0x01, 0x0d, 0x13, 0x11, 0x11, 0x0f, 0x00], // 'd'
+	[0x00, 0x00, 0x0e, 0x11, 0x1f, 0x10, 0x0e, 0x00], // 'e'
+	[0x06, 0x09, 0x08, 0x1c, 0x08, 0x08, 0x08, 0x00], // 'f'
+	[0x00, 0x00, 0x0f, 0x11, 0x11, 0x0f, 0x01, 0x0e], // 'g'
+	[0x10, 0x10, 0x16, 0x19, 0x11, 0x11, 0x11, 0x00], // 'h'
+	[0x04, 0x00, 0x0c, 0x04, 0x04, 0x04, 0x0e, 0x00], // 'i'
+	[0x02, 0x00, 0x06, 0x02, 0x02, 0x02, 0x12, 0x0c], // 'j'
+	[0x10, 0x10, 0x12, 0x14, 0x18, 0x14, 0x12, 0x00], // 'k'
+	[0x0c, 0x04, 0x04, 0x04, 0x04, 0x04, 0x0e, 0x00], // 'l'
+	[0x00, 0x00, 0x1a, 0x15, 0x15, 0x11, 0x11, 0x00], // 'm'
+	[0x00, 0x00, 0x16, 0x19, 0x11, 0x11, 0x11, 0x00], // 'n'
+	[0x00, 0x00, 0x0e, 0x11, 0x11, 0x11, 0x0e, 0x00], // 'o'
+	[0x00, 0x00, 0x1e, 0x11, 0x11, 0x1e, 0x10, 0x10], // 'p'
+	[0x00, 0x00, 0x0f, 0x11, 0x11, 0x0f, 0x01, 0x01], // 'q'
+	[0x00, 0x00, 0x16, 0x19, 0x10, 0x10, 0x10, 0x00], // 'r'
+	[0x00, 0x00, 0x0e, 0x10, 0x0e, 0x01, 0x1e, 0x00], // 's'
+	[0x08, 0x08, 0x1c, 0x08, 0x08, 0x09, 0x06, 0x00], // 't'
+	[0x00, 0x00, 0x11, 0x11, 0x11, 0x13, 0x0d, 0x00], // 'u'
+	[0x00, 0x00, 0x11, 0x11, 0x11, 0x0a, 0x04, 0x00], // 'v'
+	[0x00, 0x00, 0x11, 0x11, 0x15, 0x15, 0x0a, 0x00], // 'w'
+	[0x00, 0x00, 0x11, 0x0a, 0x04, 0x0a, 0x11, 0x00], // 'x'
+	[0x00, 0x00, 0x11, 0x11, 0x11, 0x0f, 0x01, 0x0e], // 'y'
+	[0x00, 0x00, 0x1f, 0x02, 0x04, 0x08, 0x1f, 0x00], // 'z'
+	[0x02, 0x04, 0x04, 0x08, 0x04, 0x04, 0x02, 0x00], // '{'
+	[0x04, 0x04, 0x04, 0x04, 0x04, 0x04, 0x04, 0x00], // '|'
+	[0x08, 0x04, 0x04, 0x02, 0x04, 0x04, 0x08, 0x00], // '}'
+	[0x00, 0x00, 0x08, 0x15, 0x02, 0x00, 0x00, 0x00], // '~'
+];
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn colors() {
+		assert_eq!(
+			css_color("green"),
+			Some(Vec4::new(0.0, 128.0 / 255.0, 0.0, 1.0))
+		);
+		assert_eq!(css_color("#FFFFFF"), css_color("white"));
+		assert_eq!(css_color("#f00"), css_color("red"));
+		assert_eq!(css_color("nope"), None);
+	}
+
+	#[test]
+	fn fill_rect_is_exact_on_pixel_edges() {
+		let mut canvas = Canvas::new(4, 4);
+		canvas.set_fill_color("red");
+		canvas.fill_rect(1.0, 1.0, 2.0, 2.0);
+		assert_eq!(canvas.pixel(1, 1), Vec4::new(1.0, 0.0, 0.0, 1.0));
+		assert_eq!(canvas.pixel(0, 0), Vec4::default());
+		assert_eq!(canvas.pixel(3, 2), Vec4::default());
+		// Half a pixel of 50% black over red.
+		canvas.set_fill_color("black");
+		canvas.fill_rect(1.0, 1.0, 0.5, 1.0);
+		assert_eq!(canvas.pixel(1, 1), Vec4::new(0.5, 0.0, 0.0, 1.0));
+	}
+
+	#[test]
+	fn stroke_union() {
+		let mut canvas = Canvas::new(8, 8);
+		canvas.move_to(0.0, 4.0);
+		canvas.line_to(8.0, 4.0);
+		canvas.move_to(4.0, 0.0);
+		canvas.line_to(4.0, 8.0);
+		canvas.stroke();
+		// A 1px line on a pixel edge half-covers the pixels either side, and
+		// the crossing is not darkened twice.
+		assert_eq!(canvas.pixel(0, 4).w, 0.5);
+		assert_eq!(canvas.pixel(4, 4).w, 0.75);
+	}
+
+	#[test]
+	fn radial_gradient() {
+		let mut g = RadialGradient::new(8.0, 8.0, 0.0, 8.0, 8.0, 8.0);
+		g.add_color_stop(0.0, Vec4::new(1.0, 1.0, 1.0, 1.0));
+		g.add_color_stop(1.0, Vec4::new(0.0, 0.0, 0.0, 1.0));
+		assert_eq!(
+			g.color_at(Vec2::new(8.0, 8.0)),
+			Vec4::new(1.0, 1.0, 1.0, 1.0)
+		);
+		assert_eq!(
+			g.color_at(Vec2::new(12.0, 8.0)),
+			Vec4::new(0.5, 0.5, 0.5, 1.0)
+		);
+		assert_eq!(
+			g.color_at(Vec2::new(20.0, 8.0)),
+			Vec4::new(0.0, 0.0, 0.0, 1.0)
+		);
+	}
+
+	#[test]
+	fn arc_fill_area() {
+		let mut canvas = Canvas::new(32, 32);
+		canvas.begin_path();
+		canvas.arc(16.0, 16.0, 10.0, 0.0, 2.0 * PI);
+		canvas.fill();
+		let area: f32 = canvas.pixels.iter().map(|p| p.w).sum();
+		assert!((area - PI * 100.0).abs() < 1.0, "{area}");
+	}
+
+	#[test]
+	fn text() {
+		let mut canvas = Canvas::new(32, 16);
+		canvas.font = Font::parse("bold 8px serif").unwrap();
+		assert_eq!(
+			canvas.font,
+			Font {
+				size: 8.0,
+				bold: true
+			}
+		);
+		assert_eq!(canvas.measure_text("Hi"), 11.0);
+		canvas.text_baseline = TextBaseline::Top;
+		canvas.fill_text("I", 0.0, 0.0);
+		// 'I' has a three dot bar on the top row, emboldened by half a dot.
+		assert_eq!(canvas.pixel(0, 0).w, 0.0);
+		assert_eq!(canvas.pixel(1, 0).w, 1.0);
+		assert_eq!(canvas.pixel(4, 0).w, 0.5);
+		assert_eq!(canvas.pixel(2, 7).w, 0.0);
+	}
+}
@@ -0,0 +1,295 @@
+////////////////////////////////////////////////////////////////////////////////
+// Dual Numbers
+////////////////////////////////////////////////////////////////////////////////
+//
+// Forward-mode automatic differentiation in two variables. A `Dual` carries a
+// value and its partial derivatives with respect to u and v; evaluating a
+// function written against `Real` on `Dual::u(u)` and `Dual::v(v)` gives the
+// function and its exact partials in one pass.
+
+use std::ops::{Add, Div, Mul, Neg, Sub};
+
+use crate::math::Vec3;
+
+/// The scalar operations a surface function may use, for `f32` and [`Dual`].
+pub trait Real:
+	Copy
+	+ From<f32>
+	+ Add<Output = Self>
+	+ Sub<Output = Self>
+	+ Mul<Output = Self>
+	+ Div<Output = Self>
+	+ Neg<Output = Self>
+	+ Add<f32, Output = Self>
+	+ Sub<f32, Output = Self>
+	+ Mul<f32, Output = Self>
+	+ Div<f32, Output = Self>
+{
+	fn value(self) -> f32;
+	fn sin(self) -> Self;
+	fn cos(self) -> Self;
+	fn tan(self) -> Self;
+	fn sqrt(self) -> Self;
+	fn exp(self) -> Self;
+	fn ln(self) -> Self;
+	fn powf(self, n: f32) -> Self;
+	fn abs(self) -> Self;
+	fn atan2(self, x: Self) -> Self;
+
+	/// `sign(self) * |self|^n`, the signed power of superquadrics.
+	fn signed_powf(self, n: f32) -> Self {
+		let p = self.abs().powf(n);
+		if self.value() < 0.0 {
+			-p
+		} else {
+			p
+		}
+	}
+}
+
+impl Real for f32 {
+	fn value(self) -> f32 {
+		self
+	}
+	fn sin(self) -> Self {
+		f32::sin(self)
+	}
+	fn cos(self) -> Self {
+		f32::cos(self)
+	}
+	fn tan(self) -> Self {
+		f32::tan(self)
+	}
+	fn sqrt(self) -> Self {
+		f32::sqrt(self)
+	}
+	fn exp(self) -> Self {
+		f32::exp(self)
+	}
+	fn ln(self) -> Self {
+		f32::ln(self)
+	}
+	fn powf(self, n: f32) -> Self {
+		f32::powf(self, n)
+	}
+	fn abs(self) -> Self {
+		f32::abs(self)
+	}
+	fn atan2(self, x: Self) -> Self {
+		f32::atan2(self, x)
+	}
+}
+
+/// A value with its partial derivatives `du` and `dv`.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Dual {
+	pub re: f32,
+	pub du: f32,
+	pub dv: f32,
+}
+
+impl Dual {
+	pub const fn new(re: f32, du: f32, dv: f32) -> Self {
+		Self { re, du, dv }
+	}
+
+	/// The variable u: derivative 1 with respect to itself.
+	pub const fn u(u: f32) -> Self {
+		Self::new(u, 1.0, 0.0)
+	}
+
+	/// The variable v.
+	pub const fn v(v: f32) -> Self {
+		Self::new(v, 0.0, 1.0)
+	}
+
+	// Apply a function with value `f` and derivative `df` at `self.re`.
+	fn chain(self, f: f32, df: f32) -> Self {
+		Self::new(f, self.du * df, self.dv * df)
+	}
+}
+
+impl From<f32> for Dual {
+	fn from(re: f32) -> Self {
+		Self::new(re, 0.0, 0.0)
+	}
+}
+
+impl Add for Dual {
+	type Output = Self;
+	fn add(self, rhs: Self) -> Self {
+		Self::new(self.re + rhs.re, self.du + rhs.du, self.dv + rhs.dv)
+	}
+}
+
+impl Sub for Dual {
+	type Output = Self;
+	fn sub(self, rhs: Self) -> Self {
+		Self::new(self.re - rhs.re, self.du - rhs.du, self.dv - rhs.dv)
+	}
+}
+
+impl Mul for Dual {
+	type Output = Self;
+	fn mul(self, rhs: Self) -> Self {
+		Self::new(
+			self.re * rhs.re,
+			self.du * rhs.re + self.re * rhs.du,
+			self.dv * rhs.re + self.re * rhs.dv,
+		)
+	}
+}
+
+impl Div for Dual {
+	type Output = Self;
+	fn div(self, rhs: Self) -> Self {
+		let q = self.re / rhs.re;
+		Self::new(
+			q,
+			(self.du - q * rhs.du) / rhs.re,
+			(self.dv - q * rhs.dv) / rhs.re,
+		)
+	}
+}
+
+impl Neg for Dual {
+	type Output = Self;
+	fn neg(self) -> Self {
+		Self::new(-self.re, -self.du, -self.dv)
+	}
+}
+
+impl Add<f32> for Dual {
+	type Output = Self;
+	fn add(self, rhs: f32) -> Self {
+		Self::new(self.re + rhs, self.du, self.dv)
+	}
+}
+
+impl Sub<f32> for Dual {
+	type Output = Self;
+	fn sub(self, rhs: f32) -> Self {
+		Self::new(self.re - rhs, self.du, self.dv)
+	}
+}
+
+impl Mul<f32> for Dual {
+	type Output = Self;
+	fn mul(self, rhs: f32) -> Self {
+		Self::new(self.re * rhs, self.du * rhs, self.dv * rhs)
+	}
+}
+
+impl Div<f32> for Dual {
+	type Output = Self;
+	fn div(self, rhs: f32) -> Self {
+		Self::new(self.re / rhs, self.du / rhs, self.dv / rhs)
+	}
+}
+
+impl Real for Dual {
+	fn value(self) -> f32 {
+		self.re
+	}
+	fn sin(self) -> Self {
+		let (s, c) = self.re.sin_cos();
+		self.chain(s, c)
+	}
+	fn cos(self) -> Self {
+		let (s, c) = self.re.sin_cos();
+		self.chain(c, -s)
+	}
+	fn tan(self) -> Self {
+		let t = self.re.tan();
+		self.chain(t, 1.0 + t * t)
+	}
+	fn sqrt(self) -> Self {
+		let r = self.re.sqrt();
+		self.chain(r, 0.5 / r)
+	}
+	fn exp(self) -> Self {
+		let e = self.re.exp();
+		self.chain(e, e)
+	}
+	fn ln(self) -> Self {
+		self.chain(self.re.ln(), 1.0 / self.re)
+	}
+	fn powf(self, n: f32) -> Self {
+		self.chain(self.re.powf(n), n * self.re.powf(n - 1.0))
+	}
+	fn abs(self) -> Self {
+		if self.re < 0.0 {
+			-self
+		} else {
+			self
+		}
+	}
+	fn atan2(self, x: Self) -> Self {
+		// d atan2(y, x) = (x dy - y dx) / (x^2 + y^2)
+		let r2 = self.re * self.re + x.re * x.re;
+		Self::new(
+			self.re.atan2(x.re),
+			(x.re * self.du - self.re * x.du) / r2,
+			(x.re * self.dv - self.re * x.dv) / r2,
+		)
+	}
+}
+
+/// Value and partials of a point computed with duals.
+pub fn dual_partials(p: [Dual; 3]) -> (Vec3, Vec3, Vec3) {
+	(
+		Vec3::new(p[0].re, p[1].re, p[2].re),
+		Vec3::new(p[0].du, p[1].du, p[2].du),
+		Vec3::new(p[0].dv, p[1].dv, p[2].dv),
+	)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn assert_near(a: f32, b: f32) {
+		assert!((a - b).abs() < 1e-5, "{a} != {b}");
+	}
+
+	#[test]
+	fn arithmetic() {
+		// f(u, v) = u * v / (u + v) at (2, 3): df/du = v^2 / (u + v)^2.
+		let (u, v) = (Dual::u(2.0), Dual::v(3.0));
+		let f = u * v / (u + v);
+		assert_near(f.re, 1.2);
+		assert_near(f.du, 9.0 / 25.0);
+		assert_near(f.dv, 4.0 / 25.0);
+		let g = -(u * 3.0 - 1.0) / 2.0 + 0.5;
+		assert_eq!(g, Dual::new(-2.0, -1.5, 0.0));
+	}
+
+	#[test]
+	fn functions_match_finite_differences() {
+		let fs: [fn(Dual) -> Dual; 9] = [
+			Real::sin,
+			Real::cos,
+			Real::tan,
+			Real::sqrt,
+			Real::exp,
+			Real::ln,
+			|x| x.powf(2.5),
+			|x| x.signed_powf(0.5),
+			|x| x.atan2(Dual::from(0.3) - x),
+		];
+		for f in fs {
+			for x in [0.3f32, 0.7, 1.1] {
+				let h = 1e-3;
+				let numeric = (f(Dual::from(x + h)).re - f(Dual::from(x - h)).re) / (2.0 * h);
+				let d = f(Dual::u(x));
+				assert!(
+					(d.du - numeric).abs() < 2e-3 * numeric.abs().max(1.0),
+					"{x}: {d:?} vs {numeric}"
+				);
+				assert_eq!(d.dv, 0.0);
+			}
+		}
+		assert_eq!(Dual::u(-4.0).signed_powf(0.5), Dual::new(-2.0, 0.25, 0.0));
+		assert_eq!(Dual::u(-2.0).abs(), Dual::new(2.0, -1.0, 0.0));
+	}
+}
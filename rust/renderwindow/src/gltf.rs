@@ -0,0 +1,1247 @@
+////////////////////////////////////////////////////////////////////////////////
+// glTF 2.0
+////////////////////////////////////////////////////////////////////////////////
+//
+// glTF stores column-vector matrices column by column, which is the same
+// sixteen numbers as a row-vector `Mat4` row by row, so matrices copy straight
+// across and a child's world matrix is `local * parent`. Texture coordinates
+// start at the top left as `st0` does. Reading flattens the node hierarchy of
+// the default scene into one `Instance` per primitive and keeps the point
+// lights of `KHR_lights_punctual`; writing gives every instance and light its
+// own root node and shares meshes, materials and textures that share an `Arc`.
+
+use std::collections::HashMap;
+use std::io;
+use std::path::Path;
+use std::sync::Arc;
+
+use crate::image::{decode_png, encode_png};
+use crate::json::Json;
+use crate::math::{mat_scale, mat_translate, Mat4, Vec2, Vec3, Vec4};
+use crate::mesh::{IndexBuffer, MeshGen};
+use crate::scene::{Instance, Material, PointLight, Scene};
+use crate::texture::Texture2D;
+use crate::vertex::{Semantic, VertexLayout};
+
+const GLB_MAGIC: &[u8; 4] = b"glTF";
+const CHUNK_JSON: &[u8; 4] = b"JSON";
+const CHUNK_BIN: &[u8; 4] = b"BIN\0";
+
+const BYTE: usize = 5120;
+const UNSIGNED_BYTE: usize = 5121;
+const SHORT: usize = 5122;
+const UNSIGNED_SHORT: usize = 5123;
+const UNSIGNED_INT: usize = 5125;
+const FLOAT: usize = 5126;
+
+const ARRAY_BUFFER: usize = 34962;
+const ELEMENT_ARRAY_BUFFER: usize = 34963;
+
+const LIGHTS: &str = "KHR_lights_punctual";
+
+fn invalid(message: &str) -> io::Error {
+	io::Error::new(io::ErrorKind::InvalidData, message.to_string())
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// Base64
+////////////////////////////////////////////////////////////////////////////////
+
+const BASE64: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+fn base64_encode(bytes: &[u8]) -> String {
+	let mut out = String::with_capacity(bytes.len().div_ceil(3) * 4);
+	for chunk in bytes.chunks(3) {
+		let b = [0, 1, 2].map(|i| chunk.get(i).copied().unwrap_or(0) as u32);
+		let n = (b[0] << 16) | (b[1] << 8) | b[2];
+		for i in 0..4 {
+			if i <= chunk.len() {
+				out.push(BASE64[(n >> (18 - 6 * i) & 63) as usize] as char);
+			} else {
+				out.push('=');
+			}
+		}
+	}
+	out
+}
+
+fn base64_decode(text: &str) -> io::Result<Vec<u8>> {
+	let text = text.trim_end_matches('=');
+	let mut out = Vec::with_capacity(text.len() * 3 / 4);
+	let (mut n, mut bits) = (0u32, 0);
+	for c in text.bytes() {
+		let value = BASE64
+			.iter()
+			.position(|&b| b == c)
+			.ok_or_else(|| invalid("Bad base64 character."))?;
+		n = (n << 6) | value as u32;
+		bits += 6;
+		if bits >= 8 {
+			bits -= 8;
+			out.push((n >> bits) as u8);
+		}
+	}
+	Ok(out)
+}
+
+// The payload of a `data:` URI, which glTF requires to be base64.
+fn data_uri(uri: &str) -> Option<io::Result<Vec<u8>>> {
+	let rest = uri.strip_prefix("data:")?;
+	Some(match rest.split_once(";base64,") {
+		Some((_, data)) => base64_decode(data),
+		None => Err(invalid("Data URIs must be base64.")),
+	})
+}
+
+// `%XX` escapes of a relative URI.
+fn percent_decode(uri: &str) -> String {
+	let bytes = uri.as_bytes();
+	let mut out = Vec::with_capacity(bytes.len());
+	let mut i = 0;
+	while i < bytes.len() {
+		let hex = bytes
+			.get(i + 1..i + 3)
+			.and_then(|h| std::str::from_utf8(h).ok())
+			.and_then(|h| u8::from_str_radix(h, 16).ok());
+		match (bytes[i], hex) {
+			(b'%', Some(b)) => {
+				out.push(b);
+				i += 3;
+			}
+			(b, _) => {
+				out.push(b);
+				i += 1;
+			}
+		}
+	}
+	String::from_utf8_lossy(&out).into_owned()
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// Reading
+////////////////////////////////////////////////////////////////////////////////
+
+// The members of an array-valued key, or none.
+fn items<'a>(json: &'a Json, key: &str) -> &'a [Json] {
+	json.get(key).and_then(Json::as_array).unwrap_or(&[])
+}
+
+fn index(json: &Json, key: &str) -> io::Result<Option<usize>> {
+	match json.get(key) {
+		None => Ok(None),
+		Some(v) => v
+			.as_usize()
+			.map(Some)
+			.ok_or_else(|| invalid(&format!("{key} is not an index."))),
+	}
+}
+
+fn number(json: &Json, key: &str, default: f32) -> f32 {
+	json.get(key)
+		.and_then(Json::as_f64)
+		.map_or(default, |n| n as f32)
+}
+
+fn numbers<const N: usize>(json: &Json, key: &str, default: [f32; N]) -> io::Result<[f32; N]> {
+	match json.get(key) {
+		None => Ok(default),
+		Some(v) => v
+			.as_f32s()
+			.and_then(|v| v.try_into().ok())
+			.ok_or_else(|| invalid(&format!("{key} needs {N} numbers."))),
+	}
+}
+
+// The element `i` of a top-level array such as `accessors`.
+fn element<'a>(json: &'a Json, key: &str, i: usize) -> io::Result<&'a Json> {
+	items(json, key)
+		.get(i)
+		.ok_or_else(|| invalid(&format!("{key}[{i}] does not exist.")))
+}
+
+/// Rotation by a unit quaternion `[x, y, z, w]`, for row vectors.
+pub fn mat_quaternion(q: [f32; 4]) -> Mat4 {
+	let [x, y, z, w] = q;
+	Mat4::from_rows([
+		[
+			1.0 - 2.0 * (y * y + z * z),
+			2.0 * (x * y + z * w),
+			2.0 * (x * z - y * w),
+			0.0,
+		],
+		[
+			2.0 * (x * y - z * w),
+			1.0 - 2.0 * (x * x + z * z),
+			2.0 * (y * z + x * w),
+			0.0,
+		],
+		[
+			2.0 * (x * z + y * w),
+			2.0 * (y * z - x * w),
+			1.0 - 2.0 * (x * x + y * y),
+			0.0,
+		],
+		[0.0, 0.0, 0.0, 1.0],
+	])
+}
+
+struct Reader<'a> {
+	json: &'a Json,
+	buffers: Vec<Vec<u8>>,
+	resolve: &'a dyn Fn(&str) -> io::Result<Vec<u8>>,
+	// Primitives that differ only in material share vertex data.
+	meshes: HashMap<String, Arc<MeshGen>>,
+	materials: HashMap<usize, Arc<Material>>,
+	// `None` for images in formats that are not decoded.
+	images: HashMap<usize, Option<Arc<Texture2D>>>,
+}
+
+impl Reader<'_> {
+	fn buffer_view(&self, i: usize) -> io::Result<(&[u8], Option<usize>)> {
+		let view = element(self.json, "bufferViews", i)?;
+		let buffer =
+			index(view, "buffer")?.ok_or_else(|| invalid("A buffer view needs a buffer."))?;
+		let bytes = self
+			.buffers
+			.get(buffer)
+			.ok_or_else(|| invalid(&format!("buffers[{buffer}] does not exist.")))?;
+		let offset = index(view, "byteOffset")?.unwrap_or(0);
+		let length = index(view, "byteLength")?.unwrap_or(0);
+		let bytes = bytes
+			.get(offset..offset + length)
+			.ok_or_else(|| invalid(&format!("bufferViews[{i}] overruns its buffer.")))?;
+		Ok((bytes, index(view, "byteStride")?))
+	}
+
+	// The elements of an accessor as rows of `components` numbers. Integers
+	// are exact in `f64`; normalized ones are mapped to [0, 1] or [-1, 1].
+	fn accessor(&self, i: usize) -> io::Result<(Vec<f64>, usize)> {
+		let accessor = element(self.json, "accessors", i)?;
+		let count = index(accessor, "count")?.unwrap_or(0);
+		let components = match accessor.get("type").and_then(Json::as_str) {
+			Some("SCALAR") => 1,
+			Some("VEC2") => 2,
+			Some("VEC3") => 3,
+			Some("VEC4") => 4,
+			_ => return Err(invalid(&format!("accessors[{i}] has an unsupported type."))),
+		};
+		if accessor.get("sparse").is_some() {
+			return Err(invalid("Sparse accessors are not supported."));
+		}
+		let kind = index(accessor, "componentType")?.unwrap_or(0);
+		let size = match kind {
+			BYTE | UNSIGNED_BYTE => 1,
+			SHORT | UNSIGNED_SHORT => 2,
+			UNSIGNED_INT | FLOAT => 4,
+			_ => return Err(invalid(&format!("accessors[{i}] has a bad componentType."))),
+		};
+		let normalized = accessor
+			.get("normalized")
+			.and_then(Json::as_bool)
+			.unwrap_or(false);
+		// Without a buffer view every element is zero. Such an accessor is
+		// still held to the size of the file's buffers, so that a short
+		// document cannot ask for an arbitrarily large allocation.
+		let Some(view) = index(accessor, "bufferView")? else {
+			let total: usize = self.buffers.iter().map(Vec::len).sum();
+			if count
+				.checked_mul(components * size)
+				.is_none_or(|n| n > total)
+			{
+				return Err(invalid(&format!(
+					"accessors[{i}] is larger than the buffers."
+				)));
+			}
+			return Ok((vec![0.0; count * components], components));
+		};
+		let (bytes, stride) = self.buffer_view(view)?;
+		let stride = stride.unwrap_or(components * size);
+		let offset = index(accessor, "byteOffset")?.unwrap_or(0);
+		let end = match count {
+			0 => Some(0),
+			_ => (count - 1)
+				.checked_mul(stride)
+				.and_then(|n| n.checked_add(offset + components * size)),
+		};
+		if end.is_none_or(|end| end > bytes.len()) {
+			return Err(invalid(&format!(
+				"accessors[{i}] overruns its buffer view."
+			)));
+		}
+		let mut values = Vec::with_capacity(count * components);
+		for e in 0..count {
+			for c in 0..components {
+				let at = offset + e * stride + c * size;
+				let b = &bytes[at..at + size];
+				let value = match kind {
+					BYTE => b[0] as i8 as f64,
+					UNSIGNED_BYTE => b[0] as f64,
+					SHORT => i16::from_le_bytes([b[0], b[1]]) as f64,
+					UNSIGNED_SHORT => u16::from_le_bytes([b[0], b[1]]) as f64,
+					UNSIGNED_INT => u32::from_le_bytes([b[0], b[1], b[2], b[3]]) as f64,
+					_ => f32::from_le_bytes([b[0], b[1], b[2], b[3]]) as f64,
+				};
+				values.push(match (normalized, kind) {
+					(true, BYTE) => (value / 127.0).max(-1.0),
+					(true, UNSIGNED_BYTE) => value / 255.0,
+					(true, SHORT) => (value / 32767.0).max(-1.0),
+					(true, UNSIGNED_SHORT) => value / 65535.0,
+					_ => value,
+				});
+			}
+		}
+		Ok((values, components))
+	}
+
+	// An attribute accessor with at least `components` per element.
+	fn attribute(&self, i: usize, name: &str, components: usize) -> io::Result<Vec<Vec<f32>>> {
+		let (values, n) = self.accessor(i)?;
+		if n < components {
+			return Err(invalid(&format!("{name} needs {components} components.")));
+		}
+		Ok(values
+			.chunks_exact(n)
+			.map(|v| v.iter().map(|&x| x as f32).collect())
+			.collect())
+	}
+
+	fn primitive(&mut self, p: &Json) -> io::Result<Arc<MeshGen>> {
+		let attributes = p
+			.get("attributes")
+			.ok_or_else(|| invalid("A primitive needs attributes."))?;
+		let mode = index(p, "mode")?.unwrap_or(4);
+		let key = format!("{attributes} {:?} {mode}", index(p, "indices")?);
+		if let Some(m) = self.meshes.get(&key) {
+			return Ok(Arc::clone(m));
+		}
+		let position =
+			index(attributes, "POSITION")?.ok_or_else(|| invalid("A primitive needs POSITION."))?;
+		let mut out = MeshGen {
+			vtx: self
+				.attribute(position, "POSITION", 3)?
+				.iter()
+				.map(|v| Vec3::new(v[0], v[1], v[2]))
+				.collect(),
+			..MeshGen::default()
+		};
+		let n = out.vtx.len();
+		let optional = |name: &str, components: usize| -> io::Result<Option<Vec<Vec<f32>>>> {
+			let Some(i) = index(attributes, name)? else {
+				return Ok(None);
+			};
+			let values = self.attribute(i, name, components)?;
+			if values.len() != n {
+				return Err(invalid(&format!("{name} and POSITION differ in count.")));
+			}
+			Ok(Some(values))
+		};
+		if let Some(v) = optional("NORMAL", 3)? {
+			out.nor = v.iter().map(|v| Vec3::new(v[0], v[1], v[2])).collect();
+		}
+		if let Some(v) = optional("TEXCOORD_0", 2)? {
+			out.st0 = v.iter().map(|v| Vec2::new(v[0], v[1])).collect();
+		}
+		if let Some(v) = optional("TANGENT", 4)? {
+			out.tan = v
+				.iter()
+				.map(|v| Vec4::new(v[0], v[1], v[2], v[3]))
+				.collect();
+		}
+		let corners: Vec<u32> = match index(p, "indices")? {
+			Some(i) => {
+				let (values, _) = self.accessor(i)?;
+				values.into_iter().map(|v| v as u32).collect()
+			}
+			None => (0..n as u32).collect(),
+		};
+		out.idx = match mode {
+			4 => corners,
+			// Strips alternate winding; fans share the first corner.
+			5 => (0..corners.len().saturating_sub(2))
+				.flat_map(|i| {
+					let [a, b, c] = [corners[i], corners[i + 1], corners[i + 2]];
+					if i % 2 == 0 {
+						[a, b, c]
+					} else {
+						[b, a, c]
+					}
+				})
+				.collect(),
+			6 => (1..corners.len().saturating_sub(1))
+				.flat_map(|i| [corners[0], corners[i], corners[i + 1]])
+				.collect(),
+			_ => return Err(invalid("Only triangle primitives are supported.")),
+		};
+		out.validate_indices()
+			.map_err(|e| invalid(&e.to_string()))?;
+		// The rasterizer needs normals and texture coordinates.
+		if out.nor.is_empty() {
+			out.generate_normals();
+		}
+		if out.st0.is_empty() {
+			out.st0 = vec![Vec2::default(); n];
+		}
+		let out = Arc::new(out);
+		self.meshes.insert(key, Arc::clone(&out));
+		Ok(out)
+	}
+
+	fn image(&mut self, i: usize) -> io::Result<Option<Arc<Texture2D>>> {
+		if let Some(t) = self.images.get(&i) {
+			return Ok(t.clone());
+		}
+		let image = element(self.json, "images", i)?;
+		let bytes = match (
+			image.get("uri").and_then(Json::as_str),
+			index(image, "bufferView")?,
+		) {
+			(Some(uri), _) => match data_uri(uri) {
+				Some(data) => data?,
+				None => (self.resolve)(&percent_decode(uri))?,
+			},
+			(None, Some(view)) => self.buffer_view(view)?.0.to_vec(),
+			(None, None) => return Err(invalid(&format!("images[{i}] has no data."))),
+		};
+		// JPEG and other formats leave their texture slots empty.
+		let texture = if bytes.starts_with(b"\x89PNG") {
+			let png = decode_png(&bytes)?;
+			Some(Arc::new(Texture2D::from_rgba8(
+				png.width, png.height, &png.rgba,
+			)))
+		} else {
+			None
+		};
+		self.images.insert(i, texture.clone());
+		Ok(texture)
+	}
+
+	// The image of a `textureInfo` object such as `baseColorTexture`.
+	fn texture(&mut self, info: Option<&Json>) -> io::Result<Option<Arc<Texture2D>>> {
+		let Some(info) = info else {
+			return Ok(None);
+		};
+		let texture = index(info, "index")?.ok_or_else(|| invalid("A texture needs an index."))?;
+		match index(element(self.json, "textures", texture)?, "source")? {
+			Some(image) => self.image(image),
+			None => Ok(None),
+		}
+	}
+
+	fn material(&mut self, i: usize) -> io::Result<Arc<Material>> {
+		if let Some(m) = self.materials.get(&i) {
+			return Ok(Arc::clone(m));
+		}
+		let json = self.json;
+		let m = element(json, "materials", i)?;
+		let pbr = m.get("pbrMetallicRoughness").unwrap_or(&Json::Null);
+		let [r, g, b, a] = numbers(pbr, "baseColorFactor", [1.0; 4])?;
+		let [er, eg, eb] = numbers(m, "emissiveFactor", [0.0; 3])?;
+		let material = Arc::new(Material {
+			name: m
+				.get("name")
+				.and_then(Json::as_str)
+				.unwrap_or("")
+				.to_string(),
+			base_color: Vec4::new(r, g, b, a),
+			base_color_texture: self.texture(pbr.get("baseColorTexture"))?,
+			metallic: number(pbr, "metallicFactor", 1.0),
+			roughness: number(pbr, "roughnessFactor", 1.0),
+			metallic_roughness_texture: self.texture(pbr.get("metallicRoughnessTexture"))?,
+			normal_texture: self.texture(m.get("normalTexture"))?,
+			emissive: Vec3::new(er, eg, eb),
+			double_sided: m
+				.get("doubleSided")
+				.and_then(Json::as_bool)
+				.unwrap_or(false),
+		});
+		self.materials.insert(i, Arc::clone(&material));
+		Ok(material)
+	}
+
+	// The instances and lights below `roots`, depth first. The walk keeps its
+	// own stack so that deep hierarchies cannot overflow the thread's, and
+	// visits each node once, which rejects cycles and shared children.
+	fn nodes(&mut self, roots: &[usize], scene: &mut Scene) -> io::Result<()> {
+		let json = self.json;
+		let mut visited = vec![false; items(json, "nodes").len()];
+		let mut stack: Vec<(usize, Mat4)> =
+			roots.iter().rev().map(|&i| (i, Mat4::IDENTITY)).collect();
+		while let Some((i, parent)) = stack.pop() {
+			match visited.get_mut(i) {
+				Some(true) => {
+					return Err(invalid(&format!(
+						"nodes[{i}] has two parents or is part of a cycle."
+					)))
+				}
+				Some(seen) => *seen = true,
+				None => return Err(invalid(&format!("nodes[{i}] does not exist."))),
+			}
+			let world = self.node(i, parent, scene)?;
+			let children = items(element(json, "nodes", i)?, "children");
+			for child in children.iter().rev() {
+				let child = child
+					.as_usize()
+					.ok_or_else(|| invalid("children must be indices."))?;
+				stack.push((child, world));
+			}
+		}
+		Ok(())
+	}
+
+	// Add the instances and light of one node, returning its world matrix.
+	fn node(&mut self, i: usize, parent: Mat4, scene: &mut Scene) -> io::Result<Mat4> {
+		let json = self.json;
+		let node = element(json, "nodes", i)?;
+		let local = match node.get("matrix") {
+			Some(_) => Mat4::from_array(numbers(node, "matrix", [0.0; 16])?),
+			None => {
+				let [sx, sy, sz] = numbers(node, "scale", [1.0; 3])?;
+				let [tx, ty, tz] = numbers(node, "translation", [0.0; 3])?;
+				let rotation = numbers(node, "rotation", [0.0, 0.0, 0.0, 1.0])?;
+				mat_scale(sx, sy, sz) * mat_quaternion(rotation) * mat_translate(tx, ty, tz)
+			}
+		};
+		let world = local * parent;
+		if let Some(mesh) = index(node, "mesh")? {
+			let primitives = items(element(json, "meshes", mesh)?, "primitives");
+			for primitive in primitives {
+				let material = match index(primitive, "material")? {
+					Some(m) => Some(self.material(m)?),
+					None => None,
+				};
+				scene.instances.push(Instance {
+					mesh: self.primitive(primitive)?,
+					model: world,
+					material,
+				});
+			}
+		}
+		let light = node
+			.get("extensions")
+			.and_then(|e| e.get(LIGHTS))
+			.map(|l| index(l, "light"))
+			.transpose()?
+			.flatten();
+		if let Some(light) = light {
+			let lights = json.get("extensions").and_then(|e| e.get(LIGHTS));
+			let light = lights
+				.and_then(|l| items(l, "lights").get(light))
+				.ok_or_else(|| invalid(&format!("Light {light} does not exist.")))?;
+			// Directional and spot lights have no counterpart here.
+			if light.get("type").and_then(Json::as_str) == Some("point") {
+				let [r, g, b] = numbers(light, "color", [1.0; 3])?;
+				scene.lights.push(PointLight {
+					position: world.transform_point(Vec3::default()),
+					color: Vec3::new(r, g, b),
+					intensity: number(light, "intensity", 1.0),
+					range: light.get("range").and_then(Json::as_f64).map(|r| r as f32),
+				});
+			}
+		}
+		Ok(world)
+	}
+}
+
+// The JSON and binary chunks of a GLB file.
+fn split_glb(bytes: &[u8]) -> io::Result<(&[u8], Option<&[u8]>)> {
+	let word = |at: usize| {
+		bytes
+			.get(at..at + 4)
+			.map(|w| u32::from_le_bytes([w[0], w[1], w[2], w[3]]) as usize)
+			.ok_or_else(|| invalid("Truncated GLB."))
+	};
+	if word(4)? != 2 {
+		return Err(invalid("Only GLB version 2 is supported."));
+	}
+	let total = word(8)?.min(bytes.len());
+	let (mut pos, mut json, mut bin) = (12, None, None);
+	while pos + 8 <= total {
+		let len = word(pos)?;
+		let data = bytes
+			.get(pos + 8..pos + 8 + len)
+			.ok_or_else(|| invalid("Truncated GLB chunk."))?;
+		match &bytes[pos + 4..pos + 8] {
+			k if k == CHUNK_JSON && json.is_none() => json = Some(data),
+			k if k == CHUNK_BIN && bin.is_none() => bin = Some(data),
+			_ => {}
+		}
+		pos += 8 + len;
+	}
+	Ok((json.ok_or_else(|| invalid("GLB has no JSON chunk."))?, bin))
+}
+
+/// Read a `.gltf` document or a `.glb` file into a scene. `resolve` fetches
+/// buffers and images given by relative URI; data URIs and the GLB binary
+/// chunk need no help. Only PNG images are decoded; a texture whose image is
+/// JPEG or any other format is left out of its material, as if unset.
+pub fn decode_gltf(
+	bytes: &[u8],
+	resolve: &dyn Fn(&str) -> io::Result<Vec<u8>>,
+) -> io::Result<Scene> {
+	let (text, bin) = if bytes.starts_with(GLB_MAGIC) {
+		split_glb(bytes)?
+	} else {
+		(bytes, None)
+	};
+	let text = std::str::from_utf8(text).map_err(|_| invalid("glTF JSON is not UTF-8."))?;
+	let json = Json::parse(text).map_err(|e| invalid(&e.to_string()))?;
+	let version = json
+		.get("asset")
+		.and_then(|a| a.get("version"))
+		.and_then(Json::as_str);
+	if !version.is_some_and(|v| v.starts_with("2.")) {
+		return Err(invalid("Only glTF 2.x is supported."));
+	}
+
+	let mut buffers = Vec::new();
+	for (i, buffer) in items(&json, "buffers").iter().enumerate() {
+		let length = index(buffer, "byteLength")?.unwrap_or(0);
+		let data = match buffer.get("uri").and_then(Json::as_str) {
+			Some(uri) => match data_uri(uri) {
+				Some(data) => data?,
+				None => resolve(&percent_decode(uri))?,
+			},
+			None if i == 0 => bin
+				.ok_or_else(|| invalid("buffers[0] has no URI and no GLB chunk."))?
+				.to_vec(),
+			None => return Err(invalid(&format!("buffers[{i}] has no URI."))),
+		};
+		if data.len() < length {
+			return Err(invalid(&format!(
+				"buffers[{i}] is shorter than byteLength."
+			)));
+		}
+		buffers.push(data);
+	}
+
+	let mut reader = Reader {
+		json: &json,
+		buffers,
+		resolve,
+		meshes: HashMap::new(),
+		materials: HashMap::new(),
+		images: HashMap::new(),
+	};
+	// The default scene, or else every node that is nobody's child.
+	let roots: Vec<usize> = match (index(&json, "scene")?, items(&json, "scenes").is_empty()) {
+		(None, true) => {
+			let nodes = items(&json, "nodes");
+			let mut child = vec![false; nodes.len()];
+			for i in nodes
+				.iter()
+				.flat_map(|n| items(n, "children").iter().filter_map(Json::as_usize))
+			{
+				if let Some(c) = child.get_mut(i) {
+					*c = true;
+				}
+			}
+			(0..nodes.len()).filter(|&i| !child[i]).collect()
+		}
+		(scene, _) => items(element(&json, "scenes", scene.unwrap_or(0))?, "nodes")
+			.iter()
+			.map(|n| {
+				n.as_usize()
+					.ok_or_else(|| invalid("Scene nodes must be indices."))
+			})
+			.collect::<io::Result<_>>()?,
+	};
+	let mut scene = Scene::default();
+	reader.nodes(&roots, &mut scene)?;
+	Ok(scene)
+}
+
+/// Read a `.gltf` or `.glb` file, with external buffers and images next to
+/// it.
+pub fn load_gltf(path: impl AsRef<Path>) -> io::Result<Scene> {
+	let path = path.as_ref();
+	let dir = path.parent().unwrap_or(Path::new(""));
+	decode_gltf(&std::fs::read(path)?, &|uri| std::fs::read(dir.join(uri)))
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// Writing
+////////////////////////////////////////////////////////////////////////////////
+
+fn object(members: Vec<(&str, Json)>) -> Json {
+	Json::Object(
+		members
+			.into_iter()
+			.map(|(k, v)| (k.to_string(), v))
+			.collect(),
+	)
+}
+
+// Everything a scene refers to, each written once.
+#[derive(Default)]
+struct Writer {
+	bin: Vec<u8>,
+	views: Vec<Json>,
+	accessors: Vec<Json>,
+	meshes: Vec<Json>,
+	materials: Vec<Json>,
+	// One texture per image, so they share indices.
+	images: Vec<Json>,
+	// Keyed by `Arc` address.
+	geometry: HashMap<usize, Json>,
+	mesh_ids: HashMap<(usize, usize), usize>,
+	material_ids: HashMap<usize, usize>,
+	texture_ids: HashMap<usize, usize>,
+}
+
+impl Writer {
+	fn view(&mut self, bytes: &[u8], target: Option<usize>) -> usize {
+		// Accessor data must be aligned to its component size.
+		while !self.bin.len().is_multiple_of(4) {
+			self.bin.push(0);
+		}
+		let mut view = object(vec![
+			("buffer", 0.into()),
+			("byteOffset", self.bin.len().into()),
+			("byteLength", bytes.len().into()),
+		]);
+		if let Some(target) = target {
+			view.push("target", target);
+		}
+		self.bin.extend_from_slice(bytes);
+		self.views.push(view);
+		self.views.len() - 1
+	}
+
+	fn accessor(&mut self, view: usize, kind: usize, count: usize, ty: &str) -> usize {
+		self.accessors.push(object(vec![
+			("bufferView", view.into()),
+			("componentType", kind.into()),
+			("count", count.into()),
+			("type", ty.into()),
+		]));
+		self.accessors.len() - 1
+	}
+
+	fn texture(&mut self, texture: &Arc<Texture2D>) -> usize {
+		let key = Arc::as_ptr(texture) as usize;
+		if let Some(&i) = self.texture_ids.get(&key) {
+			return i;
+		}
+		let png = encode_png(texture.width(), texture.height(), &texture.to_rgba8());
+		let view = self.view(&png, None);
+		self.images.push(object(vec![
+			("bufferView", view.into()),
+			("mimeType", "image/png".into()),
+		]));
+		let i = self.images.len() - 1;
+		self.texture_ids.insert(key, i);
+		i
+	}
+
+	fn material(&mut self, m: &Arc<Material>) -> usize {
+		let key = Arc::as_ptr(m) as usize;
+		if let Some(&i) = self.material_ids.get(&key) {
+			return i;
+		}
+		let info = |w: &mut Self, t: &Arc<Texture2D>| object(vec![("index", w.texture(t).into())]);
+		let mut pbr = object(vec![
+			("baseColorFactor", m.base_color.to_array().to_vec().into()),
+			("metallicFactor", m.metallic.into()),
+			("roughnessFactor", m.roughness.into()),
+		]);
+		if let Some(t) = &m.base_color_texture {
+			pbr.push("baseColorTexture", info(self, t));
+		}
+		if let Some(t) = &m.metallic_roughness_texture {
+			pbr.push("metallicRoughnessTexture", info(self, t));
+		}
+		let mut json = object(vec![("pbrMetallicRoughness", pbr)]);
+		if !m.name.is_empty() {
+			json.push("name", m.name.as_str());
+		}
+		if let Some(t) = &m.normal_texture {
+			json.push("normalTexture", info(self, t));
+		}
+		if m.emissive != Vec3::default() {
+			json.push("emissiveFactor", m.emissive.to_array().to_vec());
+		}
+		if m.double_sided {
+			json.push("doubleSided", true);
+		}
+		self.materials.push(json);
+		let i = self.materials.len() - 1;
+		self.material_ids.insert(key, i);
+		i
+	}
+
+	// A primitive without a material, whose accessors every material of the
+	// mesh reuses.
+	fn geometry(&mut self, mesh: &Arc<MeshGen>) -> io::Result<Json> {
+		let key = Arc::as_ptr(mesh) as usize;
+		if let Some(primitive) = self.geometry.get(&key) {
+			return Ok(primitive.clone());
+		}
+		let n = mesh.vertex_count();
+		let present = [
+			(Semantic::Position, "POSITION", mesh.vtx.len(), "VEC3"),
+			(Semantic::Normal, "NORMAL", mesh.nor.len(), "VEC3"),
+			(Semantic::TexCoord0, "TEXCOORD_0", mesh.st0.len(), "VEC2"),
+			(Semantic::Tangent, "TANGENT", mesh.tan.len(), "VEC4"),
+		]
+		.into_iter()
+		.filter(|&(_, _, len, _)| len == n)
+		.collect::<Vec<_>>();
+		let semantics: Vec<Semantic> = present.iter().map(|p| p.0).collect();
+		let buffers = VertexLayout::planar(&semantics)
+			.and_then(|layout| layout.write(mesh))
+			.map_err(|e| invalid(&e.to_string()))?;
+		let mut attributes = object(Vec::new());
+		for ((semantic, name, _, ty), bytes) in present.into_iter().zip(&buffers) {
+			let view = self.view(bytes, Some(ARRAY_BUFFER));
+			let accessor = self.accessor(view, FLOAT, n, ty);
+			// POSITION must carry its bounds.
+			if semantic == Semantic::Position && n > 0 {
+				let (mut lo, mut hi) = ([f32::INFINITY; 3], [f32::NEG_INFINITY; 3]);
+				for p in &mesh.vtx {
+					for (c, v) in p.to_array().into_iter().enumerate() {
+						lo[c] = lo[c].min(v);
+						hi[c] = hi[c].max(v);
+					}
+				}
+				self.accessors[accessor].push("min", lo.to_vec());
+				self.accessors[accessor].push("max", hi.to_vec());
+			}
+			attributes.push(name, accessor);
+		}
+		let indices = mesh
+			.index_buffer()
+			.map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+		let kind = match indices {
+			IndexBuffer::U16(_) => UNSIGNED_SHORT,
+			IndexBuffer::U32(_) => UNSIGNED_INT,
+		};
+		let view = self.view(&indices.to_le_bytes(), Some(ELEMENT_ARRAY_BUFFER));
+		let indices = self.accessor(view, kind, indices.len(), "SCALAR");
+		let primitive = object(vec![
+			("attributes", attributes),
+			("indices", indices.into()),
+		]);
+		self.geometry.insert(key, primitive.clone());
+		Ok(primitive)
+	}
+
+	fn mesh(&mut self, mesh: &Arc<MeshGen>, material: Option<&Arc<Material>>) -> io::Result<usize> {
+		let key = (
+			Arc::as_ptr(mesh) as usize,
+			material.map_or(0, |m| Arc::as_ptr(m) as usize),
+		);
+		if let Some(&i) = self.mesh_ids.get(&key) {
+			return Ok(i);
+		}
+		let mut primitive = self.geometry(mesh)?;
+		if let Some(m) = material {
+			primitive.push("material", self.material(m));
+		}
+		self.meshes
+			.push(object(vec![("primitives", Json::Array(vec![primitive]))]));
+		let i = self.meshes.len() - 1;
+		self.mesh_ids.insert(key, i);
+		Ok(i)
+	}
+}
+
+// The document and its binary buffer, embedded as a data URI or left for the
+// GLB binary chunk.
+fn write_document(scene: &Scene, embed: bool) -> io::Result<(Json, Vec<u8>)> {
+	let mut w = Writer::default();
+	let mut nodes = Vec::new();
+	for instance in &scene.instances {
+		let mesh = w.mesh(&instance.mesh, instance.material.as_ref())?;
+		let mut node = object(vec![("mesh", mesh.into())]);
+		if instance.model != Mat4::IDENTITY {
+			node.push("matrix", instance.model.to_array().to_vec());
+		}
+		nodes.push(node);
+	}
+	let mut lights = Vec::new();
+	for light in &scene.lights {
+		let mut json = object(vec![
+			("type", "point".into()),
+			("color", light.color.to_array().to_vec().into()),
+			("intensity", light.intensity.into()),
+		]);
+		if let Some(range) = light.range {
+			json.push("range", range);
+		}
+		nodes.push(object(vec![
+			("translation", light.position.to_array().to_vec().into()),
+			(
+				"extensions",
+				object(vec![(LIGHTS, object(vec![("light", lights.len().into())]))]),
+			),
+		]));
+		lights.push(json);
+	}
+
+	let mut doc = object(vec![
+		(
+			"asset",
+			object(vec![
+				("version", "2.0".into()),
+				("generator", "renderwindow".into()),
+			]),
+		),
+		("scene", 0.into()),
+		(
+			"scenes",
+			Json::Array(vec![object(vec![(
+				"nodes",
+				(0..nodes.len()).collect::<Vec<_>>().into(),
+			)])]),
+		),
+		("nodes", Json::Array(nodes)),
+	]);
+	let textures = (0..w.images.len())
+		.map(|i| object(vec![("source", i.into())]))
+		.collect();
+	let sections = [
+		("meshes", w.meshes),
+		("materials", w.materials),
+		("textures", textures),
+		("images", w.images),
+		("accessors", w.accessors),
+		("bufferViews", w.views),
+	];
+	for (key, values) in sections {
+		if !values.is_empty() {
+			doc.push(key, Json::Array(values));
+		}
+	}
+	if !w.bin.is_empty() {
+		let mut buffer = object(vec![("byteLength", w.bin.len().into())]);
+		if embed {
+			let data = base64_encode(&w.bin);
+			buffer.push(
+				"uri",
+				format!("data:application/octet-stream;base64,{data}"),
+			);
+		}
+		doc.push("buffers", Json::Array(vec![buffer]));
+	}
+	if !lights.is_empty() {
+		doc.push("extensionsUsed", vec![LIGHTS]);
+		doc.push(
+			"extensions",
+			object(vec![(
+				LIGHTS,
+				object(vec![("lights", Json::Array(lights))]),
+			)]),
+		);
+	}
+	Ok((doc, w.bin))
+}
+
+/// A self-contained `.gltf` document with the buffer as a data URI. Fails if
+/// a mesh's indices are out of range.
+pub fn encode_gltf(scene: &Scene) -> io::Result<String> {
+	Ok(write_document(scene, true)?.0.to_string())
+}
+
+/// A binary `.glb` file.
+pub fn encode_glb(scene: &Scene) -> io::Result<Vec<u8>> {
+	let (doc, mut bin) = write_document(scene, false)?;
+	let mut json = doc.to_string().into_bytes();
+	// Chunks are padded to 4 bytes, JSON with spaces.
+	while !json.len().is_multiple_of(4) {
+		json.push(b' ');
+	}
+	while !bin.len().is_multiple_of(4) {
+		bin.push(0);
+	}
+	let total = 12 + 8 + json.len() + if bin.is_empty() { 0 } else { 8 + bin.len() };
+	let mut out = Vec::with_capacity(total);
+	out.extend_from_slice(GLB_MAGIC);
+	out.extend_from_slice(&2u32.to_le_bytes());
+	out.extend_from_slice(&(total as u32).to_le_bytes());
+	for (kind, data) in [(CHUNK_JSON, &json), (CHUNK_BIN, &bin)] {
+		if !data.is_empty() {
+			out.extend_from_slice(&(data.len() as u32).to_le_bytes());
+			out.extend_from_slice(kind);
+			out.extend_from_slice(data);
+		}
+	}
+	Ok(out)
+}
+
+/// Write `.glb` if the extension says so, and a self-contained `.gltf`
+/// otherwise.
+pub fn save_gltf(scene: &Scene, path: impl AsRef<Path>) -> io::Result<()> {
+	let path = path.as_ref();
+	let glb = path
+		.extension()
+		.is_some_and(|e| e.eq_ignore_ascii_case("glb"));
+	if glb {
+		std::fs::write(path, encode_glb(scene)?)
+	} else {
+		std::fs::write(path, encode_gltf(scene)?)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn no_files(uri: &str) -> io::Result<Vec<u8>> {
+		Err(io::Error::new(io::ErrorKind::NotFound, uri.to_string()))
+	}
+
+	fn distinct_meshes(scene: &Scene) -> usize {
+		let mut meshes: Vec<_> = scene
+			.instances
+			.iter()
+			.map(|i| Arc::as_ptr(&i.mesh))
+			.collect();
+		meshes.sort();
+		meshes.dedup();
+		meshes.len()
+	}
+
+	#[test]
+	fn encodings() {
+		for (bytes, text) in [
+			(&b""[..], ""),
+			(b"f", "Zg=="),
+			(b"fo", "Zm8="),
+			(b"foo", "Zm9v"),
+			(b"foob", "Zm9vYg=="),
+		] {
+			assert_eq!(base64_encode(bytes), text);
+			assert_eq!(base64_decode(text).unwrap(), bytes);
+		}
+		assert!(base64_decode("Zm9v!").is_err());
+		assert_eq!(percent_decode("a%20b%2x.bin"), "a b%2x.bin");
+		// A right-handed quarter turn about +Y takes +X to -Z.
+		let h = std::f32::consts::FRAC_1_SQRT_2;
+		let x = mat_quaternion([0.0, h, 0.0, h]).transform_point(Vec3::new(1.0, 0.0, 0.0));
+		assert!((x - Vec3::new(0.0, 0.0, -1.0)).length() < 1e-6, "{x:?}");
+	}
+
+	#[test]
+	fn demo_round_trip() {
+		let scene = Scene::demo();
+		let glb = encode_glb(&scene).unwrap();
+		assert_eq!(&glb[..4], b"glTF");
+		assert_eq!(glb.len() % 4, 0);
+		let gltf = encode_gltf(&scene).unwrap();
+		for back in [
+			decode_gltf(&glb, &no_files).unwrap(),
+			decode_gltf(gltf.as_bytes(), &no_files).unwrap(),
+		] {
+			assert_eq!(back.instances.len(), scene.instances.len());
+			// Plane, sphere and torus, each stored once.
+			assert_eq!(distinct_meshes(&back), 3);
+			for (a, b) in back.instances.iter().zip(&scene.instances) {
+				assert_eq!(a.model, b.model);
+				assert_eq!(a.material, b.material);
+				assert_eq!(a.mesh.vtx, b.mesh.vtx);
+				assert_eq!(a.mesh.nor, b.mesh.nor);
+				assert_eq!(a.mesh.st0, b.mesh.st0);
+				assert_eq!(a.mesh.tan, b.mesh.tan);
+				assert_eq!(a.mesh.idx, b.mesh.idx);
+			}
+			assert_eq!(back.lights, scene.lights);
+		}
+	}
+
+	// A strip with no normals, byte indices and normalized byte texture
+	// coordinates in a separate file, under a rotated and scaled child.
+	#[test]
+	fn hierarchy_and_accessor_types() {
+		let mut bin = Vec::new();
+		for p in [
+			[0.0f32, 0.0, 0.0],
+			[0.0, 0.0, 1.0],
+			[1.0, 0.0, 0.0],
+			[1.0, 0.0, 1.0],
+		] {
+			bin.extend(p.iter().flat_map(|c| c.to_le_bytes()));
+		}
+		// Texture coordinates with a stride of 4.
+		bin.extend([0, 0, 9, 9, 255, 0, 9, 9, 0, 255, 9, 9, 255, 255, 9, 9]);
+		bin.extend([0u8, 1, 2, 3]);
+		let document = |indices: &str| {
+			format!(
+				r#"{{
+				"asset": {{"version": "2.0"}},
+				"scenes": [{{"nodes": [0]}}],
+				"nodes": [
+					{{"translation": [0, 0, 5], "children": [1]}},
+					{{"rotation": [0, 0.70710678, 0, 0.70710678], "scale": [2, 2, 2], "mesh": 0}}
+				],
+				"meshes": [{{"primitives": [{{
+					"attributes": {{"POSITION": 0, "TEXCOORD_0": 1}},
+					"indices": 2, "mode": 5
+				}}]}}],
+				"accessors": [
+					{{"bufferView": 0, "componentType": 5126, "count": 4, "type": "VEC3"}},
+					{{"bufferView": 1, "componentType": 5121, "normalized": true,
+						"count": 4, "type": "VEC2"}},
+					{{"bufferView": 2, "componentType": 5121, "count": 4, "type": "SCALAR"}}
+				],
+				"bufferViews": [
+					{{"buffer": 0, "byteLength": 48}},
+					{{"buffer": 0, "byteOffset": 48, "byteLength": 16, "byteStride": 4}},
+					{{"buffer": 0, "byteOffset": 64, "byteLength": 4}}
+				],
+				"buffers": [{{"uri": "{indices}", "byteLength": 68}}]
+			}}"#
+			)
+		};
+		let files = |uri: &str| match uri {
+			"quad data.bin" => Ok(bin.clone()),
+			_ => no_files(uri),
+		};
+		let scene = decode_gltf(document("quad%20data.bin").as_bytes(), &files).unwrap();
+		assert_eq!(scene.instances.len(), 1);
+		let instance = &scene.instances[0];
+		let mesh = &instance.mesh;
+		assert_eq!(mesh.idx, [0, 1, 2, 2, 1, 3]);
+		assert_eq!(mesh.nor, [Vec3::new(0.0, 1.0, 0.0); 4]);
+		assert_eq!(mesh.st0[1], Vec2::new(1.0, 0.0));
+		assert_eq!(mesh.st0[2], Vec2::new(0.0, 1.0));
+		// Scale 2, a quarter turn taking +X to -Z, then the parent's +5 Z.
+		let p = instance.model.transform_point(Vec3::new(1.0, 0.0, 0.0));
+		assert!((p - Vec3::new(0.0, 0.0, 3.0)).length() < 1e-5, "{p:?}");
+		assert!(instance.material.is_none());
+
+		let err = decode_gltf(document("missing.bin").as_bytes(), &files).unwrap_err();
+		assert_eq!(err.kind(), io::ErrorKind::NotFound);
+		let mut short = bin.clone();
+		short[64 + 3] = 4;
+		let short_files = |_: &str| Ok(short.clone());
+		assert!(decode_gltf(document("x.bin").as_bytes(), &short_files).is_err());
+		// An accessor without a buffer view cannot outgrow the buffers.
+		let huge = document("quad%20data.bin")
+			.replace(r#""bufferView": 1, "#, "")
+			.replace(
+				r#""count": 4, "type": "VEC2""#,
+				r#""count": 4294967295, "type": "VEC2""#,
+			);
+		let err = decode_gltf(huge.as_bytes(), &files).unwrap_err();
+		assert_eq!(err.to_string(), "accessors[1] is larger than the buffers.");
+		assert!(decode_gltf(br#"{"asset": {"version": "1.0"}}"#, &no_files).is_err());
+	}
+
+	// A chain far deeper than the thread's stack would allow by recursion,
+	// with a light at the end, then cycles and shared children.
+	#[test]
+	fn deep_and_cyclic_hierarchies() {
+		let document = |nodes: &str| {
+			format!(
+				r#"{{
+				"asset": {{"version": "2.0"}},
+				"extensions": {{"{LIGHTS}": {{"lights": [{{"type": "point"}}]}}}},
+				"nodes": [{nodes}]
+			}}"#
+			)
+		};
+		let depth = 200_000;
+		let mut chain: Vec<String> = (1..depth)
+			.map(|i| format!(r#"{{"translation": [1, 0, 0], "children": [{i}]}}"#))
+			.collect();
+		chain.push(format!(
+			r#"{{"extensions": {{"{LIGHTS}": {{"light": 0}}}}}}"#
+		));
+		let scene = decode_gltf(document(&chain.join(",")).as_bytes(), &no_files).unwrap();
+		assert_eq!(scene.lights.len(), 1);
+		let p = scene.lights[0].position;
+		assert_eq!(p, Vec3::new((depth - 1) as f32, 0.0, 0.0));
+
+		let cycle = document(r#"{"children": [1]}, {"children": [0]}"#)
+			.replace(r#""nodes""#, r#""scenes": [{"nodes": [0]}], "nodes""#);
+		let err = decode_gltf(cycle.as_bytes(), &no_files).unwrap_err();
+		assert_eq!(
+			err.to_string(),
+			"nodes[0] has two parents or is part of a cycle."
+		);
+		let shared = document(r#"{"children": [2]}, {"children": [2]}, {}"#);
+		assert!(decode_gltf(shared.as_bytes(), &no_files).is_err());
+		let missing = document(r#"{"children": [7]}"#);
+		assert!(decode_gltf(missing.as_bytes(), &no_files).is_err());
+	}
+
+	#[test]
+	fn materials_and_textures() {
+		let rgba: Vec<u8> = (0..4 * 2 * 4).map(|i| (i * 29 % 256) as u8).collect();
+		let texture = Arc::new(Texture2D::from_rgba8(4, 2, &rgba));
+		let material = Arc::new(Material {
+			name: "checker".to_string(),
+			base_color_texture: Some(Arc::clone(&texture)),
+			normal_texture: Some(Arc::clone(&texture)),
+			roughness: 0.25,
+			emissive: Vec3::new(0.5, 0.0, 0.0),
+			double_sided: true,
+			..Material::diffuse(Vec3::new(0.1, 0.2, 0.3))
+		});
+		let mesh = Arc::new(MeshGen {
+			vtx: vec![
+				Vec3::new(0.0, 0.0, 0.0),
+				Vec3::new(1.0, 0.0, 0.0),
+				Vec3::new(0.0, 1.0, 0.0),
+			],
+			nor: vec![Vec3::new(0.0, 0.0, 1.0); 3],
+			st0: vec![Vec2::default(); 3],
+			tan: Vec::new(),
+			idx: vec![0, 1, 2],
+		});
+		let mut scene = Scene::default();
+		scene.add_with_material(&mesh, Mat4::IDENTITY, &material);
+		scene.add_with_material(&mesh, mat_translate(2.0, 0.0, 0.0), &material);
+		scene.add(&mesh, mat_translate(4.0, 0.0, 0.0));
+
+		let dir = std::env::temp_dir().join(format!("renderwindow-gltf-{}", std::process::id()));
+		std::fs::create_dir_all(&dir).unwrap();
+		save_gltf(&scene, dir.join("tri.glb")).unwrap();
+		save_gltf(&scene, dir.join("tri.gltf")).unwrap();
+		let glb = load_gltf(dir.join("tri.glb")).unwrap();
+		let gltf = load_gltf(dir.join("tri.gltf")).unwrap();
+		std::fs::remove_dir_all(&dir).unwrap();
+		for back in [glb, gltf] {
+			let read = back.instances[0].material.as_ref().unwrap();
+			assert_eq!(read, &material);
+			assert!(Arc::ptr_eq(
+				read,
+				back.instances[1].material.as_ref().unwrap()
+			));
+			assert!(back.instances[2].material.is_none());
+			// One texture in both slots, and one mesh with or without material.
+			let base = read.base_color_texture.as_ref().unwrap();
+			assert!(Arc::ptr_eq(base, read.normal_texture.as_ref().unwrap()));
+			assert_eq!(base.to_rgba8(), rgba);
+			assert_eq!(distinct_meshes(&back), 1);
+		}
+
+		// The scene with its one image replaced by a data URI.
+		let with_image = |uri: String| {
+			let mut doc = Json::parse(&encode_gltf(&scene).unwrap()).unwrap();
+			let Json::Object(members) = &mut doc else {
+				unreachable!()
+			};
+			for (key, value) in members {
+				if key == "images" {
+					let image = Json::Object(vec![("uri".into(), uri.as_str().into())]);
+					*value = Json::Array(vec![image]);
+				}
+			}
+			doc.to_string()
+		};
+		// A JPEG image drops its texture but not the material or scene.
+		let jpeg = with_image("data:image/jpeg;base64,/9j/4AAQSkZJRgABAQ==".into());
+		let back = decode_gltf(jpeg.as_bytes(), &no_files).unwrap();
+		let read = back.instances[0].material.as_ref().unwrap();
+		assert!(read.base_color_texture.is_none() && read.normal_texture.is_none());
+		assert_eq!(read.roughness, 0.25);
+		assert_eq!(back.instances.len(), 3);
+		// A PNG with no pixels is an error rather than an empty texture.
+		let mut png = encode_png(1, 1, &[0; 4]);
+		png[16..20].fill(0);
+		let empty = with_image(format!("data:image/png;base64,{}", base64_encode(&png)));
+		let err = decode_gltf(empty.as_bytes(), &no_files).unwrap_err();
+		assert_eq!(err.to_string(), "PNG dimensions must not be zero.");
+	}
+}
@@ -0,0 +1,156 @@
+////////////////////////////////////////////////////////////////////////////////
+// Golden Image Comparison
+////////////////////////////////////////////////////////////////////////////////
+//
+// Two measures are combined: a per-channel tolerance that catches any pixel
+// moving by more than rounding noise, and CIE76 ΔE in L*a*b*, which weights
+// the error by how visible it is.
+
+/// Limits a comparison must stay within to pass.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Tolerance {
+	/// Largest per-channel difference that is not counted as an outlier.
+	pub channel: u8,
+	/// Fraction of pixels allowed to exceed `channel`.
+	pub max_outliers: f32,
+	/// Largest allowed mean ΔE over the whole image.
+	pub max_mean_delta_e: f32,
+}
+
+impl Default for Tolerance {
+	fn default() -> Self {
+		Self {
+			channel: 2,
+			max_outliers: 0.001,
+			max_mean_delta_e: 0.5,
+		}
+	}
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Comparison {
+	pub width: u32,
+	pub height: u32,
+	pub outliers: usize,
+	pub max_channel_diff: u8,
+	pub mean_delta_e: f32,
+	pub max_delta_e: f32,
+	/// RGB8 diff image: outliers in red scaled by ΔE over a dimmed gray copy
+	/// of the actual image.
+	pub diff: Vec<u8>,
+}
+
+impl Comparison {
+	pub fn passes(&self, tolerance: &Tolerance) -> bool {
+		let pixels = (self.width * self.height).max(1) as f32;
+		self.outliers as f32 / pixels <= tolerance.max_outliers
+			&& self.mean_delta_e <= tolerance.max_mean_delta_e
+	}
+}
+
+/// Compare two top-row-first RGB8 images of the same size.
+pub fn compare_rgb8(
+	width: u32,
+	height: u32,
+	expected: &[u8],
+	actual: &[u8],
+	tolerance: &Tolerance,
+) -> Comparison {
+	let size = (width * height * 3) as usize;
+	assert!(
+		expected.len() == size && actual.len() == size,
+		"RGB8 size mismatch"
+	);
+	let mut result = Comparison {
+		width,
+		height,
+		outliers: 0,
+		max_channel_diff: 0,
+		mean_delta_e: 0.0,
+		max_delta_e: 0.0,
+		diff: Vec::with_capacity(size),
+	};
+	let mut total_delta_e = 0.0f64;
+	for (e, a) in expected.chunks(3).zip(actual.chunks(3)) {
+		let (e, a) = ([e[0], e[1], e[2]], [a[0], a[1], a[2]]);
+		let channel = (0..3).map(|k| e[k].abs_diff(a[k])).max().unwrap();
+		let de = delta_e(srgb_to_lab(e), srgb_to_lab(a));
+		result.max_channel_diff = result.max_channel_diff.max(channel);
+		result.max_delta_e = result.max_delta_e.max(de);
+		total_delta_e += de as f64;
+		if channel > tolerance.channel {
+			result.outliers += 1;
+			let red = (64.0 + de * 8.0).min(255.0) as u8;
+			result.diff.extend_from_slice(&[red, 0, 0]);
+		} else {
+			let gray = ((a[0] as u32 + a[1] as u32 + a[2] as u32) / 12) as u8;
+			result.diff.extend_from_slice(&[gray; 3]);
+		}
+	}
+	result.mean_delta_e = (total_delta_e / (width * height).max(1) as f64) as f32;
+	result
+}
+
+/// sRGB (D65) to CIE L*a*b*.
+pub fn srgb_to_lab(rgb: [u8; 3]) -> [f32; 3] {
+	let linear = rgb.map(|c| {
+		let c = c as f32 / 255.0;
+		if c <= 0.04045 {
+			c / 12.92
+		} else {
+			((c + 0.055) / 1.055).powf(2.4)
+		}
+	});
+	let [r, g, b] = linear;
+	let x = (0.4124564 * r + 0.3575761 * g + 0.1804375 * b) / 0.95047;
+	let y = 0.2126729 * r + 0.7151522 * g + 0.0721750 * b;
+	let z = (0.0193339 * r + 0.119192 * g + 0.9503041 * b) / 1.08883;
+	let f = |t: f32| {
+		if t > 216.0 / 24389.0 {
+			t.cbrt()
+		} else {
+			(24389.0 / 27.0 * t + 16.0) / 116.0
+		}
+	};
+	let (fx, fy, fz) = (f(x), f(y), f(z));
+	[116.0 * fy - 16.0, 500.0 * (fx - fy), 200.0 * (fy - fz)]
+}
+
+/// CIE76 color difference; about 2.3 is a just-noticeable difference.
+pub fn delta_e(a: [f32; 3], b: [f32; 3]) -> f32 {
+	((a[0] - b[0]).powi(2) + (a[1] - b[1]).powi(2) + (a[2] - b[2]).powi(2)).sqrt()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn lab_reference_colors() {
+		let white = srgb_to_lab([255, 255, 255]);
+		assert!((white[0] - 100.0).abs() < 0.01 && white[1].abs() < 0.01 && white[2].abs() < 0.01);
+		assert_eq!(srgb_to_lab([0, 0, 0]), [0.0, 0.0, 0.0]);
+		// Pure red is L* 53.24, a* 80.09, b* 67.20.
+		let red = srgb_to_lab([255, 0, 0]);
+		assert!(delta_e(red, [53.24, 80.09, 67.20]) < 0.1, "{red:?}");
+	}
+
+	#[test]
+	fn compare() {
+		let expected = vec![10, 20, 30, 200, 200, 200];
+		let tolerance = Tolerance::default();
+		let same = compare_rgb8(2, 1, &expected, &expected, &tolerance);
+		assert_eq!(
+			(same.outliers, same.max_channel_diff, same.mean_delta_e),
+			(0, 0, 0.0)
+		);
+		assert!(same.passes(&tolerance));
+		let noisy = compare_rgb8(2, 1, &expected, &[10, 20, 30, 202, 200, 199], &tolerance);
+		assert_eq!((noisy.outliers, noisy.max_channel_diff), (0, 2));
+		assert!(noisy.passes(&tolerance));
+		let broken = compare_rgb8(2, 1, &expected, &[10, 20, 30, 0, 200, 200], &tolerance);
+		assert_eq!((broken.outliers, broken.max_channel_diff), (1, 200));
+		assert!(!broken.passes(&tolerance));
+		assert_eq!(broken.diff[3], 255);
+	}
+}
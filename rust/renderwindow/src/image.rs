@@ -0,0 +1,747 @@
+////////////////////////////////////////////////////////////////////////////////
+// Image Export
+////////////////////////////////////////////////////////////////////////////////
+//
+// PNG is written with uncompressed (stored) deflate blocks, which every
+// decoder accepts and needs nothing beyond CRC-32 and Adler-32.
+// Reading has to inflate all three block types, since other tools compress;
+// it exists for textures embedded in glTF files.
+
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+use crate::raster::Framebuffer;
+
+/// Color buffer as RGBA8, top row first.
+pub fn framebuffer_rgba8(fb: &Framebuffer) -> Vec<u8> {
+	fb.color_buffer()
+		.iter()
+		.flat_map(|c| c.to_array().map(unorm8))
+		.collect()
+}
+
+/// Color buffer as RGB8, top row first.
+pub fn framebuffer_rgb8(fb: &Framebuffer) -> Vec<u8> {
+	fb.color_buffer()
+		.iter()
+		.flat_map(|c| c.xyz().to_array().map(unorm8))
+		.collect()
+}
+
+/// Depth buffer as 8-bit grayscale. With a near plane of 0.001 almost every
+/// window depth is close to 1, so the drawn depths are stretched over 0..254
+/// and cleared pixels (depth 1) are white.
+pub fn depth_gray8(fb: &Framebuffer) -> Vec<u8> {
+	let depth = fb.depth_buffer();
+	let drawn = depth.iter().copied().filter(|&d| d < 1.0);
+	let min = drawn.clone().fold(f32::INFINITY, f32::min);
+	let max = drawn.fold(f32::NEG_INFINITY, f32::max);
+	let range = (max - min).max(f32::EPSILON);
+	depth
+		.iter()
+		.map(|&d| {
+			if d >= 1.0 {
+				255
+			} else {
+				((d - min) / range * 254.0).round() as u8
+			}
+		})
+		.collect()
+}
+
+fn unorm8(x: f32) -> u8 {
+	(x.clamp(0.0, 1.0) * 255.0).round() as u8
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// PNG
+////////////////////////////////////////////////////////////////////////////////
+
+const CRC_TABLE: [u32; 256] = {
+	let mut table = [0u32; 256];
+	let mut n = 0;
+	while n < 256 {
+		let mut c = n as u32;
+		let mut k = 0;
+		while k < 8 {
+			c = if c & 1 != 0 {
+				0xedb88320 ^ (c >> 1)
+			} else {
+				c >> 1
+			};
+			k += 1;
+		}
+		table[n] = c;
+		n += 1;
+	}
+	table
+};
+
+pub fn crc32(bytes: &[u8]) -> u32 {
+	!bytes.iter().fold(!0u32, |c, &b| {
+		CRC_TABLE[((c ^ b as u32) & 0xff) as usize] ^ (c >> 8)
+	})
+}
+
+pub fn adler32(bytes: &[u8]) -> u32 {
+	let (mut a, mut b) = (1u32, 0u32);
+	// 5552 is the largest run that cannot overflow before the modulo.
+	for chunk in bytes.chunks(5552) {
+		for &x in chunk {
+			a += x as u32;
+			b += a;
+		}
+		a %= 65521;
+		b %= 65521;
+	}
+	(b << 16) | a
+}
+
+fn zlib_stored(data: &[u8]) -> Vec<u8> {
+	let mut out = vec![0x78, 0x01];
+	let mut blocks = data.chunks(0xffff).peekable();
+	if blocks.peek().is_none() {
+		out.extend_from_slice(&[1, 0, 0, 0xff, 0xff]);
+	}
+	while let Some(block) = blocks.next() {
+		let len = block.len() as u16;
+		out.push(blocks.peek().is_none() as u8);
+		out.extend_from_slice(&len.to_le_bytes());
+		out.extend_from_slice(&(!len).to_le_bytes());
+		out.extend_from_slice(block);
+	}
+	out.extend_from_slice(&adler32(data).to_be_bytes());
+	out
+}
+
+fn png_chunk(out: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+	out.extend_from_slice(&(data.len() as u32).to_be_bytes());
+	let start = out.len();
+	out.extend_from_slice(kind);
+	out.extend_from_slice(data);
+	let crc = crc32(&out[start..]);
+	out.extend_from_slice(&crc.to_be_bytes());
+}
+
+/// Encode top-row-first RGBA8 pixels as a PNG file.
+pub fn encode_png(width: u32, height: u32, rgba: &[u8]) -> Vec<u8> {
+	assert_eq!(
+		rgba.len(),
+		(width * height * 4) as usize,
+		"RGBA8 size mismatch"
+	);
+	let mut ihdr = Vec::with_capacity(13);
+	ihdr.extend_from_slice(&width.to_be_bytes());
+	ihdr.extend_from_slice(&height.to_be_bytes());
+	// 8 bits per channel, RGBA, deflate, adaptive filtering, no interlace.
+	ihdr.extend_from_slice(&[8, 6, 0, 0, 0]);
+	// Every scanline uses filter type 0 (None).
+	let mut raw = Vec::with_capacity(rgba.len() + height as usize);
+	if width > 0 {
+		for row in rgba.chunks(width as usize * 4) {
+			raw.push(0);
+			raw.extend_from_slice(row);
+		}
+	}
+	let mut out = b"\x89PNG\r\n\x1a\n".to_vec();
+	png_chunk(&mut out, b"IHDR", &ihdr);
+	png_chunk(&mut out, b"IDAT", &zlib_stored(&raw));
+	png_chunk(&mut out, b"IEND", &[]);
+	out
+}
+
+// Reads deflate's little-endian bit stream.
+struct BitReader<'a> {
+	bytes: &'a [u8],
+	pos: usize,
+	buffer: u32,
+	count: u32,
+}
+
+impl BitReader<'_> {
+	fn bits(&mut self, n: u32) -> io::Result<u32> {
+		while self.count < n {
+			let byte = *self
+				.bytes
+				.get(self.pos)
+				.ok_or_else(|| invalid("Truncated deflate stream."))?;
+			self.buffer |= (byte as u32) << self.count;
+			self.pos += 1;
+			self.count += 8;
+		}
+		let value = self.buffer & ((1u64 << n) - 1) as u32;
+		self.buffer >>= n;
+		self.count -= n;
+		Ok(value)
+	}
+}
+
+// A canonical Huffman code as the number of codes of each length and the
+// symbols in code order.
+struct Huffman {
+	counts: [u16; 16],
+	symbols: Vec<u16>,
+}
+
+impl Huffman {
+	fn new(lengths: &[u8]) -> Self {
+		let mut counts = [0u16; 16];
+		for &l in lengths {
+			counts[l as usize] += 1;
+		}
+		counts[0] = 0;
+		let mut offsets = [0u16; 16];
+		for l in 1..15 {
+			offsets[l + 1] = offsets[l] + counts[l];
+		}
+		let mut symbols = vec![0; lengths.len()];
+		for (symbol, &l) in lengths.iter().enumerate() {
+			if l != 0 {
+				symbols[offsets[l as usize] as usize] = symbol as u16;
+				offsets[l as usize] += 1;
+			}
+		}
+		Self { counts, symbols }
+	}
+
+	fn decode(&self, r: &mut BitReader) -> io::Result<u16> {
+		let (mut code, mut first, mut index) = (0i32, 0i32, 0i32);
+		for &count in &self.counts[1..] {
+			code |= r.bits(1)? as i32;
+			let count = count as i32;
+			if code - first < count {
+				return Ok(self.symbols[(index + code - first) as usize]);
+			}
+			index += count;
+			first = (first + count) << 1;
+			code <<= 1;
+		}
+		Err(invalid("Bad Huffman code."))
+	}
+}
+
+const LENGTH_BASE: [u16; 29] = [
+	3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131,
+	163, 195, 227, 258,
+];
+const LENGTH_EXTRA: [u8; 29] = [
+	0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+];
+const DISTANCE_BASE: [u16; 30] = [
+	1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537,
+	2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577,
+];
+const DISTANCE_EXTRA: [u8; 30] = [
+	0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13,
+	13,
+];
+
+// Decompress a raw deflate stream (RFC 1951).
+fn inflate(bytes: &[u8]) -> io::Result<Vec<u8>> {
+	let mut r = BitReader {
+		bytes,
+		pos: 0,
+		buffer: 0,
+		count: 0,
+	};
+	let mut out = Vec::new();
+	loop {
+		let last = r.bits(1)? == 1;
+		match r.bits(2)? {
+			0 => {
+				// Stored blocks start on a byte boundary.
+				r.buffer = 0;
+				r.count = 0;
+				let header = bytes
+					.get(r.pos..r.pos + 4)
+					.ok_or_else(|| invalid("Truncated deflate stream."))?;
+				let len = u16::from_le_bytes([header[0], header[1]]);
+				if len != !u16::from_le_bytes([header[2], header[3]]) {
+					return Err(invalid("Bad stored block length."));
+				}
+				let start = r.pos + 4;
+				let block = bytes
+					.get(start..start + len as usize)
+					.ok_or_else(|| invalid("Truncated deflate stream."))?;
+				out.extend_from_slice(block);
+				r.pos = start + len as usize;
+			}
+			1 => {
+				let mut lengths = [8u8; 288];
+				lengths[144..256].fill(9);
+				lengths[256..280].fill(7);
+				inflate_block(
+					&mut r,
+					&mut out,
+					&Huffman::new(&lengths),
+					&Huffman::new(&[5; 30]),
+				)?;
+			}
+			2 => {
+				let literals = r.bits(5)? as usize + 257;
+				let distances = r.bits(5)? as usize + 1;
+				let codes = r.bits(4)? as usize + 4;
+				const ORDER: [usize; 19] = [
+					16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15,
+				];
+				let mut code_lengths = [0u8; 19];
+				for &i in &ORDER[..codes] {
+					code_lengths[i] = r.bits(3)? as u8;
+				}
+				let code = Huffman::new(&code_lengths);
+				let mut lengths = Vec::with_capacity(literals + distances);
+				while lengths.len() < literals + distances {
+					let (value, repeat) = match code.decode(&mut r)? {
+						symbol @ 0..=15 => (symbol as u8, 1),
+						16 => {
+							let previous = *lengths
+								.last()
+								.ok_or_else(|| invalid("Repeat with no previous length."))?;
+							(previous, 3 + r.bits(2)?)
+						}
+						17 => (0, 3 + r.bits(3)?),
+						_ => (0, 11 + r.bits(7)?),
+					};
+					lengths.extend(std::iter::repeat_n(value, repeat as usize));
+				}
+				if lengths.len() > literals + distances {
+					return Err(invalid("Too many code lengths."));
+				}
+				let (lit, dist) = lengths.split_at(literals);
+				inflate_block(&mut r, &mut out, &Huffman::new(lit), &Huffman::new(dist))?;
+			}
+			_ => return Err(invalid("Bad deflate block type.")),
+		}
+		if last {
+			return Ok(out);
+		}
+	}
+}
+
+fn inflate_block(
+	r: &mut BitReader,
+	out: &mut Vec<u8>,
+	literals: &Huffman,
+	distances: &Huffman,
+) -> io::Result<()> {
+	loop {
+		let symbol = literals.decode(r)? as usize;
+		match symbol {
+			0..=255 => out.push(symbol as u8),
+			256 => return Ok(()),
+			257..=285 => {
+				let i = symbol - 257;
+				let len = LENGTH_BASE[i] as usize + r.bits(LENGTH_EXTRA[i] as u32)? as usize;
+				let d = distances.decode(r)? as usize;
+				if d >= 30 {
+					return Err(invalid("Bad deflate distance."));
+				}
+				let distance =
+					DISTANCE_BASE[d] as usize + r.bits(DISTANCE_EXTRA[d] as u32)? as usize;
+				if distance > out.len() {
+					return Err(invalid("Deflate distance before the start."));
+				}
+				// Copies may overlap their own output.
+				let start = out.len() - distance;
+				for k in 0..len {
+					out.push(out[start + k]);
+				}
+			}
+			_ => return Err(invalid("Bad deflate length code.")),
+		}
+	}
+}
+
+// The payload of a zlib stream, checked against its Adler-32.
+fn zlib_decompress(bytes: &[u8]) -> io::Result<Vec<u8>> {
+	if bytes.len() < 6
+		|| bytes[0] & 0x0f != 8
+		|| !u16::from_be_bytes([bytes[0], bytes[1]]).is_multiple_of(31)
+		|| bytes[1] & 0x20 != 0
+	{
+		return Err(invalid("Bad zlib header."));
+	}
+	let data = inflate(&bytes[2..])?;
+	let checksum = &bytes[bytes.len() - 4..];
+	if adler32(&data).to_be_bytes() != checksum {
+		return Err(invalid("zlib checksum mismatch."));
+	}
+	Ok(data)
+}
+
+/// A decoded PNG as top-row-first RGBA8.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Png {
+	pub width: u32,
+	pub height: u32,
+	pub rgba: Vec<u8>,
+}
+
+/// Read a non-interlaced PNG of any color type with 8-bit channels, or 16-bit
+/// ones, which are rounded down to 8.
+pub fn decode_png(bytes: &[u8]) -> io::Result<Png> {
+	if !bytes.starts_with(b"\x89PNG\r\n\x1a\n") {
+		return Err(invalid("Not a PNG file."));
+	}
+	let mut pos = 8;
+	let (mut header, mut palette, mut alpha, mut idat) = (None, Vec::new(), Vec::new(), Vec::new());
+	while pos + 8 <= bytes.len() {
+		let len = u32::from_be_bytes(bytes[pos..pos + 4].try_into().unwrap()) as usize;
+		let kind = &bytes[pos + 4..pos + 8];
+		let data = bytes
+			.get(pos + 8..pos + 8 + len)
+			.ok_or_else(|| invalid("Truncated PNG chunk."))?;
+		match kind {
+			b"IHDR" if len == 13 => header = Some(data),
+			b"PLTE" => palette = data.to_vec(),
+			b"tRNS" => alpha = data.to_vec(),
+			b"IDAT" => idat.extend_from_slice(data),
+			b"IEND" => break,
+			_ => {}
+		}
+		pos += 12 + len;
+	}
+	let header = header.ok_or_else(|| invalid("Missing IHDR."))?;
+	let width = u32::from_be_bytes(header[0..4].try_into().unwrap());
+	let height = u32::from_be_bytes(header[4..8].try_into().unwrap());
+	if width == 0 || height == 0 {
+		return Err(invalid("PNG dimensions must not be zero."));
+	}
+	let (depth, color, interlace) = (header[8], header[9], header[12]);
+	let channels = match color {
+		0 | 3 => 1,
+		2 => 3,
+		4 => 2,
+		6 => 4,
+		_ => return Err(invalid("Bad PNG color type.")),
+	};
+	let sample = match (depth, color) {
+		(8, _) => 1,
+		(16, 0 | 2 | 4 | 6) => 2,
+		_ => return Err(invalid("Only 8- and 16-bit PNG channels are supported.")),
+	};
+	if interlace != 0 {
+		return Err(invalid("Interlaced PNG is not supported."));
+	}
+	let bpp = channels * sample;
+	// The filtered rows are the largest buffer; at 8 bytes per pixel they
+	// bound the RGBA output too.
+	let row = (width as usize)
+		.checked_mul(bpp)
+		.filter(|row| (row + 1).checked_mul(height as usize).is_some())
+		.ok_or_else(|| invalid("PNG dimensions are too large."))?;
+	let raw = zlib_decompress(&idat)?;
+	if raw.len() < (row + 1) * height as usize {
+		return Err(invalid("PNG image data is too short."));
+	}
+	// Undo the per-row filters against the previous unfiltered row.
+	let mut pixels = vec![0u8; row * height as usize];
+	for y in 0..height as usize {
+		let filter = raw[y * (row + 1)];
+		let line = &raw[y * (row + 1) + 1..(y + 1) * (row + 1)];
+		let (done, rest) = pixels.split_at_mut(y * row);
+		let up = if y > 0 {
+			&done[(y - 1) * row..]
+		} else {
+			&[][..]
+		};
+		let current = &mut rest[..row];
+		for x in 0..row {
+			let a = if x >= bpp { current[x - bpp] as i16 } else { 0 };
+			let b = up.get(x).map_or(0, |&b| b as i16);
+			let c = if x >= bpp {
+				up.get(x - bpp).map_or(0, |&c| c as i16)
+			} else {
+				0
+			};
+			let predictor = match filter {
+				0 => 0,
+				1 => a,
+				2 => b,
+				3 => (a + b) / 2,
+				4 => {
+					let p = a + b - c;
+					let (pa, pb, pc) = ((p - a).abs(), (p - b).abs(), (p - c).abs());
+					if pa <= pb && pa <= pc {
+						a
+					} else if pb <= pc {
+						b
+					} else {
+						c
+					}
+				}
+				_ => return Err(invalid("Bad PNG filter type.")),
+			};
+			current[x] = line[x].wrapping_add(predictor as u8);
+		}
+	}
+	// Keep the high byte of 16-bit samples and expand to RGBA.
+	let mut rgba = Vec::with_capacity(width as usize * height as usize * 4);
+	for pixel in pixels.chunks_exact(bpp) {
+		let s = |i: usize| pixel[i * sample];
+		match color {
+			0 => rgba.extend_from_slice(&[s(0), s(0), s(0), 255]),
+			2 => rgba.extend_from_slice(&[s(0), s(1), s(2), 255]),
+			3 => {
+				let i = s(0) as usize;
+				let rgb = palette
+					.get(i * 3..i * 3 + 3)
+					.ok_or_else(|| invalid("PNG palette index out of range."))?;
+				rgba.extend_from_slice(rgb);
+				rgba.push(alpha.get(i).copied().unwrap_or(255));
+			}
+			4 => rgba.extend_from_slice(&[s(0), s(0), s(0), s(1)]),
+			_ => rgba.extend_from_slice(&[s(0), s(1), s(2), s(3)]),
+		}
+	}
+	Ok(Png {
+		width,
+		height,
+		rgba,
+	})
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// PPM / PGM
+////////////////////////////////////////////////////////////////////////////////
+
+/// Binary PPM (P6) from top-row-first RGB8 pixels.
+pub fn encode_ppm(width: u32, height: u32, rgb: &[u8]) -> Vec<u8> {
+	assert_eq!(
+		rgb.len(),
+		(width * height * 3) as usize,
+		"RGB8 size mismatch"
+	);
+	let mut out = format!("P6\n{width} {height}\n255\n").into_bytes();
+	out.extend_from_slice(rgb);
+	out
+}
+
+/// Binary PGM (P5) from top-row-first 8-bit gray pixels.
+pub fn encode_pgm(width: u32, height: u32, gray: &[u8]) -> Vec<u8> {
+	assert_eq!(gray.len(), (width * height) as usize, "gray8 size mismatch");
+	let mut out = format!("P5\n{width} {height}\n255\n").into_bytes();
+	out.extend_from_slice(gray);
+	out
+}
+
+/// An 8-bit PNM image; `channels` is 1 for PGM and 3 for PPM.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Pnm {
+	pub width: u32,
+	pub height: u32,
+	pub channels: u32,
+	pub data: Vec<u8>,
+}
+
+fn invalid(message: &str) -> io::Error {
+	io::Error::new(io::ErrorKind::InvalidData, message.to_string())
+}
+
+/// Read a binary PGM (P5) or PPM (P6) with a maxval of 255.
+pub fn decode_pnm(bytes: &[u8]) -> io::Result<Pnm> {
+	let mut pos = 0;
+	let mut fields = Vec::new();
+	while fields.len() < 4 {
+		// Whitespace and comments separate header fields.
+		while pos < bytes.len() && (bytes[pos].is_ascii_whitespace() || bytes[pos] == b'#') {
+			if bytes[pos] == b'#' {
+				while pos < bytes.len() && bytes[pos] != b'\n' {
+					pos += 1;
+				}
+			} else {
+				pos += 1;
+			}
+		}
+		let start = pos;
+		while pos < bytes.len() && !bytes[pos].is_ascii_whitespace() {
+			pos += 1;
+		}
+		if start == pos {
+			return Err(invalid("Truncated PNM header."));
+		}
+		fields
+			.push(std::str::from_utf8(&bytes[start..pos]).map_err(|_| invalid("Bad PNM header."))?);
+	}
+	// Exactly one whitespace byte follows maxval.
+	pos += 1;
+	let channels = match fields[0] {
+		"P5" => 1,
+		"P6" => 3,
+		_ => return Err(invalid("Only binary PGM (P5) and PPM (P6) are supported.")),
+	};
+	let number = |s: &str| s.parse::<u32>().map_err(|_| invalid("Bad PNM dimension."));
+	let (width, height) = (number(fields[1])?, number(fields[2])?);
+	if number(fields[3])? != 255 {
+		return Err(invalid("Only 8-bit PNM is supported."));
+	}
+	let end = (width as usize)
+		.checked_mul(height as usize)
+		.and_then(|n| n.checked_mul(channels as usize))
+		.and_then(|size| size.checked_add(pos))
+		.ok_or_else(|| invalid("PNM dimensions are too large."))?;
+	let data = bytes
+		.get(pos..end)
+		.ok_or_else(|| invalid("Truncated PNM data."))?;
+	Ok(Pnm {
+		width,
+		height,
+		channels,
+		data: data.to_vec(),
+	})
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// Files
+////////////////////////////////////////////////////////////////////////////////
+
+fn write_file(path: &Path, bytes: &[u8]) -> io::Result<()> {
+	let mut file = BufWriter::new(File::create(path)?);
+	file.write_all(bytes)?;
+	file.flush()
+}
+
+pub fn load_pnm(path: impl AsRef<Path>) -> io::Result<Pnm> {
+	decode_pnm(&std::fs::read(path)?)
+}
+
+pub fn load_png(path: impl AsRef<Path>) -> io::Result<Png> {
+	decode_png(&std::fs::read(path)?)
+}
+
+pub fn save_png(fb: &Framebuffer, path: impl AsRef<Path>) -> io::Result<()> {
+	let png = encode_png(fb.width(), fb.height(), &framebuffer_rgba8(fb));
+	write_file(path.as_ref(), &png)
+}
+
+pub fn save_ppm(fb: &Framebuffer, path: impl AsRef<Path>) -> io::Result<()> {
+	let ppm = encode_ppm(fb.width(), fb.height(), &framebuffer_rgb8(fb));
+	write_file(path.as_ref(), &ppm)
+}
+
+/// Save the depth buffer visualisation from [`depth_gray8`].
+pub fn save_depth_pgm(fb: &Framebuffer, path: impl AsRef<Path>) -> io::Result<()> {
+	let pgm = encode_pgm(fb.width(), fb.height(), &depth_gray8(fb));
+	write_file(path.as_ref(), &pgm)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::math::Vec4;
+
+	#[test]
+	fn checksums() {
+		assert_eq!(crc32(b"IEND"), 0xae426082);
+		assert_eq!(crc32(b"123456789"), 0xcbf43926);
+		assert_eq!(adler32(b"Wikipedia"), 0x11e60398);
+		// Long enough to need the periodic modulo.
+		assert_eq!(adler32(&vec![0xff; 100_000]), 0x149a302c);
+	}
+
+	#[test]
+	fn png_layout() {
+		let png = encode_png(2, 1, &[255, 0, 0, 255, 0, 255, 0, 128]);
+		assert_eq!(&png[..8], b"\x89PNG\r\n\x1a\n");
+		assert_eq!(&png[12..16], b"IHDR");
+		assert_eq!(&png[16..24], &[0, 0, 0, 2, 0, 0, 0, 1]);
+		assert_eq!(&png[png.len() - 12..], b"\0\0\0\0IEND\xae\x42\x60\x82");
+		// IDAT: zlib header, one final stored block of 1 + 8 bytes.
+		let idat = &png[33 + 8..];
+		assert_eq!(&idat[..7], &[0x78, 0x01, 1, 9, 0, 0xf6, 0xff]);
+		assert_eq!(&idat[7..16], &[0, 255, 0, 0, 255, 0, 255, 0, 128]);
+	}
+
+	#[test]
+	fn stored_blocks_split() {
+		let data = vec![7; 0x10000 + 10];
+		let z = zlib_stored(&data);
+		assert_eq!(z.len(), 2 + 2 * 5 + data.len() + 4);
+		assert_eq!(z[2], 0);
+		assert_eq!(z[2 + 5 + 0xffff], 1);
+	}
+
+	fn hex(s: &str) -> Vec<u8> {
+		(0..s.len())
+			.step_by(2)
+			.map(|i| u8::from_str_radix(&s[i..i + 2], 16).unwrap())
+			.collect()
+	}
+
+	// Streams from Python's `zlib.compress(data, 9)`.
+	#[test]
+	fn inflate_huffman_blocks() {
+		let fixed = hex("78dacbcd2c2e06a182824c85dc11c04e4acc03424a290009e17a4f");
+		let text = [&b"mississippi ".repeat(20)[..], &b"banana ".repeat(10)].concat();
+		assert_eq!(zlib_decompress(&fixed).unwrap(), text);
+		let dynamic = hex(concat!(
+			"78da1d8edb0d803010c316f207f7beeebf18692554014d1c7f18c9c11a2fa2c943276b049ee430",
+			"8e13463b4789603ecca9e028adae007a4fea63e9c2875d2a71674513aa30639b566309c794e404",
+			"5bcc6174c67bdea77eeaaa6f4851155453d91e48384185d6806634a6c9bde352b822f6a4fa0a4a",
+			"53b252fe011dac2cab"
+		));
+		let squares: String = (0..80).map(|i| format!("{},", i * i % 97)).collect();
+		assert_eq!(zlib_decompress(&dynamic).unwrap(), squares.as_bytes());
+		assert_eq!(zlib_decompress(&zlib_stored(&text)).unwrap(), text);
+		let mut corrupt = dynamic.clone();
+		corrupt[40] ^= 0x10;
+		assert!(zlib_decompress(&corrupt).is_err());
+		assert!(zlib_decompress(&dynamic[..60]).is_err());
+	}
+
+	#[test]
+	fn png_round_trip_and_filters() {
+		let rgba: Vec<u8> = (0..5 * 3 * 4).map(|i| (i * 37 % 256) as u8).collect();
+		let png = decode_png(&encode_png(5, 3, &rgba)).unwrap();
+		assert_eq!((png.width, png.height), (5, 3));
+		assert_eq!(png.rgba, rgba);
+		// RGB rows filtered with Sub, Paeth and Average.
+		let filtered = hex(concat!(
+			"89504e470d0a1a0a0000000d4948445200000003000000030802000000d94a22e8000000234944",
+			"415478da636438c1c8f5880188588c1818b818d88188d93b8551ee23b7dc473e00553d05e5f961",
+			"40530000000049454e44ae426082"
+		));
+		let png = decode_png(&filtered).unwrap();
+		for y in 0..3u32 {
+			for x in 0..3u32 {
+				let i = (y * 3 + x) as usize * 4;
+				let expected = [10 * x + 50 * y, 200 - 30 * x, 7 * x * y + 1, 255];
+				assert_eq!(png.rgba[i..i + 4], expected.map(|c| c as u8));
+			}
+		}
+		assert!(decode_png(&filtered[..50]).is_err());
+		assert!(decode_png(b"GIF89a").is_err());
+		// A 16-bit RGBA header whose rows would overflow, checked before the
+		// image data is inflated.
+		let mut huge = encode_png(1, 1, &[0; 4]);
+		huge[16..24].fill(0xff);
+		huge[24] = 16;
+		let err = decode_png(&huge).unwrap_err();
+		assert_eq!(err.to_string(), "PNG dimensions are too large.");
+		let mut empty = encode_png(1, 1, &[0; 4]);
+		empty[16..20].fill(0);
+		let err = decode_png(&empty).unwrap_err();
+		assert_eq!(err.to_string(), "PNG dimensions must not be zero.");
+	}
+
+	#[test]
+	fn pnm() {
+		let mut fb = Framebuffer::new(2, 2);
+		fb.clear_color(Vec4::new(1.0, 0.5, 0.0, 1.0));
+		let ppm = encode_ppm(2, 2, &framebuffer_rgb8(&fb));
+		assert!(ppm.starts_with(b"P6\n2 2\n255\n"));
+		assert_eq!(&ppm[11..14], &[255, 128, 0]);
+		assert_eq!(depth_gray8(&fb), vec![255; 4]);
+		let pnm = decode_pnm(&ppm).unwrap();
+		assert_eq!((pnm.width, pnm.height, pnm.channels), (2, 2, 3));
+		assert_eq!(pnm.data, framebuffer_rgb8(&fb));
+		let commented = b"P5 # gray\n1 1\n255\n\x7f";
+		assert_eq!(decode_pnm(commented).unwrap().data, vec![0x7f]);
+		let err = decode_pnm(b"P6\n2 2\n255\n\0").unwrap_err();
+		assert_eq!(err.to_string(), "Truncated PNM data.");
+		let err = decode_pnm(b"P6 65536 65536 255\n").unwrap_err();
+		assert_eq!(err.to_string(), "Truncated PNM data.");
+		assert!(decode_pnm(b"P6 4294967295 4294967295 255\n").is_err());
+	}
+}
@@ -0,0 +1,459 @@
+////////////////////////////////////////////////////////////////////////////////
+// JSON
+////////////////////////////////////////////////////////////////////////////////
+//
+// Enough JSON for glTF: a value tree that keeps object keys in file order, a
+// strict RFC 8259 parser and a compact writer.
+
+use std::fmt;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Json {
+	Null,
+	Bool(bool),
+	Number(f64),
+	String(String),
+	Array(Vec<Json>),
+	Object(Vec<(String, Json)>),
+}
+
+impl Json {
+	/// The member `key` of an object.
+	pub fn get(&self, key: &str) -> Option<&Json> {
+		match self {
+			Json::Object(members) => members.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+			_ => None,
+		}
+	}
+
+	pub fn as_f64(&self) -> Option<f64> {
+		match *self {
+			Json::Number(n) => Some(n),
+			_ => None,
+		}
+	}
+
+	/// A number that is a non-negative integer.
+	pub fn as_usize(&self) -> Option<usize> {
+		self.as_f64()
+			.filter(|n| *n >= 0.0 && n.fract() == 0.0 && *n <= u32::MAX as f64)
+			.map(|n| n as usize)
+	}
+
+	pub fn as_bool(&self) -> Option<bool> {
+		match *self {
+			Json::Bool(b) => Some(b),
+			_ => None,
+		}
+	}
+
+	pub fn as_str(&self) -> Option<&str> {
+		match self {
+			Json::String(s) => Some(s),
+			_ => None,
+		}
+	}
+
+	pub fn as_array(&self) -> Option<&[Json]> {
+		match self {
+			Json::Array(a) => Some(a),
+			_ => None,
+		}
+	}
+
+	/// An array of numbers as `f32`.
+	pub fn as_f32s(&self) -> Option<Vec<f32>> {
+		self.as_array()?
+			.iter()
+			.map(|v| v.as_f64().map(|n| n as f32))
+			.collect()
+	}
+
+	/// Append `key: value` to an object; does nothing to other values.
+	pub fn push(&mut self, key: &str, value: impl Into<Json>) {
+		if let Json::Object(members) = self {
+			members.push((key.to_string(), value.into()));
+		}
+	}
+
+	pub fn parse(text: &str) -> Result<Json, JsonError> {
+		let mut parser = Parser {
+			bytes: text.as_bytes(),
+			pos: 0,
+		};
+		let value = parser.value(0)?;
+		parser.whitespace();
+		if parser.pos != parser.bytes.len() {
+			return Err(parser.error("Trailing characters"));
+		}
+		Ok(value)
+	}
+}
+
+impl From<bool> for Json {
+	fn from(b: bool) -> Self {
+		Json::Bool(b)
+	}
+}
+
+impl From<f32> for Json {
+	/// The shortest decimal that reads back as `n`, so 0.1 is written as 0.1
+	/// rather than 0.10000000149011612.
+	fn from(n: f32) -> Self {
+		Json::Number(n.to_string().parse().unwrap_or(n as f64))
+	}
+}
+
+impl From<usize> for Json {
+	fn from(n: usize) -> Self {
+		Json::Number(n as f64)
+	}
+}
+
+impl From<&str> for Json {
+	fn from(s: &str) -> Self {
+		Json::String(s.to_string())
+	}
+}
+
+impl From<String> for Json {
+	fn from(s: String) -> Self {
+		Json::String(s)
+	}
+}
+
+impl<T: Into<Json>> From<Vec<T>> for Json {
+	fn from(v: Vec<T>) -> Self {
+		Json::Array(v.into_iter().map(Into::into).collect())
+	}
+}
+
+/// Where and why [`Json::parse`] stopped.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct JsonError {
+	pub offset: usize,
+	pub message: &'static str,
+}
+
+impl fmt::Display for JsonError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "{} at byte {}.", self.message, self.offset)
+	}
+}
+
+impl std::error::Error for JsonError {}
+
+// Deeper nesting than any glTF file needs, and shallow enough not to overflow
+// the stack.
+const MAX_DEPTH: usize = 128;
+
+struct Parser<'a> {
+	bytes: &'a [u8],
+	pos: usize,
+}
+
+impl Parser<'_> {
+	fn error(&self, message: &'static str) -> JsonError {
+		JsonError {
+			offset: self.pos,
+			message,
+		}
+	}
+
+	fn whitespace(&mut self) {
+		while matches!(self.bytes.get(self.pos), Some(b' ' | b'\t' | b'\n' | b'\r')) {
+			self.pos += 1;
+		}
+	}
+
+	fn literal(&mut self, word: &str, value: Json) -> Result<Json, JsonError> {
+		if self.bytes[self.pos..].starts_with(word.as_bytes()) {
+			self.pos += word.len();
+			Ok(value)
+		} else {
+			Err(self.error("Unexpected character"))
+		}
+	}
+
+	fn value(&mut self, depth: usize) -> Result<Json, JsonError> {
+		if depth > MAX_DEPTH {
+			return Err(self.error("Nesting too deep"));
+		}
+		self.whitespace();
+		match self.bytes.get(self.pos) {
+			None => Err(self.error("Unexpected end")),
+			Some(b'n') => self.literal("null", Json::Null),
+			Some(b't') => self.literal("true", Json::Bool(true)),
+			Some(b'f') => self.literal("false", Json::Bool(false)),
+			Some(b'"') => self.string().map(Json::String),
+			Some(b'[') => {
+				self.pos += 1;
+				let mut items = Vec::new();
+				self.whitespace();
+				if self.bytes.get(self.pos) == Some(&b']') {
+					self.pos += 1;
+					return Ok(Json::Array(items));
+				}
+				loop {
+					items.push(self.value(depth + 1)?);
+					self.whitespace();
+					match self.bytes.get(self.pos) {
+						Some(b',') => self.pos += 1,
+						Some(b']') => {
+							self.pos += 1;
+							return Ok(Json::Array(items));
+						}
+						_ => return Err(self.error("Expected ',' or ']'")),
+					}
+				}
+			}
+			Some(b'{') => {
+				self.pos += 1;
+				let mut members = Vec::new();
+				self.whitespace();
+				if self.bytes.get(self.pos) == Some(&b'}') {
+					self.pos += 1;
+					return Ok(Json::Object(members));
+				}
+				loop {
+					self.whitespace();
+					if self.bytes.get(self.pos) != Some(&b'"') {
+						return Err(self.error("Expected a key"));
+					}
+					let key = self.string()?;
+					self.whitespace();
+					if self.bytes.get(self.pos) != Some(&b':') {
+						return Err(self.error("Expected ':'"));
+					}
+					self.pos += 1;
+					members.push((key, self.value(depth + 1)?));
+					self.whitespace();
+					match self.bytes.get(self.pos) {
+						Some(b',') => self.pos += 1,
+						Some(b'}') => {
+							self.pos += 1;
+							return Ok(Json::Object(members));
+						}
+						_ => return Err(self.error("Expected ',' or '}'")),
+					}
+				}
+			}
+			Some(b'-' | b'0'..=b'9') => self.number(),
+			Some(_) => Err(self.error("Unexpected character")),
+		}
+	}
+
+	fn number(&mut self) -> Result<Json, JsonError> {
+		let start = self.pos;
+		let digits = |p: &mut Self| {
+			let from = p.pos;
+			while matches!(p.bytes.get(p.pos), Some(b'0'..=b'9')) {
+				p.pos += 1;
+			}
+			p.pos > from
+		};
+		if self.bytes.get(self.pos) == Some(&b'-') {
+			self.pos += 1;
+		}
+		if self.bytes.get(self.pos) == Some(&b'0') {
+			self.pos += 1;
+		} else if !digits(self) {
+			return Err(self.error("Expected a digit"));
+		}
+		if self.bytes.get(self.pos) == Some(&b'.') {
+			self.pos += 1;
+			if !digits(self) {
+				return Err(self.error("Expected a digit"));
+			}
+		}
+		if matches!(self.bytes.get(self.pos), Some(b'e' | b'E')) {
+			self.pos += 1;
+			if matches!(self.bytes.get(self.pos), Some(b'+' | b'-')) {
+				self.pos += 1;
+			}
+			if !digits(self) {
+				return Err(self.error("Expected a digit"));
+			}
+		}
+		// Only ASCII was consumed.
+		let text = std::str::from_utf8(&self.bytes[start..self.pos]).unwrap();
+		text.parse()
+			.map(Json::Number)
+			.map_err(|_| self.error("Bad number"))
+	}
+
+	fn hex4(&mut self) -> Result<u32, JsonError> {
+		let digits = self
+			.bytes
+			.get(self.pos..self.pos + 4)
+			.and_then(|d| std::str::from_utf8(d).ok())
+			.and_then(|d| u32::from_str_radix(d, 16).ok())
+			.ok_or_else(|| self.error("Bad \\u escape"))?;
+		self.pos += 4;
+		Ok(digits)
+	}
+
+	fn string(&mut self) -> Result<String, JsonError> {
+		self.pos += 1;
+		let mut out = String::new();
+		loop {
+			let start = self.pos;
+			while !matches!(
+				self.bytes.get(self.pos),
+				None | Some(b'"' | b'\\' | 0..=0x1f)
+			) {
+				self.pos += 1;
+			}
+			// The input is a `str` and the run stops at ASCII, so it is UTF-8.
+			out.push_str(std::str::from_utf8(&self.bytes[start..self.pos]).unwrap());
+			match self.bytes.get(self.pos) {
+				Some(b'"') => {
+					self.pos += 1;
+					return Ok(out);
+				}
+				Some(b'\\') => {
+					self.pos += 1;
+					let escape = self.bytes.get(self.pos).copied();
+					self.pos += 1;
+					match escape {
+						Some(b'"') => out.push('"'),
+						Some(b'\\') => out.push('\\'),
+						Some(b'/') => out.push('/'),
+						Some(b'b') => out.push('\u{8}'),
+						Some(b'f') => out.push('\u{c}'),
+						Some(b'n') => out.push('\n'),
+						Some(b'r') => out.push('\r'),
+						Some(b't') => out.push('\t'),
+						Some(b'u') => {
+							let mut c = self.hex4()?;
+							// A high surrogate must pair with a low one.
+							if (0xd800..0xdc00).contains(&c) {
+								if !self.bytes[self.pos..].starts_with(b"\\u") {
+									return Err(self.error("Unpaired surrogate"));
+								}
+								self.pos += 2;
+								let low = self.hex4()?;
+								if !(0xdc00..0xe000).contains(&low) {
+									return Err(self.error("Unpaired surrogate"));
+								}
+								c = 0x10000 + ((c - 0xd800) << 10) + (low - 0xdc00);
+							}
+							out.push(
+								char::from_u32(c)
+									.ok_or_else(|| self.error("Unpaired surrogate"))?,
+							);
+						}
+						_ => return Err(self.error("Bad escape")),
+					}
+				}
+				None => return Err(self.error("Unterminated string")),
+				Some(_) => return Err(self.error("Control character in string")),
+			}
+		}
+	}
+}
+
+fn write_string(f: &mut fmt::Formatter<'_>, s: &str) -> fmt::Result {
+	f.write_str("\"")?;
+	for c in s.chars() {
+		match c {
+			'"' => f.write_str("\\\"")?,
+			'\\' => f.write_str("\\\\")?,
+			'\n' => f.write_str("\\n")?,
+			'\r' => f.write_str("\\r")?,
+			'\t' => f.write_str("\\t")?,
+			c if (c as u32) < 0x20 => write!(f, "\\u{:04x}", c as u32)?,
+			c => write!(f, "{c}")?,
+		}
+	}
+	f.write_str("\"")
+}
+
+/// Compact JSON. Numbers that are not finite have no JSON form and are written
+/// as `null`.
+impl fmt::Display for Json {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Json::Null => f.write_str("null"),
+			Json::Bool(b) => write!(f, "{b}"),
+			Json::Number(n) if n.is_finite() => write!(f, "{n}"),
+			Json::Number(_) => f.write_str("null"),
+			Json::String(s) => write_string(f, s),
+			Json::Array(items) => {
+				f.write_str("[")?;
+				for (i, item) in items.iter().enumerate() {
+					if i > 0 {
+						f.write_str(",")?;
+					}
+					write!(f, "{item}")?;
+				}
+				f.write_str("]")
+			}
+			Json::Object(members) => {
+				f.write_str("{")?;
+				for (i, (key, value)) in members.iter().enumerate() {
+					if i > 0 {
+						f.write_str(",")?;
+					}
+					write_string(f, key)?;
+					write!(f, ":{value}")?;
+				}
+				f.write_str("}")
+			}
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn parse_and_write() {
+		let text = r#" {"asset": {"version": "2.0"}, "n": [0, -1.5, 2e3, 1E-2],
+			"ok": true, "no": null, "s": "a\"\\\/\n\u00e9\ud83d\ude00\t"} "#;
+		let v = Json::parse(text).unwrap();
+		assert_eq!(
+			v.get("asset").and_then(|a| a.get("version")),
+			Some(&Json::from("2.0"))
+		);
+		assert_eq!(
+			v.get("n").and_then(Json::as_f32s),
+			Some(vec![0.0, -1.5, 2000.0, 0.01])
+		);
+		assert_eq!(v.get("ok").and_then(Json::as_bool), Some(true));
+		assert_eq!(v.get("no"), Some(&Json::Null));
+		assert_eq!(v.get("s").and_then(Json::as_str), Some("a\"\\/\né😀\t"));
+		assert_eq!(Json::from(3.0f32).as_usize(), Some(3));
+		assert_eq!(Json::from(-3.0f32).as_usize(), None);
+		// Keys keep their order, and writing parses back to the same value.
+		let written = v.to_string();
+		assert!(written.starts_with(r#"{"asset":{"version":"2.0"},"n":[0,-1.5,2000,0.01]"#));
+		assert_eq!(Json::parse(&written).unwrap(), v);
+		assert_eq!(Json::from(f32::NAN).to_string(), "null");
+	}
+
+	#[test]
+	fn errors() {
+		for bad in [
+			"",
+			"[1,]",
+			"{\"a\" 1}",
+			"01",
+			"1.",
+			"-",
+			"\"abc",
+			"tru",
+			"[1] 2",
+			"\"\\x\"",
+			"\"\\ud83d\"",
+			"\"a\nb\"",
+			"{1: 2}",
+		] {
+			assert!(Json::parse(bad).is_err(), "{bad:?} parsed");
+		}
+		let err = Json::parse("[1, 2 3]").unwrap_err();
+		assert_eq!(err.to_string(), "Expected ',' or ']' at byte 6.");
+		assert!(Json::parse(&"[".repeat(1000)).is_err());
+	}
+}
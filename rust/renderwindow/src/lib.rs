@@ -0,0 +1,22 @@
+//! Rust port of RenderWindow.
+//!
+//! The modules mirror the Python (`python/module_*.py`), WebGL (`mozilla/module_*.js`) and
+//! Metal (`apple/*.swift`) ports so that code can be transcribed between them one-to-one.
+
+pub mod bump;
+pub mod canvas;
+pub mod dual;
+pub mod gltf;
+pub mod golden;
+pub mod image;
+pub mod json;
+pub mod math;
+pub mod mesh;
+pub mod parametric;
+pub mod raster;
+pub mod relief;
+pub mod scene;
+pub mod shader;
+pub mod texture;
+pub mod uniform;
+pub mod vertex;
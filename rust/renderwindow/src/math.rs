@@ -0,0 +1,445 @@
+////////////////////////////////////////////////////////////////////////////////
+// Linear algebra and matrix utilities for 3D graphics
+////////////////////////////////////////////////////////////////////////////////
+//
+// Matrices are row-major and vectors are rows, exactly like `module_matrix.py`
+// and `module_matrix.js`: a point is transformed by `v * m` and transforms are
+// concatenated left to right, so `m * v * p` means model, then view, then
+// projection.
+
+use std::ops::{Add, AddAssign, Div, Index, IndexMut, Mul, Neg, Sub, SubAssign};
+
+////////////////////////////////////////////////////////////////////////////////
+// Vectors
+////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Vec2 {
+	pub x: f32,
+	pub y: f32,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Vec3 {
+	pub x: f32,
+	pub y: f32,
+	pub z: f32,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Vec4 {
+	pub x: f32,
+	pub y: f32,
+	pub z: f32,
+	pub w: f32,
+}
+
+impl Vec2 {
+	pub const fn new(x: f32, y: f32) -> Self {
+		Self { x, y }
+	}
+
+	pub fn dot(self, rhs: Self) -> f32 {
+		self.x * rhs.x + self.y * rhs.y
+	}
+
+	pub fn length(self) -> f32 {
+		self.dot(self).sqrt()
+	}
+
+	pub fn to_array(self) -> [f32; 2] {
+		[self.x, self.y]
+	}
+}
+
+impl Vec3 {
+	pub const fn new(x: f32, y: f32, z: f32) -> Self {
+		Self { x, y, z }
+	}
+
+	pub fn dot(self, rhs: Self) -> f32 {
+		self.x * rhs.x + self.y * rhs.y + self.z * rhs.z
+	}
+
+	pub fn cross(self, rhs: Self) -> Self {
+		Self::new(
+			self.y * rhs.z - self.z * rhs.y,
+			self.z * rhs.x - self.x * rhs.z,
+			self.x * rhs.y - self.y * rhs.x,
+		)
+	}
+
+	pub fn length(self) -> f32 {
+		self.dot(self).sqrt()
+	}
+
+	pub fn normalize(self) -> Self {
+		self * (1.0 / self.length())
+	}
+
+	/// Promote to a homogeneous row vector.
+	pub fn extend(self, w: f32) -> Vec4 {
+		Vec4::new(self.x, self.y, self.z, w)
+	}
+
+	pub fn to_array(self) -> [f32; 3] {
+		[self.x, self.y, self.z]
+	}
+}
+
+impl Vec4 {
+	pub const fn new(x: f32, y: f32, z: f32, w: f32) -> Self {
+		Self { x, y, z, w }
+	}
+
+	pub fn dot(self, rhs: Self) -> f32 {
+		self.x * rhs.x + self.y * rhs.y + self.z * rhs.z + self.w * rhs.w
+	}
+
+	pub fn xyz(self) -> Vec3 {
+		Vec3::new(self.x, self.y, self.z)
+	}
+
+	pub fn to_array(self) -> [f32; 4] {
+		[self.x, self.y, self.z, self.w]
+	}
+}
+
+macro_rules! impl_vector_ops {
+	($t:ident { $($f:ident),+ }) => {
+		impl Add for $t {
+			type Output = Self;
+			fn add(self, rhs: Self) -> Self {
+				Self { $($f: self.$f + rhs.$f),+ }
+			}
+		}
+
+		impl AddAssign for $t {
+			fn add_assign(&mut self, rhs: Self) {
+				$(self.$f += rhs.$f;)+
+			}
+		}
+
+		impl Sub for $t {
+			type Output = Self;
+			fn sub(self, rhs: Self) -> Self {
+				Self { $($f: self.$f - rhs.$f),+ }
+			}
+		}
+
+		impl SubAssign for $t {
+			fn sub_assign(&mut self, rhs: Self) {
+				$(self.$f -= rhs.$f;)+
+			}
+		}
+
+		impl Mul<f32> for $t {
+			type Output = Self;
+			fn mul(self, rhs: f32) -> Self {
+				Self { $($f: self.$f * rhs),+ }
+			}
+		}
+
+		impl Mul<$t> for f32 {
+			type Output = $t;
+			fn mul(self, rhs: $t) -> $t {
+				rhs * self
+			}
+		}
+
+		impl Div<f32> for $t {
+			type Output = Self;
+			fn div(self, rhs: f32) -> Self {
+				Self { $($f: self.$f / rhs),+ }
+			}
+		}
+
+		impl Neg for $t {
+			type Output = Self;
+			fn neg(self) -> Self {
+				Self { $($f: -self.$f),+ }
+			}
+		}
+	};
+}
+
+impl_vector_ops!(Vec2 { x, y });
+impl_vector_ops!(Vec3 { x, y, z });
+impl_vector_ops!(Vec4 { x, y, z, w });
+
+////////////////////////////////////////////////////////////////////////////////
+// Matrices
+////////////////////////////////////////////////////////////////////////////////
+
+/// A row-major 4x4 matrix; `m[3]` holds the translation row.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Mat4 {
+	pub m: [[f32; 4]; 4],
+}
+
+impl Mat4 {
+	pub const IDENTITY: Mat4 = Mat4 {
+		m: [
+			[1.0, 0.0, 0.0, 0.0],
+			[0.0, 1.0, 0.0, 0.0],
+			[0.0, 0.0, 1.0, 0.0],
+			[0.0, 0.0, 0.0, 1.0],
+		],
+	};
+
+	pub const fn from_rows(m: [[f32; 4]; 4]) -> Self {
+		Self { m }
+	}
+
+	/// Build from the flat 16 element layout used by the JS port.
+	pub fn from_array(a: [f32; 16]) -> Self {
+		let mut m = [[0.0; 4]; 4];
+		for (i, row) in m.iter_mut().enumerate() {
+			row.copy_from_slice(&a[i * 4..i * 4 + 4]);
+		}
+		Self { m }
+	}
+
+	pub fn to_array(&self) -> [f32; 16] {
+		let mut a = [0.0; 16];
+		for (i, row) in self.m.iter().enumerate() {
+			a[i * 4..i * 4 + 4].copy_from_slice(row);
+		}
+		a
+	}
+
+	pub fn row(&self, i: usize) -> Vec4 {
+		let r = self.m[i];
+		Vec4::new(r[0], r[1], r[2], r[3])
+	}
+
+	pub fn transpose(&self) -> Mat4 {
+		mat_transpose(self)
+	}
+
+	pub fn determinant(&self) -> f32 {
+		mat_determinant(self)
+	}
+
+	pub fn inverse(&self) -> Option<Mat4> {
+		mat_invert(self)
+	}
+
+	/// Transform a point (w = 1) and drop the homogeneous coordinate.
+	pub fn transform_point(&self, p: Vec3) -> Vec3 {
+		(p.extend(1.0) * *self).xyz()
+	}
+
+	/// Transform a direction (w = 0).
+	pub fn transform_vector(&self, v: Vec3) -> Vec3 {
+		(v.extend(0.0) * *self).xyz()
+	}
+}
+
+impl Default for Mat4 {
+	fn default() -> Self {
+		Mat4::IDENTITY
+	}
+}
+
+impl Index<usize> for Mat4 {
+	type Output = [f32; 4];
+	fn index(&self, i: usize) -> &[f32; 4] {
+		&self.m[i]
+	}
+}
+
+impl IndexMut<usize> for Mat4 {
+	fn index_mut(&mut self, i: usize) -> &mut [f32; 4] {
+		&mut self.m[i]
+	}
+}
+
+impl Mul for Mat4 {
+	type Output = Mat4;
+	fn mul(self, rhs: Mat4) -> Mat4 {
+		mat_multiply(&self, &rhs)
+	}
+}
+
+impl Mul<Mat4> for Vec4 {
+	type Output = Vec4;
+	fn mul(self, m: Mat4) -> Vec4 {
+		let v = self.to_array();
+		let mut r = [0.0; 4];
+		for (j, r) in r.iter_mut().enumerate() {
+			*r = v[0] * m[0][j] + v[1] * m[1][j] + v[2] * m[2][j] + v[3] * m[3][j];
+		}
+		Vec4::new(r[0], r[1], r[2], r[3])
+	}
+}
+
+pub fn mat_multiply(a: &Mat4, b: &Mat4) -> Mat4 {
+	let mut r = [[0.0; 4]; 4];
+	for i in 0..4 {
+		for j in 0..4 {
+			for k in 0..4 {
+				r[i][j] += a[i][k] * b[k][j];
+			}
+		}
+	}
+	Mat4::from_rows(r)
+}
+
+pub fn mat_transpose(m: &Mat4) -> Mat4 {
+	let mut r = [[0.0; 4]; 4];
+	for (i, row) in r.iter_mut().enumerate() {
+		for (j, e) in row.iter_mut().enumerate() {
+			*e = m[j][i];
+		}
+	}
+	Mat4::from_rows(r)
+}
+
+// Closed-form determinant and inverse generated by build.rs from the
+// symbolic expressions in renderwindow-linalg.
+mod generated {
+	include!(concat!(env!("OUT_DIR"), "/linalg.rs"));
+}
+
+pub fn mat_determinant(m: &Mat4) -> f32 {
+	generated::mat4_determinant(&m.to_array())
+}
+
+/// Returns `None` for a singular matrix instead of the JS port's Inf/NaN.
+pub fn mat_invert(m: &Mat4) -> Option<Mat4> {
+	generated::mat4_inverse(&m.to_array()).map(Mat4::from_array)
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// Standard Matrices
+////////////////////////////////////////////////////////////////////////////////
+
+pub fn mat_look_at(eye: Vec3, center: Vec3, up: Vec3) -> Mat4 {
+	// Standard right-handed lookAt matrix
+	let f = (center - eye).normalize(); // forward
+	let s = f.cross(up).normalize(); // right (side)
+	let u = s.cross(f); // up
+	Mat4::from_rows([
+		[s.x, u.x, -f.x, 0.0],
+		[s.y, u.y, -f.y, 0.0],
+		[s.z, u.z, -f.z, 0.0],
+		[-s.dot(eye), -u.dot(eye), f.dot(eye), 1.0],
+	])
+}
+
+pub fn mat_projection(fov: f32, near: f32, far: f32) -> Mat4 {
+	// Perspective projection, aspect ratio = 1
+	mat_perspective(fov, 1.0, near, far)
+}
+
+pub fn mat_perspective(fov: f32, aspect: f32, near: f32, far: f32) -> Mat4 {
+	// Standard perspective projection with aspect ratio
+	let f = 1.0 / ((fov / 2.0).to_radians()).tan();
+	let n = (far + near) / (near - far);
+	let d = (2.0 * far * near) / (near - far);
+	Mat4::from_rows([
+		[f / aspect, 0.0, 0.0, 0.0],
+		[0.0, f, 0.0, 0.0],
+		[0.0, 0.0, n, -1.0],
+		[0.0, 0.0, d, 0.0],
+	])
+}
+
+pub fn mat_rotate_y(angle: f32) -> Mat4 {
+	let (s, c) = angle.sin_cos();
+	Mat4::from_rows([
+		[c, 0.0, s, 0.0],
+		[0.0, 1.0, 0.0, 0.0],
+		[-s, 0.0, c, 0.0],
+		[0.0, 0.0, 0.0, 1.0],
+	])
+}
+
+pub fn mat_scale(x: f32, y: f32, z: f32) -> Mat4 {
+	let mut m = Mat4::IDENTITY;
+	m[0][0] = x;
+	m[1][1] = y;
+	m[2][2] = z;
+	m
+}
+
+pub fn mat_translate(x: f32, y: f32, z: f32) -> Mat4 {
+	let mut m = Mat4::IDENTITY;
+	m[3][0] = x;
+	m[3][1] = y;
+	m[3][2] = z;
+	m
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn assert_mat_eq(a: &Mat4, b: &Mat4, tolerance: f32) {
+		for i in 0..4 {
+			for j in 0..4 {
+				assert!(
+					(a[i][j] - b[i][j]).abs() <= tolerance,
+					"[{i}][{j}]: {} != {}\n{a:?}\n{b:?}",
+					a[i][j],
+					b[i][j]
+				);
+			}
+		}
+	}
+
+	#[test]
+	fn row_vector_convention() {
+		// Scale then translate, in that order, as `matScale(...) @ matTranslate(...)`.
+		let m = mat_scale(50.0, 1.0, 50.0) * mat_translate(0.0, -6.0, 0.0);
+		let p = Vec4::new(0.5, 0.0, -0.5, 1.0) * m;
+		assert_eq!(p, Vec4::new(25.0, -6.0, -25.0, 1.0));
+	}
+
+	#[test]
+	fn look_at_moves_eye_to_origin() {
+		let eye = Vec3::new(25.0, 3.0, 10.0);
+		let v = mat_look_at(eye, Vec3::default(), Vec3::new(0.0, 1.0, 0.0));
+		let p = v.transform_point(eye);
+		assert!(p.length() < 1e-5, "{p:?}");
+		// The center lands on the -Z axis in view space.
+		let c = v.transform_point(Vec3::default());
+		assert!(c.x.abs() < 1e-5 && c.y.abs() < 1e-5 && c.z < 0.0, "{c:?}");
+	}
+
+	#[test]
+	fn projection_matches_python() {
+		// matProjection(90, 0.001, 100.0) from module_matrix.py
+		let p = mat_projection(90.0, 0.001, 100.0);
+		assert!((p[0][0] - 1.0).abs() < 1e-6);
+		assert!((p[1][1] - 1.0).abs() < 1e-6);
+		assert!((p[2][2] - -1.00002).abs() < 1e-6);
+		assert_eq!(p[2][3], -1.0);
+		assert!((p[3][2] - -0.002_000_02).abs() < 1e-8);
+		assert_eq!(p[3][3], 0.0);
+	}
+
+	#[test]
+	fn determinant() {
+		assert_eq!(Mat4::IDENTITY.determinant(), 1.0);
+		assert_eq!(mat_scale(2.0, 3.0, 4.0).determinant(), 24.0);
+		assert_eq!(mat_translate(5.0, 6.0, 7.0).determinant(), 1.0);
+	}
+
+	#[test]
+	fn invert() {
+		let m = mat_rotate_y(0.3) * mat_scale(2.0, 3.0, 4.0) * mat_translate(5.0, -6.0, 7.0);
+		let inv = m.inverse().unwrap();
+		assert_mat_eq(&(m * inv), &Mat4::IDENTITY, 1e-5);
+		assert_mat_eq(&(inv * m), &Mat4::IDENTITY, 1e-5);
+		assert!(mat_scale(1.0, 0.0, 1.0).inverse().is_none());
+	}
+
+	#[test]
+	fn transpose() {
+		let m = Mat4::from_array(std::array::from_fn(|i| i as f32));
+		assert_eq!(m.transpose()[0], [0.0, 4.0, 8.0, 12.0]);
+		assert_eq!(m.transpose().transpose(), m);
+	}
+}
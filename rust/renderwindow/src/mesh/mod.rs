@@ -0,0 +1,683 @@
+////////////////////////////////////////////////////////////////////////////////
+// Mesh Construction
+////////////////////////////////////////////////////////////////////////////////
+
+pub mod obj;
+pub mod ply;
+pub mod stl;
+
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::math::{Vec2, Vec3, Vec4};
+
+/// CPU-side vertex and index buffers, the `MeshGen` tuple of `module_mesh.py`.
+///
+/// `vtx`, `nor` and `st0` are parallel per-vertex arrays and `idx` holds three
+/// indices per triangle. `tan` is either empty or parallel too: unit tangents
+/// along +s with the handedness in w, so the bitangent is `w * cross(nor, tan)`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MeshGen {
+	pub vtx: Vec<Vec3>,
+	pub nor: Vec<Vec3>,
+	pub st0: Vec<Vec2>,
+	pub tan: Vec<Vec4>,
+	pub idx: Vec<u32>,
+}
+
+impl MeshGen {
+	pub fn vertex_count(&self) -> usize {
+		self.vtx.len()
+	}
+
+	pub fn triangle_count(&self) -> usize {
+		self.idx.len() / 3
+	}
+
+	/// Vertex indices of each triangle.
+	pub fn triangles(&self) -> impl Iterator<Item = [u32; 3]> + '_ {
+		self.idx.chunks_exact(3).map(|t| [t[0], t[1], t[2]])
+	}
+
+	/// Fill `nor` with the area-weighted average of the normals of the
+	/// triangles around each vertex; unused vertices get +Y.
+	pub fn generate_normals(&mut self) {
+		let mut sums = vec![Vec3::default(); self.vtx.len()];
+		for tri in self.triangles() {
+			let [a, b, c] = tri.map(|i| self.vtx[i as usize]);
+			// Twice the area along the normal.
+			let n = (b - a).cross(c - a);
+			for i in tri {
+				sums[i as usize] += n;
+			}
+		}
+		self.nor = sums
+			.into_iter()
+			.map(|n| {
+				if n.length() > 0.0 {
+					n.normalize()
+				} else {
+					Vec3::new(0.0, 1.0, 0.0)
+				}
+			})
+			.collect();
+	}
+
+	/// Fill `tan` from the triangles' texture coordinate gradients.
+	pub fn generate_tangents(&mut self) {
+		self.tan = uv_tangents(self);
+	}
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// Tangent Space
+////////////////////////////////////////////////////////////////////////////////
+//
+// The same construction as MikkTSpace for vertices that are already shared:
+// each triangle's s and t directions are projected into the plane of the
+// corner's normal and summed, weighted by the corner angle. MikkTSpace would
+// also split vertices whose corners disagree on handedness; those keep the sign
+// of the summed bitangent here.
+
+/// Unit tangent with handedness from a normal and the (unnormalized) s and t
+/// directions, or `None` if the tangent is parallel to the normal.
+pub fn orthonormal_tangent(nor: Vec3, dpds: Vec3, dpdt: Vec3) -> Option<Vec4> {
+	let t = dpds - nor * nor.dot(dpds);
+	// Also catches NaN from degenerate input.
+	if t.length() <= 1e-6 || t.length().is_nan() {
+		return None;
+	}
+	let t = t.normalize();
+	let w = if nor.cross(t).dot(dpdt) < 0.0 {
+		-1.0
+	} else {
+		1.0
+	};
+	Some(t.extend(w))
+}
+
+/// Per-vertex tangents from the texture coordinate gradients of the triangles
+/// around each vertex. Vertices without a usable triangle get any unit vector
+/// perpendicular to their normal.
+pub fn uv_tangents(mesh: &MeshGen) -> Vec<Vec4> {
+	let n = mesh.vtx.len();
+	let mut sum_t = vec![Vec3::default(); n];
+	let mut sum_b = vec![Vec3::default(); n];
+	for tri in mesh.triangles() {
+		let [p0, p1, p2] = tri.map(|i| mesh.vtx[i as usize]);
+		let [s0, s1, s2] = tri.map(|i| mesh.st0[i as usize]);
+		let (e1, e2) = (p1 - p0, p2 - p0);
+		let (d1, d2) = (s1 - s0, s2 - s0);
+		let det = d1.x * d2.y - d2.x * d1.y;
+		if det == 0.0 {
+			continue;
+		}
+		// Flipped texture space keeps the sign in `det` and so in the bitangent.
+		let dpds = (e1 * d2.y - e2 * d1.y) * (1.0 / det);
+		let dpdt = (e2 * d1.x - e1 * d2.x) * (1.0 / det);
+		for k in 0..3 {
+			let i = tri[k] as usize;
+			let p = mesh.vtx[i];
+			let (a, b) = (
+				mesh.vtx[tri[(k + 1) % 3] as usize] - p,
+				mesh.vtx[tri[(k + 2) % 3] as usize] - p,
+			);
+			if a.length() == 0.0 || b.length() == 0.0 {
+				continue;
+			}
+			let angle = (a.normalize().dot(b.normalize())).clamp(-1.0, 1.0).acos();
+			let nor = mesh.nor[i].normalize();
+			let project = |v: Vec3| {
+				let v = v - nor * nor.dot(v);
+				if v.length() > 0.0 {
+					v.normalize()
+				} else {
+					v
+				}
+			};
+			sum_t[i] += project(dpds) * angle;
+			sum_b[i] += project(dpdt) * angle;
+		}
+	}
+	(0..n)
+		.map(|i| {
+			let nor = mesh.nor[i].normalize();
+			orthonormal_tangent(nor, sum_t[i], sum_b[i]).unwrap_or_else(|| any_tangent(nor))
+		})
+		.collect()
+}
+
+// A unit vector perpendicular to `nor`, with positive handedness.
+fn any_tangent(nor: Vec3) -> Vec4 {
+	let axis = if nor.x.abs() < 0.9 {
+		Vec3::new(1.0, 0.0, 0.0)
+	} else {
+		Vec3::new(0.0, 1.0, 0.0)
+	};
+	orthonormal_tangent(nor, axis, nor.cross(axis)).unwrap_or(Vec4::new(1.0, 0.0, 0.0, 1.0))
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// Welding
+////////////////////////////////////////////////////////////////////////////////
+//
+// A parametric grid has `(usteps + 1) * (vsteps + 1)` vertices: the u = 0 and
+// u = 1 columns coincide, and on a sphere the whole first and last rows sit on
+// the poles, where every other triangle has zero area. Welding merges vertices
+// only when every attribute the mesh carries agrees, so seams whose texture
+// coordinates differ stay split; it then drops degenerate triangles and the
+// vertices nothing uses any more.
+
+/// Tolerances for [`MeshGen::weld`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct WeldOptions {
+	/// Distance under which positions coincide, and the height under which a
+	/// triangle counts as degenerate.
+	pub position: f32,
+	/// Per-component difference under which normals, texture coordinates and
+	/// tangents agree.
+	pub attribute: f32,
+}
+
+impl Default for WeldOptions {
+	fn default() -> Self {
+		Self {
+			position: 1e-5,
+			attribute: 1e-4,
+		}
+	}
+}
+
+/// What [`MeshGen::weld`] removed.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct WeldReport {
+	/// Vertices merged into an earlier one.
+	pub vertices_merged: usize,
+	/// Vertices left unreferenced once degenerate triangles were removed.
+	pub vertices_unused: usize,
+	pub triangles_removed: usize,
+}
+
+impl MeshGen {
+	/// Merge coincident vertices whose attributes match and remove
+	/// zero-area triangles. Vertices keep their first-seen order.
+	pub fn weld(&mut self, options: &WeldOptions) -> WeldReport {
+		let mut report = WeldReport::default();
+		let cell = options.position.max(f32::EPSILON);
+		let key = |p: Vec3| [p.x, p.y, p.z].map(|c| (c / cell).floor() as i64);
+		let near = |a: &[f32], b: &[f32], e: f32| a.iter().zip(b).all(|(a, b)| (a - b).abs() <= e);
+		let same = |a: usize, b: usize| {
+			let e = options.attribute;
+			(self.vtx[a] - self.vtx[b]).length() <= options.position
+				&& (self.nor.is_empty()
+					|| near(&self.nor[a].to_array(), &self.nor[b].to_array(), e))
+				&& (self.st0.is_empty()
+					|| near(&self.st0[a].to_array(), &self.st0[b].to_array(), e))
+				&& (self.tan.is_empty()
+					|| near(&self.tan[a].to_array(), &self.tan[b].to_array(), e))
+		};
+		// Representatives by grid cell; a match within the tolerance may lie
+		// in any neighbouring cell.
+		let mut cells: HashMap<[i64; 3], Vec<usize>> = HashMap::new();
+		let mut remap = Vec::with_capacity(self.vtx.len());
+		for i in 0..self.vtx.len() {
+			let [x, y, z] = key(self.vtx[i]);
+			let mut found = None;
+			'search: for dz in -1..=1 {
+				for dy in -1..=1 {
+					for dx in -1..=1 {
+						if let Some(list) = cells.get(&[x + dx, y + dy, z + dz]) {
+							if let Some(&j) = list.iter().find(|&&j| same(i, j)) {
+								found = Some(j);
+								break 'search;
+							}
+						}
+					}
+				}
+			}
+			match found {
+				Some(j) => {
+					report.vertices_merged += 1;
+					remap.push(j);
+				}
+				None => {
+					cells.entry([x, y, z]).or_default().push(i);
+					remap.push(i);
+				}
+			}
+		}
+		let mut idx = Vec::with_capacity(self.idx.len());
+		for [a, b, c] in self.triangles() {
+			let [a, b, c] = [a, b, c].map(|i| remap[i as usize]);
+			let (pa, pb, pc) = (self.vtx[a], self.vtx[b], self.vtx[c]);
+			let longest = (pb - pa)
+				.length()
+				.max((pc - pb).length())
+				.max((pa - pc).length());
+			// Twice the area over the longest edge is the smallest height.
+			if (pb - pa).cross(pc - pa).length() <= options.position * longest {
+				report.triangles_removed += 1;
+			} else {
+				idx.extend([a, b, c]);
+			}
+		}
+		// Compact to the vertices still referenced, in their original order.
+		let mut used = vec![false; self.vtx.len()];
+		for &i in &idx {
+			used[i] = true;
+		}
+		let mut new_index = vec![u32::MAX; self.vtx.len()];
+		let mut next = 0;
+		for (i, &u) in used.iter().enumerate() {
+			if u {
+				new_index[i] = next;
+				next += 1;
+			}
+		}
+		report.vertices_unused = self.vtx.len() - report.vertices_merged - next as usize;
+		self.vtx = retain_used(&self.vtx, &used);
+		self.nor = retain_used(&self.nor, &used);
+		self.st0 = retain_used(&self.st0, &used);
+		self.tan = retain_used(&self.tan, &used);
+		self.idx = idx.into_iter().map(|i| new_index[i]).collect();
+		report
+	}
+}
+
+// The elements of a per-vertex array whose vertex is `used`.
+fn retain_used<T: Copy>(v: &[T], used: &[bool]) -> Vec<T> {
+	if v.is_empty() {
+		return Vec::new();
+	}
+	v.iter()
+		.zip(used)
+		.filter(|(_, &u)| u)
+		.map(|(&x, _)| x)
+		.collect()
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// Index Buffers
+////////////////////////////////////////////////////////////////////////////////
+//
+// `module_mesh_gl41.py` uploads `numpy.uint16` indices and ModelUV.swift casts
+// to `UInt16`, so a 300x300 grid's 90,601 vertices wrap around. Here the index
+// type is chosen from the vertex count, and meshes bound for a 16-bit backend
+// are split rather than truncated.
+
+/// Most vertices a 16-bit index buffer can address. 0xFFFF is left free for
+/// use as the primitive restart index.
+pub const U16_VERTEX_LIMIT: usize = 0xFFFF;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum IndexError {
+	/// `idx` is not a whole number of triangles.
+	PartialTriangle {
+		len: usize,
+	},
+	IndexOutOfRange {
+		index: u32,
+		vertex_count: usize,
+	},
+	TooManyVertices {
+		vertex_count: usize,
+		limit: usize,
+	},
+}
+
+impl fmt::Display for IndexError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			IndexError::PartialTriangle { len } => {
+				write!(f, "{len} indices do not make whole triangles.")
+			}
+			IndexError::IndexOutOfRange {
+				index,
+				vertex_count,
+			} => write!(
+				f,
+				"Index {index} is out of range for {vertex_count} vertices."
+			),
+			IndexError::TooManyVertices {
+				vertex_count,
+				limit,
+			} => write!(
+				f,
+				"{vertex_count} vertices exceed the limit of {limit}; split the mesh."
+			),
+		}
+	}
+}
+
+impl std::error::Error for IndexError {}
+
+/// Triangle indices in the narrowest type that holds them, as
+/// `GL_UNSIGNED_SHORT` or `GL_UNSIGNED_INT` data.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum IndexBuffer {
+	U16(Vec<u16>),
+	U32(Vec<u32>),
+}
+
+impl IndexBuffer {
+	pub fn len(&self) -> usize {
+		match self {
+			IndexBuffer::U16(v) => v.len(),
+			IndexBuffer::U32(v) => v.len(),
+		}
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.len() == 0
+	}
+
+	pub fn get(&self, i: usize) -> Option<u32> {
+		match self {
+			IndexBuffer::U16(v) => v.get(i).map(|&x| x as u32),
+			IndexBuffer::U32(v) => v.get(i).copied(),
+		}
+	}
+
+	/// 2 or 4.
+	pub fn bytes_per_index(&self) -> usize {
+		match self {
+			IndexBuffer::U16(_) => 2,
+			IndexBuffer::U32(_) => 4,
+		}
+	}
+
+	/// The indices as little-endian bytes, ready to upload or write to a file.
+	pub fn to_le_bytes(&self) -> Vec<u8> {
+		match self {
+			IndexBuffer::U16(v) => v.iter().flat_map(|x| x.to_le_bytes()).collect(),
+			IndexBuffer::U32(v) => v.iter().flat_map(|x| x.to_le_bytes()).collect(),
+		}
+	}
+}
+
+impl MeshGen {
+	/// Check that `idx` is whole triangles of existing vertices.
+	pub fn validate_indices(&self) -> Result<(), IndexError> {
+		if !self.idx.len().is_multiple_of(3) {
+			return Err(IndexError::PartialTriangle {
+				len: self.idx.len(),
+			});
+		}
+		let vertex_count = self.vertex_count();
+		match self.idx.iter().find(|&&i| i as usize >= vertex_count) {
+			Some(&index) => Err(IndexError::IndexOutOfRange {
+				index,
+				vertex_count,
+			}),
+			None => Ok(()),
+		}
+	}
+
+	/// `idx` as 16-bit indices when there are at most [`U16_VERTEX_LIMIT`]
+	/// vertices, 32-bit otherwise.
+	pub fn index_buffer(&self) -> Result<IndexBuffer, IndexError> {
+		self.validate_indices()?;
+		Ok(if self.vertex_count() > U16_VERTEX_LIMIT {
+			IndexBuffer::U32(self.idx.clone())
+		} else {
+			IndexBuffer::U16(self.idx.iter().map(|&i| i as u16).collect())
+		})
+	}
+
+	/// `idx` as 16-bit indices, or [`IndexError::TooManyVertices`] past
+	/// [`U16_VERTEX_LIMIT`]; see [`MeshGen::split`].
+	pub fn index_buffer_u16(&self) -> Result<Vec<u16>, IndexError> {
+		self.validate_indices()?;
+		if self.vertex_count() > U16_VERTEX_LIMIT {
+			return Err(IndexError::TooManyVertices {
+				vertex_count: self.vertex_count(),
+				limit: U16_VERTEX_LIMIT,
+			});
+		}
+		Ok(self.idx.iter().map(|&i| i as u16).collect())
+	}
+
+	/// Split into meshes of at most `max_vertices` vertices each, keeping
+	/// the triangle order; use [`U16_VERTEX_LIMIT`] for 16-bit backends.
+	/// Vertices shared across a split are duplicated. A mesh that already
+	/// fits is returned whole.
+	///
+	/// # Panics
+	///
+	/// If `max_vertices` is less than 3.
+	pub fn split(&self, max_vertices: usize) -> Result<Vec<MeshGen>, IndexError> {
+		assert!(max_vertices >= 3, "a triangle needs three vertices");
+		self.validate_indices()?;
+		if self.vertex_count() <= max_vertices {
+			return Ok(vec![self.clone()]);
+		}
+		let mut parts = Vec::new();
+		let mut part = MeshGen::default();
+		// Index of each source vertex in `part`, or u32::MAX.
+		let mut local = vec![u32::MAX; self.vertex_count()];
+		let mut copied: Vec<usize> = Vec::new();
+		for tri in self.triangles() {
+			let new = tri
+				.iter()
+				.filter(|&&i| local[i as usize] == u32::MAX)
+				.count();
+			if copied.len() + new > max_vertices {
+				for i in copied.drain(..) {
+					local[i] = u32::MAX;
+				}
+				parts.push(std::mem::take(&mut part));
+			}
+			for i in tri {
+				let i = i as usize;
+				if local[i] == u32::MAX {
+					local[i] = copied.len() as u32;
+					copied.push(i);
+					part.vtx.push(self.vtx[i]);
+					if !self.nor.is_empty() {
+						part.nor.push(self.nor[i]);
+					}
+					if !self.st0.is_empty() {
+						part.st0.push(self.st0[i]);
+					}
+					if !self.tan.is_empty() {
+						part.tan.push(self.tan[i]);
+					}
+				}
+				part.idx.push(local[i]);
+			}
+		}
+		if !part.idx.is_empty() {
+			parts.push(part);
+		}
+		Ok(parts)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::parametric::{create_parametric, Disk, Plane, Sphere};
+
+	fn quad(st0: [Vec2; 4]) -> MeshGen {
+		MeshGen {
+			vtx: vec![
+				Vec3::new(0.0, 0.0, 0.0),
+				Vec3::new(2.0, 0.0, 0.0),
+				Vec3::new(2.0, 1.0, 0.0),
+				Vec3::new(0.0, 1.0, 0.0),
+			],
+			nor: vec![Vec3::new(0.0, 0.0, 1.0); 4],
+			st0: st0.to_vec(),
+			tan: Vec::new(),
+			idx: vec![0, 1, 2, 2, 3, 0],
+		}
+	}
+
+	#[test]
+	fn uv_gradient_tangents() {
+		let (a, b, c, d) = (
+			Vec2::new(0.0, 0.0),
+			Vec2::new(1.0, 0.0),
+			Vec2::new(1.0, 1.0),
+			Vec2::new(0.0, 1.0),
+		);
+		let mut mesh = quad([a, b, c, d]);
+		mesh.generate_tangents();
+		// s along +x, t along +y: right-handed with the +z normal.
+		assert!(mesh.tan.iter().all(|&t| t == Vec4::new(1.0, 0.0, 0.0, 1.0)));
+		// Mirrored in s: the tangent flips and so does the handedness, keeping
+		// the bitangent along +t.
+		let mirrored = uv_tangents(&quad([b, a, d, c]));
+		assert!(mirrored
+			.iter()
+			.all(|&t| t == Vec4::new(-1.0, 0.0, 0.0, -1.0)));
+		// Texture space rotated a quarter turn: s runs along +y.
+		let rotated = uv_tangents(&quad([d, a, b, c]));
+		assert!(rotated
+			.iter()
+			.all(|&t| (t.xyz() - Vec3::new(0.0, 1.0, 0.0)).length() < 1e-6 && t.w == 1.0));
+		// Degenerate texture coordinates still give a tangent.
+		let flat = uv_tangents(&quad([a; 4]));
+		assert!(flat
+			.iter()
+			.all(|&t| t.xyz().dot(Vec3::new(0.0, 0.0, 1.0)) == 0.0));
+	}
+
+	#[test]
+	fn area_weighted_normals() {
+		let mut mesh = quad([Vec2::default(); 4]);
+		mesh.nor.clear();
+		mesh.generate_normals();
+		assert_eq!(mesh.nor, [Vec3::new(0.0, 0.0, 1.0); 4]);
+		// A fold: the shared edge averages the two faces by area.
+		mesh.vtx[3] = Vec3::new(0.0, 1.0, 2.0);
+		mesh.generate_normals();
+		let fold = mesh.nor[2];
+		assert!((fold.length() - 1.0).abs() < 1e-6);
+		assert!(fold.z > 0.0 && fold.y < 0.0);
+	}
+
+	#[test]
+	fn weld_keeps_uv_seams_and_drops_poles() {
+		// Unit texture coordinates differ along the seam and around the poles,
+		// so nothing merges; one triangle of each pole quad has zero area.
+		let mut sphere = create_parametric(8, 8, &Sphere);
+		let report = sphere.weld(&WeldOptions::default());
+		assert_eq!(
+			report,
+			WeldReport {
+				vertices_merged: 0,
+				vertices_unused: 2,
+				triangles_removed: 16,
+			}
+		);
+		assert_eq!(sphere.vertex_count(), 81 - 2);
+		assert_eq!(sphere.triangle_count(), 128 - 16);
+		assert_eq!(sphere.tan.len(), sphere.vertex_count());
+		assert!(sphere
+			.idx
+			.iter()
+			.all(|&i| (i as usize) < sphere.vertex_count()));
+		// A second pass finds nothing.
+		assert_eq!(sphere.weld(&WeldOptions::default()), WeldReport::default());
+	}
+
+	#[test]
+	fn weld_merges_matching_vertices() {
+		// The disk's projected texture coordinates agree across its seam and
+		// at its center, so both close up.
+		let mut disk = create_parametric(8, 8, &Disk::new(0.0, 1.0));
+		let report = disk.weld(&WeldOptions::default());
+		assert_eq!(
+			report,
+			WeldReport {
+				vertices_merged: 8 + 8,
+				vertices_unused: 0,
+				triangles_removed: 8,
+			}
+		);
+		assert_eq!(disk.vertex_count(), 8 * 8 + 1);
+		// Positions alone weld a triangle soup into a shared mesh.
+		let mut soup = MeshGen {
+			vtx: vec![
+				Vec3::new(0.0, 0.0, 0.0),
+				Vec3::new(1.0, 0.0, 0.0),
+				Vec3::new(0.0, 1.0, 0.0),
+				Vec3::new(1.0, 0.0, 0.0),
+				Vec3::new(1.0, 1.0, 0.0),
+				Vec3::new(0.0, 1.0 + 1e-6, 0.0),
+			],
+			idx: vec![0, 1, 2, 3, 4, 5],
+			..MeshGen::default()
+		};
+		assert_eq!(soup.weld(&WeldOptions::default()).vertices_merged, 2);
+		assert_eq!(soup.idx, [0, 1, 2, 1, 3, 2]);
+	}
+
+	#[test]
+	fn index_width_follows_vertex_count() {
+		// The Python demo's 100x100 plane fits in 16 bits; 300x300 does not.
+		let small = create_parametric(100, 100, &Plane);
+		assert!(
+			matches!(small.index_buffer(), Ok(IndexBuffer::U16(v)) if v.len() == small.idx.len())
+		);
+		let large = create_parametric(300, 300, &Plane);
+		let buffer = large.index_buffer().unwrap();
+		assert_eq!(buffer.bytes_per_index(), 4);
+		assert_eq!(buffer.get(buffer.len() - 1), large.idx.last().copied());
+		assert_eq!(
+			large.index_buffer_u16(),
+			Err(IndexError::TooManyVertices {
+				vertex_count: 301 * 301,
+				limit: U16_VERTEX_LIMIT,
+			})
+		);
+		assert_eq!(
+			IndexBuffer::U16(vec![1, 0x0203]).to_le_bytes(),
+			[1, 0, 3, 2]
+		);
+		let mut broken = small.clone();
+		broken.idx.push(0);
+		assert_eq!(
+			broken.index_buffer(),
+			Err(IndexError::PartialTriangle {
+				len: small.idx.len() + 1
+			})
+		);
+		broken.idx.extend([0, 10201]);
+		assert_eq!(
+			broken.validate_indices(),
+			Err(IndexError::IndexOutOfRange {
+				index: 10201,
+				vertex_count: 10201
+			})
+		);
+	}
+
+	#[test]
+	fn split_keeps_every_triangle() {
+		let large = create_parametric(300, 300, &Plane);
+		let parts = large.split(U16_VERTEX_LIMIT).unwrap();
+		assert_eq!(parts.len(), 2);
+		let corners = |m: &MeshGen| -> Vec<[Vec3; 3]> {
+			m.triangles()
+				.map(|t| t.map(|i| m.vtx[i as usize]))
+				.collect()
+		};
+		let mut joined = Vec::new();
+		for part in &parts {
+			assert!(part.vertex_count() <= U16_VERTEX_LIMIT);
+			assert!(matches!(part.index_buffer(), Ok(IndexBuffer::U16(_))));
+			assert_eq!(part.tan.len(), part.vertex_count());
+			joined.extend(corners(part));
+		}
+		assert_eq!(joined, corners(&large));
+		// Small limits split often; every part stays within its budget.
+		let sphere = create_parametric(8, 8, &Sphere);
+		let parts = sphere.split(10).unwrap();
+		assert!(parts.iter().all(|p| p.vertex_count() <= 10));
+		let triangles: usize = parts.iter().map(MeshGen::triangle_count).sum();
+		assert_eq!(triangles, sphere.triangle_count());
+		assert_eq!(sphere.split(81).unwrap(), std::slice::from_ref(&sphere));
+	}
+}
@@ -0,0 +1,628 @@
+////////////////////////////////////////////////////////////////////////////////
+// Wavefront OBJ
+////////////////////////////////////////////////////////////////////////////////
+//
+// OBJ indexes positions, texture coordinates and normals separately, so each
+// distinct `v/vt/vn` triple of the faces becomes one `MeshGen` vertex. Faces
+// with more than three corners are ear-clipped in their own plane, which keeps
+// concave polygons inside their outline. OBJ's vt runs up the image while
+// `st0` runs down the texture rows, so t is flipped on the way in and out.
+
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::io;
+use std::ops::Range;
+use std::path::Path;
+use std::str::SplitWhitespace;
+
+use super::MeshGen;
+use crate::math::{Vec2, Vec3};
+
+/// A mesh with the groups and materials of an OBJ file.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Obj {
+	pub mesh: MeshGen,
+	/// Runs of triangles sharing an object, group and material, in order.
+	pub groups: Vec<ObjGroup>,
+	/// `mtllib` file names, relative to the OBJ.
+	pub material_libraries: Vec<String>,
+	/// Materials read from the libraries by [`load_obj`].
+	pub materials: Vec<ObjMaterial>,
+}
+
+/// Triangles `triangles` of the mesh, as selected by `o`, `g` and `usemtl`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ObjGroup {
+	pub object: String,
+	pub group: String,
+	pub material: Option<String>,
+	pub triangles: Range<usize>,
+}
+
+/// The `newmtl` statements this crate has a use for, with the defaults of the
+/// MTL specification.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ObjMaterial {
+	pub name: String,
+	/// `Ka`
+	pub ambient: Vec3,
+	/// `Kd`
+	pub diffuse: Vec3,
+	/// `Ks`
+	pub specular: Vec3,
+	/// `Ns`
+	pub shininess: f32,
+	/// `d`, or `1 - Tr`.
+	pub dissolve: f32,
+	/// `map_Kd`
+	pub diffuse_map: Option<String>,
+	/// `map_Bump`, `bump` or `norm`.
+	pub bump_map: Option<String>,
+}
+
+impl ObjMaterial {
+	pub fn new(name: &str) -> Self {
+		Self {
+			name: name.to_string(),
+			ambient: Vec3::new(0.2, 0.2, 0.2),
+			diffuse: Vec3::new(0.8, 0.8, 0.8),
+			specular: Vec3::new(1.0, 1.0, 1.0),
+			shininess: 0.0,
+			dissolve: 1.0,
+			diffuse_map: None,
+			bump_map: None,
+		}
+	}
+}
+
+impl Obj {
+	/// A single anonymous group holding all of `mesh`.
+	pub fn new(mesh: MeshGen) -> Self {
+		Self {
+			groups: vec![ObjGroup {
+				triangles: 0..mesh.triangle_count(),
+				..ObjGroup::default()
+			}],
+			mesh,
+			..Self::default()
+		}
+	}
+
+	pub fn material(&self, name: &str) -> Option<&ObjMaterial> {
+		self.materials.iter().find(|m| m.name == name)
+	}
+}
+
+impl From<MeshGen> for Obj {
+	fn from(mesh: MeshGen) -> Self {
+		Self::new(mesh)
+	}
+}
+
+fn invalid(line: usize, message: &str) -> io::Error {
+	io::Error::new(
+		io::ErrorKind::InvalidData,
+		format!("line {line}: {message}"),
+	)
+}
+
+// Statements with their 1-based starting line, comments removed and lines
+// ending in `\` joined to the next.
+fn statements(text: &str) -> Vec<(usize, String)> {
+	let mut out = Vec::new();
+	let mut pending: Option<(usize, String)> = None;
+	for (i, line) in text.lines().enumerate() {
+		let line = line.split('#').next().unwrap_or("");
+		let (body, more) = match line.trim_end().strip_suffix('\\') {
+			Some(body) => (body, true),
+			None => (line, false),
+		};
+		let (number, mut joined) = pending.take().unwrap_or((i + 1, String::new()));
+		joined.push(' ');
+		joined.push_str(body);
+		if more {
+			pending = Some((number, joined));
+		} else {
+			out.push((number, joined));
+		}
+	}
+	out.extend(pending);
+	out
+}
+
+// All remaining words as numbers, checking there are `min..=max` of them.
+fn numbers(words: SplitWhitespace, line: usize, min: usize, max: usize) -> io::Result<Vec<f32>> {
+	let values = words
+		.map(|w| w.parse::<f32>())
+		.collect::<Result<Vec<_>, _>>()
+		.map_err(|_| invalid(line, "Expected a number."))?;
+	if values.len() < min || values.len() > max {
+		return Err(invalid(
+			line,
+			&format!("Expected {min} to {max} numbers, got {}.", values.len()),
+		));
+	}
+	Ok(values)
+}
+
+// A 1-based or negative (relative) OBJ index into a list of `len` elements.
+fn resolve(word: &str, len: usize, line: usize) -> io::Result<usize> {
+	let i: i64 = word
+		.parse()
+		.map_err(|_| invalid(line, &format!("Bad index {word:?}.")))?;
+	let resolved = if i > 0 { i - 1 } else { len as i64 + i };
+	if i == 0 || resolved < 0 || resolved >= len as i64 {
+		return Err(invalid(line, &format!("Index {i} is out of range.")));
+	}
+	Ok(resolved as usize)
+}
+
+/// Corner triples of a simple polygon, ear-clipped in the plane of its Newell
+/// normal and wound like the polygon. Degenerate input falls back to a fan.
+pub fn triangulate(polygon: &[Vec3]) -> Vec<[usize; 3]> {
+	let n = polygon.len();
+	if n < 3 {
+		return Vec::new();
+	}
+	let mut normal = Vec3::default();
+	for (i, &a) in polygon.iter().enumerate() {
+		normal += a.cross(polygon[(i + 1) % n]);
+	}
+	// A basis with `x.cross(y) == normal`, so counterclockwise stays so in 2D.
+	let x = if normal.x.abs() < 0.9 * normal.length() {
+		Vec3::new(1.0, 0.0, 0.0).cross(normal)
+	} else {
+		Vec3::new(0.0, 1.0, 0.0).cross(normal)
+	};
+	let y = normal.cross(x);
+	let flat: Vec<Vec2> = polygon
+		.iter()
+		.map(|&p| Vec2::new(p.dot(x), p.dot(y)))
+		.collect();
+	let cross = |o: Vec2, a: Vec2, b: Vec2| (a.x - o.x) * (b.y - o.y) - (a.y - o.y) * (b.x - o.x);
+
+	let mut remaining: Vec<usize> = (0..n).collect();
+	let mut triangles = Vec::with_capacity(n - 2);
+	while remaining.len() > 3 {
+		let m = remaining.len();
+		let ear = (0..m).find(|&i| {
+			let [a, b, c] = [(i + m - 1) % m, i, (i + 1) % m].map(|k| flat[remaining[k]]);
+			cross(a, b, c) > 0.0
+				&& remaining.iter().all(|&k| {
+					let p = flat[k];
+					[a, b, c].contains(&p)
+						|| cross(a, b, p) < 0.0
+						|| cross(b, c, p) < 0.0
+						|| cross(c, a, p) < 0.0
+				})
+		});
+		let i = ear.unwrap_or(0);
+		triangles.push([
+			remaining[(i + m - 1) % m],
+			remaining[i],
+			remaining[(i + 1) % m],
+		]);
+		remaining.remove(i);
+	}
+	triangles.push([remaining[0], remaining[1], remaining[2]]);
+	triangles
+}
+
+/// Read an OBJ file's geometry, groups and `mtllib` names. Faces without
+/// normals get area-weighted normals shared by corners with the same `v`, and
+/// faces without texture coordinates get (0, 0). Vertices are numbered in the
+/// order the faces first use them. Points, lines, smoothing groups and
+/// free-form geometry are skipped.
+pub fn decode_obj(text: &str) -> io::Result<Obj> {
+	let mut positions = Vec::new();
+	let mut texcoords = Vec::new();
+	let mut normals = Vec::new();
+	let mut obj = Obj::default();
+	let mut current = ObjGroup::default();
+	// One vertex per distinct corner, remembering its `v` for smoothing.
+	let mut corners: HashMap<(usize, Option<usize>, Option<usize>), u32> = HashMap::new();
+	let mut source = Vec::new();
+	let mut has_normal = Vec::new();
+
+	for (line, statement) in statements(text) {
+		let mut words = statement.split_whitespace();
+		let Some(keyword) = words.next() else {
+			continue;
+		};
+		let rest = || words.clone().collect::<Vec<_>>().join(" ");
+		match keyword {
+			// A w coordinate or vertex colour may follow.
+			"v" => {
+				let p = numbers(words, line, 3, 7)?;
+				positions.push(Vec3::new(p[0], p[1], p[2]));
+			}
+			"vt" => {
+				let t = numbers(words, line, 1, 3)?;
+				texcoords.push(Vec2::new(t[0], 1.0 - t.get(1).copied().unwrap_or(0.0)));
+			}
+			"vn" => {
+				let n = numbers(words, line, 3, 3)?;
+				normals.push(Vec3::new(n[0], n[1], n[2]));
+			}
+			"o" => {
+				current.object = rest();
+				current.group = String::new();
+			}
+			"g" => current.group = rest(),
+			"usemtl" => current.material = Some(rest()).filter(|m| !m.is_empty()),
+			"mtllib" => obj.material_libraries.extend(words.map(str::to_string)),
+			"f" => {
+				let mut face = Vec::new();
+				for word in words {
+					let mut parts = word.split('/');
+					let v = resolve(parts.next().unwrap_or(""), positions.len(), line)?;
+					let vt = match parts.next() {
+						Some("") | None => None,
+						Some(w) => Some(resolve(w, texcoords.len(), line)?),
+					};
+					let vn = match parts.next() {
+						Some("") | None => None,
+						Some(w) => Some(resolve(w, normals.len(), line)?),
+					};
+					let next = obj.mesh.vtx.len() as u32;
+					let index = *corners.entry((v, vt, vn)).or_insert_with(|| {
+						obj.mesh.vtx.push(positions[v]);
+						obj.mesh
+							.st0
+							.push(vt.map_or(Vec2::default(), |t| texcoords[t]));
+						obj.mesh
+							.nor
+							.push(vn.map_or(Vec3::default(), |n| normals[n]));
+						source.push(v);
+						has_normal.push(vn.is_some());
+						next
+					});
+					face.push(index);
+				}
+				if face.len() < 3 {
+					return Err(invalid(line, "A face needs at least three corners."));
+				}
+				let points: Vec<Vec3> = face.iter().map(|&i| obj.mesh.vtx[i as usize]).collect();
+				let start = obj.mesh.triangle_count();
+				for tri in triangulate(&points) {
+					obj.mesh.idx.extend(tri.map(|k| face[k]));
+				}
+				let end = obj.mesh.triangle_count();
+				match obj.groups.last_mut() {
+					Some(g)
+						if g.triangles.end == start
+							&& g.object == current.object
+							&& g.group == current.group
+							&& g.material == current.material =>
+					{
+						g.triangles.end = end
+					}
+					_ => obj.groups.push(ObjGroup {
+						triangles: start..end,
+						..current.clone()
+					}),
+				}
+			}
+			_ => {}
+		}
+	}
+
+	if has_normal.contains(&false) {
+		let mut sums = vec![Vec3::default(); positions.len()];
+		for tri in obj.mesh.triangles() {
+			let [a, b, c] = tri.map(|i| obj.mesh.vtx[i as usize]);
+			// Twice the area along the normal.
+			let n = (b - a).cross(c - a);
+			for i in tri {
+				sums[source[i as usize]] += n;
+			}
+		}
+		for (i, nor) in obj.mesh.nor.iter_mut().enumerate() {
+			if !has_normal[i] {
+				let sum = sums[source[i]];
+				*nor = if sum.length() > 0.0 {
+					sum.normalize()
+				} else {
+					Vec3::new(0.0, 1.0, 0.0)
+				};
+			}
+		}
+	}
+	Ok(obj)
+}
+
+/// Write `obj` with one `v`, `vt` and `vn` per vertex, so indices match
+/// `mesh.idx`. Empty `st0` or `nor` arrays are left out of the faces.
+/// Triangles outside every group are written where they fall, under whatever
+/// group precedes them.
+pub fn encode_obj(obj: &Obj) -> String {
+	let mesh = &obj.mesh;
+	let mut out = String::new();
+	for library in &obj.material_libraries {
+		let _ = writeln!(out, "mtllib {library}");
+	}
+	for p in &mesh.vtx {
+		let _ = writeln!(out, "v {} {} {}", p.x, p.y, p.z);
+	}
+	for t in &mesh.st0 {
+		let _ = writeln!(out, "vt {} {}", t.x, 1.0 - t.y);
+	}
+	for n in &mesh.nor {
+		let _ = writeln!(out, "vn {} {} {}", n.x, n.y, n.z);
+	}
+	let corner = |i: u32| {
+		let i = i + 1;
+		match (mesh.st0.is_empty(), mesh.nor.is_empty()) {
+			(false, false) => format!("{i}/{i}/{i}"),
+			(true, false) => format!("{i}//{i}"),
+			(false, true) => format!("{i}/{i}"),
+			(true, true) => format!("{i}"),
+		}
+	};
+	let faces = |out: &mut String, triangles: Range<usize>| {
+		for [a, b, c] in mesh.triangles().skip(triangles.start).take(triangles.len()) {
+			let _ = writeln!(out, "f {} {} {}", corner(a), corner(b), corner(c));
+		}
+	};
+
+	let mut written = 0;
+	let mut state = ObjGroup::default();
+	for group in &obj.groups {
+		faces(&mut out, written..group.triangles.start.max(written));
+		// `o` clears the group, and a bare `usemtl` the material.
+		if group.object != state.object {
+			let _ = writeln!(out, "o {}", group.object);
+			state.group.clear();
+		}
+		if group.group != state.group {
+			let _ = writeln!(out, "g {}", group.group);
+		}
+		if group.material != state.material {
+			let _ = writeln!(out, "usemtl {}", group.material.as_deref().unwrap_or(""));
+		}
+		faces(&mut out, group.triangles.clone());
+		written = group.triangles.end;
+		state = group.clone();
+	}
+	faces(&mut out, written..mesh.triangle_count().max(written));
+	out
+}
+
+/// Read the materials of an MTL file.
+pub fn decode_mtl(text: &str) -> io::Result<Vec<ObjMaterial>> {
+	let mut materials: Vec<ObjMaterial> = Vec::new();
+	for (line, statement) in statements(text) {
+		let mut words = statement.split_whitespace();
+		let Some(keyword) = words.next() else {
+			continue;
+		};
+		if keyword == "newmtl" {
+			materials.push(ObjMaterial::new(&words.collect::<Vec<_>>().join(" ")));
+			continue;
+		}
+		let Some(material) = materials.last_mut() else {
+			return Err(invalid(line, &format!("{keyword} before newmtl.")));
+		};
+		// Colours may give one value for all three channels.
+		let color = |words| {
+			numbers(words, line, 1, 3)
+				.map(|c| Vec3::new(c[0], c[c.len().min(2) - 1], c[c.len() - 1]))
+		};
+		// Map options such as `-bm 0.5` come before the file name.
+		let map = |words: SplitWhitespace| words.last().map(str::to_string);
+		match keyword {
+			"Ka" => material.ambient = color(words)?,
+			"Kd" => material.diffuse = color(words)?,
+			"Ks" => material.specular = color(words)?,
+			"Ns" => material.shininess = numbers(words, line, 1, 1)?[0],
+			"d" => material.dissolve = numbers(words, line, 1, 1)?[0],
+			"Tr" => material.dissolve = 1.0 - numbers(words, line, 1, 1)?[0],
+			"map_Kd" => material.diffuse_map = map(words),
+			"map_Bump" | "map_bump" | "bump" | "norm" => material.bump_map = map(words),
+			_ => {}
+		}
+	}
+	Ok(materials)
+}
+
+pub fn encode_mtl(materials: &[ObjMaterial]) -> String {
+	let mut out = String::new();
+	for m in materials {
+		let _ = writeln!(out, "newmtl {}", m.name);
+		for (key, c) in [("Ka", m.ambient), ("Kd", m.diffuse), ("Ks", m.specular)] {
+			let _ = writeln!(out, "{key} {} {} {}", c.x, c.y, c.z);
+		}
+		let _ = writeln!(out, "Ns {}", m.shininess);
+		let _ = writeln!(out, "d {}", m.dissolve);
+		if let Some(map) = &m.diffuse_map {
+			let _ = writeln!(out, "map_Kd {map}");
+		}
+		if let Some(map) = &m.bump_map {
+			let _ = writeln!(out, "map_Bump {map}");
+		}
+		out.push('\n');
+	}
+	out
+}
+
+/// Read an OBJ and the MTL libraries it names, looked up next to it.
+pub fn load_obj(path: impl AsRef<Path>) -> io::Result<Obj> {
+	let path = path.as_ref();
+	let mut obj = decode_obj(&std::fs::read_to_string(path)?)?;
+	let dir = path.parent().unwrap_or(Path::new(""));
+	for library in &obj.material_libraries {
+		let text = std::fs::read_to_string(dir.join(library))?;
+		obj.materials.extend(decode_mtl(&text)?);
+	}
+	Ok(obj)
+}
+
+/// Write an OBJ and, if it has materials, the first of its MTL libraries next
+/// to it.
+pub fn save_obj(obj: &Obj, path: impl AsRef<Path>) -> io::Result<()> {
+	let path = path.as_ref();
+	std::fs::write(path, encode_obj(obj))?;
+	if let (Some(library), false) = (obj.material_libraries.first(), obj.materials.is_empty()) {
+		let dir = path.parent().unwrap_or(Path::new(""));
+		std::fs::write(dir.join(library), encode_mtl(&obj.materials))?;
+	}
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::parametric::{create_parametric, Torus};
+
+	fn area(mesh: &MeshGen, triangles: Range<usize>) -> f32 {
+		mesh.triangles()
+			.skip(triangles.start)
+			.take(triangles.len())
+			.map(|tri| {
+				let [a, b, c] = tri.map(|i| mesh.vtx[i as usize]);
+				0.5 * (b - a).cross(c - a).length()
+			})
+			.sum()
+	}
+
+	#[test]
+	fn torus_round_trip() {
+		let mut mesh = create_parametric(12, 8, &Torus::new(10.0, 1.0));
+		mesh.tan.clear();
+		let back = decode_obj(&encode_obj(&Obj::new(mesh.clone()))).unwrap();
+		// Vertices are renumbered in the order the faces use them.
+		assert_eq!(back.mesh.vertex_count(), mesh.vertex_count());
+		assert_eq!(back.mesh.idx.len(), mesh.idx.len());
+		for (&i, &j) in back.mesh.idx.iter().zip(&mesh.idx) {
+			let (i, j) = (i as usize, j as usize);
+			assert_eq!(back.mesh.vtx[i], mesh.vtx[j]);
+			assert_eq!(back.mesh.nor[i], mesh.nor[j]);
+			let (a, b) = (back.mesh.st0[i], mesh.st0[j]);
+			assert!((a.x - b.x).abs() < 1e-6 && (a.y - b.y).abs() < 1e-6);
+		}
+		assert_eq!(back.groups, Obj::new(mesh).groups);
+	}
+
+	#[test]
+	fn polygons_groups_and_indices() {
+		let text = "\
+# A unit quad and an L-shaped hexagon.
+mtllib scene.mtl
+o quad
+v 0 0 0
+v 1 0 0
+v 1 1 0
+v 0 1 0
+vt 0 0
+vt 1 1
+vn 0 0 1
+usemtl red
+f 1/1/1 2/1/1 3/2/1 4/2/1
+o ell
+v 0 0 1
+v 2 0 1
+v 2 1 1
+v 1 1 1
+v 1 2 1
+v 0 2 1
+g left right
+usemtl blue
+f -6 -5 -4 \\
+  -3 -2 -1
+";
+		let obj = decode_obj(text).unwrap();
+		let mesh = &obj.mesh;
+		assert_eq!(obj.material_libraries, ["scene.mtl"]);
+		assert_eq!(mesh.vertex_count(), 10);
+		assert_eq!(mesh.triangle_count(), 2 + 4);
+		let names: Vec<_> = obj
+			.groups
+			.iter()
+			.map(|g| (g.object.as_str(), g.group.as_str(), g.material.as_deref()))
+			.collect();
+		assert_eq!(
+			names,
+			[
+				("quad", "", Some("red")),
+				("ell", "left right", Some("blue"))
+			]
+		);
+		assert!((area(mesh, obj.groups[0].triangles.clone()) - 1.0).abs() < 1e-6);
+		// Ear clipping stays inside the concave outline.
+		assert!((area(mesh, obj.groups[1].triangles.clone()) - 3.0).abs() < 1e-6);
+		// vt is flipped into st0, and missing normals face the winding.
+		assert_eq!(mesh.st0[0], Vec2::new(0.0, 1.0));
+		assert_eq!(mesh.st0[2], Vec2::new(1.0, 0.0));
+		assert!(mesh.nor.iter().all(|&n| n == Vec3::new(0.0, 0.0, 1.0)));
+		for tri in mesh.triangles() {
+			let [a, b, c] = tri.map(|i| mesh.vtx[i as usize]);
+			assert!((b - a).cross(c - a).z > 0.0);
+		}
+
+		// Group and material changes survive a round trip, including a new
+		// object under the same group name and a return to no material.
+		let mut regrouped = Obj::new(mesh.clone());
+		regrouped.groups = vec![
+			ObjGroup {
+				object: "a".to_string(),
+				group: "g".to_string(),
+				material: Some("m".to_string()),
+				triangles: 0..2,
+			},
+			ObjGroup {
+				object: "b".to_string(),
+				group: "g".to_string(),
+				material: None,
+				triangles: 2..6,
+			},
+		];
+		let back = decode_obj(&encode_obj(&regrouped)).unwrap();
+		assert_eq!(back.groups, regrouped.groups);
+
+		let err = decode_obj("v 0 0 0\nv 1 0 0\nf 1 2 3\n").unwrap_err();
+		assert_eq!(err.to_string(), "line 3: Index 3 is out of range.");
+		assert!(decode_obj("v 0 0 0\nf 0 1 1\n").is_err());
+		assert!(decode_obj("v 0 0\n").is_err());
+	}
+
+	#[test]
+	fn materials_load_beside_the_obj() {
+		let dir = std::env::temp_dir().join(format!("renderwindow-obj-{}", std::process::id()));
+		std::fs::create_dir_all(&dir).unwrap();
+		let mut obj = Obj::new(MeshGen {
+			vtx: vec![
+				Vec3::new(0.0, 0.0, 0.0),
+				Vec3::new(1.0, 0.0, 0.0),
+				Vec3::new(0.0, 1.0, 0.0),
+			],
+			idx: vec![0, 1, 2],
+			..MeshGen::default()
+		});
+		let mut brick = ObjMaterial::new("brick");
+		brick.diffuse = Vec3::new(0.5, 0.25, 0.125);
+		brick.diffuse_map = Some("brick.png".to_string());
+		brick.bump_map = Some("brick_bump.png".to_string());
+		obj.material_libraries.push("brick.mtl".to_string());
+		obj.materials.push(brick);
+		obj.groups[0] = ObjGroup {
+			object: "wall".to_string(),
+			group: String::new(),
+			material: Some("brick".to_string()),
+			triangles: 0..1,
+		};
+		save_obj(&obj, dir.join("wall.obj")).unwrap();
+		let mut back = load_obj(dir.join("wall.obj")).unwrap();
+		std::fs::remove_dir_all(&dir).unwrap();
+		// Normals are filled in on import.
+		assert_eq!(back.mesh.nor, [Vec3::new(0.0, 0.0, 1.0); 3]);
+		back.mesh.nor.clear();
+		back.mesh.st0.clear();
+		assert_eq!(back, obj);
+
+		let m = &decode_mtl("newmtl a\nKd 0.5\nTr 0.25\nbump -bm 2 h.png\n").unwrap()[0];
+		assert_eq!(m.diffuse, Vec3::new(0.5, 0.5, 0.5));
+		assert_eq!(m.dissolve, 0.75);
+		assert_eq!(m.bump_map.as_deref(), Some("h.png"));
+		assert!(decode_mtl("Kd 1 1 1\n").is_err());
+	}
+}
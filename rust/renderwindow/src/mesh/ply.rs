@@ -0,0 +1,721 @@
+////////////////////////////////////////////////////////////////////////////////
+// PLY
+////////////////////////////////////////////////////////////////////////////////
+//
+// A PLY header declares elements, each a table of typed properties, followed by
+// the tables in ASCII or binary. The `vertex` element's positions, normals,
+// texture coordinates and colours map onto `MeshGen` and `Ply::colors`; any
+// other vertex property is kept by name and type so it survives a round trip.
+// The `face` element's index lists are ear-clipped like OBJ polygons, and other
+// elements are read past and dropped. Texture coordinates run up the image, as
+// in OBJ, so t is flipped.
+
+use std::io;
+use std::path::Path;
+
+use super::obj::triangulate;
+use super::MeshGen;
+use crate::math::{Vec2, Vec3, Vec4};
+
+/// The three encodings of a PLY body.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PlyFormat {
+	Ascii,
+	#[default]
+	BinaryLittleEndian,
+	BinaryBigEndian,
+}
+
+impl PlyFormat {
+	fn name(self) -> &'static str {
+		match self {
+			Self::Ascii => "ascii",
+			Self::BinaryLittleEndian => "binary_little_endian",
+			Self::BinaryBigEndian => "binary_big_endian",
+		}
+	}
+}
+
+/// A PLY scalar type.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PlyType {
+	I8,
+	U8,
+	I16,
+	U16,
+	I32,
+	U32,
+	F32,
+	F64,
+}
+
+impl PlyType {
+	fn parse(name: &str) -> Option<Self> {
+		Some(match name {
+			"char" | "int8" => Self::I8,
+			"uchar" | "uint8" => Self::U8,
+			"short" | "int16" => Self::I16,
+			"ushort" | "uint16" => Self::U16,
+			"int" | "int32" => Self::I32,
+			"uint" | "uint32" => Self::U32,
+			"float" | "float32" => Self::F32,
+			"double" | "float64" => Self::F64,
+			_ => return None,
+		})
+	}
+
+	fn name(self) -> &'static str {
+		match self {
+			Self::I8 => "char",
+			Self::U8 => "uchar",
+			Self::I16 => "short",
+			Self::U16 => "ushort",
+			Self::I32 => "int",
+			Self::U32 => "uint",
+			Self::F32 => "float",
+			Self::F64 => "double",
+		}
+	}
+
+	fn size(self) -> usize {
+		match self {
+			Self::I8 | Self::U8 => 1,
+			Self::I16 | Self::U16 => 2,
+			Self::I32 | Self::U32 | Self::F32 => 4,
+			Self::F64 => 8,
+		}
+	}
+
+	fn is_integer(self) -> bool {
+		!matches!(self, Self::F32 | Self::F64)
+	}
+}
+
+/// A vertex property with no `MeshGen` counterpart, such as `quality` or
+/// `intensity`. Every type converts to `f64` without loss.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PlyProperty {
+	pub name: String,
+	pub kind: PlyType,
+	/// One value per vertex.
+	pub values: Vec<f64>,
+}
+
+/// A mesh or point cloud with the extra vertex data of a PLY file.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Ply {
+	/// Without a `face` element `idx` is empty.
+	pub mesh: MeshGen,
+	/// RGBA in 0..=1, either empty or parallel to `mesh.vtx`.
+	pub colors: Vec<Vec4>,
+	/// The remaining vertex properties, in header order.
+	pub properties: Vec<PlyProperty>,
+	pub comments: Vec<String>,
+}
+
+impl Ply {
+	pub fn new(mesh: MeshGen) -> Self {
+		Self {
+			mesh,
+			..Self::default()
+		}
+	}
+
+	pub fn property(&self, name: &str) -> Option<&PlyProperty> {
+		self.properties.iter().find(|p| p.name == name)
+	}
+}
+
+impl From<MeshGen> for Ply {
+	fn from(mesh: MeshGen) -> Self {
+		Self::new(mesh)
+	}
+}
+
+fn invalid(message: &str) -> io::Error {
+	io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// Reading
+////////////////////////////////////////////////////////////////////////////////
+
+enum Property {
+	Scalar(PlyType, String),
+	List(PlyType, PlyType, String),
+}
+
+struct Element {
+	name: String,
+	count: usize,
+	properties: Vec<Property>,
+}
+
+struct Header {
+	format: PlyFormat,
+	elements: Vec<Element>,
+	comments: Vec<String>,
+}
+
+// The header, and the offset of the body after `end_header`.
+fn parse_header(bytes: &[u8]) -> io::Result<(Header, usize)> {
+	if !bytes.starts_with(b"ply") {
+		return Err(invalid("A PLY file starts with `ply`."));
+	}
+	let mut header = Header {
+		format: PlyFormat::Ascii,
+		elements: Vec::new(),
+		comments: Vec::new(),
+	};
+	let mut format = None;
+	let mut offset = 0;
+	loop {
+		let Some(end) = bytes[offset..].iter().position(|&b| b == b'\n') else {
+			return Err(invalid("The PLY header has no `end_header`."));
+		};
+		let line = std::str::from_utf8(&bytes[offset..offset + end])
+			.map_err(|_| invalid("The PLY header must be text."))?;
+		offset += end + 1;
+		let mut words = line.split_whitespace();
+		let incomplete = || invalid(&format!("Incomplete line `{line}`."));
+		let keyword = words.next();
+		let mut word = || words.next().ok_or_else(incomplete);
+		match keyword {
+			Some("ply") | Some("obj_info") | None => {}
+			Some("end_header") => break,
+			Some("comment") => {
+				let text = line.trim_start().strip_prefix("comment").unwrap_or("");
+				header.comments.push(text.trim().to_string());
+			}
+			Some("format") => {
+				format = Some(match (word()?, word()?) {
+					("ascii", "1.0") => PlyFormat::Ascii,
+					("binary_little_endian", "1.0") => PlyFormat::BinaryLittleEndian,
+					("binary_big_endian", "1.0") => PlyFormat::BinaryBigEndian,
+					_ => return Err(invalid(&format!("Unsupported format `{line}`."))),
+				});
+			}
+			Some("element") => {
+				let name = word()?.to_string();
+				let count = word()?
+					.parse()
+					.map_err(|_| invalid(&format!("Bad element count in `{line}`.")))?;
+				header.elements.push(Element {
+					name,
+					count,
+					properties: Vec::new(),
+				});
+			}
+			Some("property") => {
+				let kind = |w: &str| {
+					PlyType::parse(w).ok_or_else(|| invalid(&format!("Unknown type `{w}`.")))
+				};
+				let property = match word()? {
+					"list" => {
+						let count = kind(word()?)?;
+						let item = kind(word()?)?;
+						Property::List(count, item, word()?.to_string())
+					}
+					w => Property::Scalar(kind(w)?, word()?.to_string()),
+				};
+				header
+					.elements
+					.last_mut()
+					.ok_or_else(|| invalid("A property comes before any element."))?
+					.properties
+					.push(property);
+			}
+			Some(_) => return Err(invalid(&format!("Unknown header line `{line}`."))),
+		}
+	}
+	header.format = format.ok_or_else(|| invalid("The PLY header has no format."))?;
+	Ok((header, offset))
+}
+
+// Values of the body in order, from whitespace-separated text or packed bytes.
+struct Body<'a> {
+	format: PlyFormat,
+	bytes: &'a [u8],
+	words: std::str::SplitAsciiWhitespace<'a>,
+	words_left: usize,
+}
+
+impl Body<'_> {
+	// Bytes or words still unread; every row takes at least one.
+	fn remaining(&self) -> usize {
+		match self.format {
+			PlyFormat::Ascii => self.words_left,
+			_ => self.bytes.len(),
+		}
+	}
+
+	fn scalar(&mut self, kind: PlyType) -> io::Result<f64> {
+		let end = || invalid("The PLY data ends early.");
+		if self.format == PlyFormat::Ascii {
+			let word = self.words.next().ok_or_else(end)?;
+			self.words_left -= 1;
+			let value: f64 = word
+				.parse()
+				.map_err(|_| invalid(&format!("Expected a number, found `{word}`.")))?;
+			if kind.is_integer() && value.fract() != 0.0 {
+				return Err(invalid(&format!("Expected an integer, found `{word}`.")));
+			}
+			return Ok(value);
+		}
+		let size = kind.size();
+		if self.bytes.len() < size {
+			return Err(end());
+		}
+		let mut b = [0; 8];
+		b[..size].copy_from_slice(&self.bytes[..size]);
+		self.bytes = &self.bytes[size..];
+		if self.format == PlyFormat::BinaryBigEndian {
+			b[..size].reverse();
+		}
+		let [b0, b1, b2, b3, ..] = b;
+		Ok(match kind {
+			PlyType::I8 => b0 as i8 as f64,
+			PlyType::U8 => b0 as f64,
+			PlyType::I16 => i16::from_le_bytes([b0, b1]) as f64,
+			PlyType::U16 => u16::from_le_bytes([b0, b1]) as f64,
+			PlyType::I32 => i32::from_le_bytes([b0, b1, b2, b3]) as f64,
+			PlyType::U32 => u32::from_le_bytes([b0, b1, b2, b3]) as f64,
+			PlyType::F32 => f32::from_le_bytes([b0, b1, b2, b3]) as f64,
+			PlyType::F64 => f64::from_le_bytes(b),
+		})
+	}
+
+	fn list(&mut self, count: PlyType, item: PlyType) -> io::Result<Vec<f64>> {
+		let n = self.scalar(count)?;
+		if n < 0.0 {
+			return Err(invalid("A PLY list has a negative length."));
+		}
+		(0..n as usize).map(|_| self.scalar(item)).collect()
+	}
+}
+
+// A vertex property's name, type and values.
+type Column = (String, PlyType, Vec<f64>);
+
+// Remove the columns `names` if all of them are present.
+fn take(columns: &mut Vec<Column>, names: &[&str]) -> Option<Vec<Column>> {
+	if !names.iter().all(|n| columns.iter().any(|c| c.0 == *n)) {
+		return None;
+	}
+	let mut taken = Vec::with_capacity(names.len());
+	for name in names {
+		let i = columns.iter().position(|c| c.0 == *name)?;
+		taken.push(columns.remove(i));
+	}
+	Some(taken)
+}
+
+// Texture coordinate property pairs in the spellings exporters use.
+const TEXCOORDS: [[&str; 2]; 4] = [
+	["s", "t"],
+	["u", "v"],
+	["texture_u", "texture_v"],
+	["texture_s", "texture_t"],
+];
+
+/// Read a PLY file. Faces without normals get area-weighted normals, and
+/// missing texture coordinates are (0, 0). Integer colours are scaled from
+/// their type's range to 0..=1 and a missing alpha is 1.
+pub fn decode_ply(bytes: &[u8]) -> io::Result<Ply> {
+	let (header, offset) = parse_header(bytes)?;
+	let mut body = Body {
+		format: header.format,
+		bytes: &bytes[offset..],
+		words: "".split_ascii_whitespace(),
+		words_left: 0,
+	};
+	if header.format == PlyFormat::Ascii {
+		let text =
+			std::str::from_utf8(body.bytes).map_err(|_| invalid("ASCII PLY data must be text."))?;
+		body.words = text.split_ascii_whitespace();
+		body.words_left = text.split_ascii_whitespace().count();
+	}
+
+	// Vertex columns in header order, and face polygons.
+	let mut columns: Vec<Column> = Vec::new();
+	let mut faces: Vec<Vec<f64>> = Vec::new();
+	let mut vertex_count = 0;
+	for element in &header.elements {
+		if element.count > body.remaining() {
+			return Err(invalid(&format!(
+				"The PLY data is too short for {} {} rows.",
+				element.count, element.name
+			)));
+		}
+		let is_vertex = element.name == "vertex";
+		let is_face = element.name == "face";
+		if is_vertex {
+			vertex_count = element.count;
+			for property in &element.properties {
+				match property {
+					Property::Scalar(kind, name) => columns.push((name.clone(), *kind, Vec::new())),
+					Property::List(..) => {
+						return Err(invalid("List vertex properties are not supported."))
+					}
+				}
+			}
+		}
+		for _ in 0..element.count {
+			for (i, property) in element.properties.iter().enumerate() {
+				match property {
+					Property::Scalar(kind, _) => {
+						let value = body.scalar(*kind)?;
+						if is_vertex {
+							columns[i].2.push(value);
+						}
+					}
+					Property::List(count, item, name) => {
+						let list = body.list(*count, *item)?;
+						if is_face && (name == "vertex_indices" || name == "vertex_index") {
+							faces.push(list);
+						}
+					}
+				}
+			}
+		}
+	}
+
+	let mut ply = Ply {
+		comments: header.comments,
+		..Ply::default()
+	};
+	let xyz = take(&mut columns, &["x", "y", "z"])
+		.ok_or_else(|| invalid("PLY vertices need x, y and z."))?;
+	let vec3 = |c: &[Column]| -> Vec<Vec3> {
+		(0..vertex_count)
+			.map(|i| Vec3::new(c[0].2[i] as f32, c[1].2[i] as f32, c[2].2[i] as f32))
+			.collect()
+	};
+	ply.mesh.vtx = vec3(&xyz);
+	let normals = take(&mut columns, &["nx", "ny", "nz"]);
+	let texcoords = TEXCOORDS.iter().find_map(|pair| take(&mut columns, pair));
+	ply.mesh.st0 = match texcoords {
+		Some(c) => (0..vertex_count)
+			.map(|i| Vec2::new(c[0].2[i] as f32, 1.0 - c[1].2[i] as f32))
+			.collect(),
+		None => vec![Vec2::default(); vertex_count],
+	};
+	if let Some(mut rgb) = take(&mut columns, &["red", "green", "blue"]) {
+		let alpha = take(&mut columns, &["alpha"]);
+		let scale = |kind: PlyType| match kind {
+			PlyType::U8 => 255.0,
+			PlyType::U16 => 65535.0,
+			PlyType::U32 => u32::MAX as f64,
+			PlyType::I8 => 127.0,
+			PlyType::I16 => 32767.0,
+			PlyType::I32 => i32::MAX as f64,
+			PlyType::F32 | PlyType::F64 => 1.0,
+		};
+		rgb.extend(alpha.into_iter().flatten());
+		ply.colors = (0..vertex_count)
+			.map(|i| {
+				let c = |k: usize| rgb.get(k).map_or(1.0, |c| (c.2[i] / scale(c.1)) as f32);
+				Vec4::new(c(0), c(1), c(2), c(3))
+			})
+			.collect();
+	}
+	ply.properties = columns
+		.into_iter()
+		.map(|(name, kind, values)| PlyProperty { name, kind, values })
+		.collect();
+
+	for face in faces {
+		let mut corners = Vec::with_capacity(face.len());
+		for &i in &face {
+			if i < 0.0 || i >= vertex_count as f64 {
+				return Err(invalid(&format!("Face index {i} is out of range.")));
+			}
+			corners.push(i as u32);
+		}
+		if corners.len() < 3 {
+			return Err(invalid("A face needs at least three corners."));
+		}
+		let points: Vec<Vec3> = corners.iter().map(|&i| ply.mesh.vtx[i as usize]).collect();
+		for tri in triangulate(&points) {
+			ply.mesh.idx.extend(tri.map(|k| corners[k]));
+		}
+	}
+	match normals {
+		Some(c) => ply.mesh.nor = vec3(&c),
+		None => ply.mesh.generate_normals(),
+	}
+	Ok(ply)
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// Writing
+////////////////////////////////////////////////////////////////////////////////
+
+struct Writer {
+	format: PlyFormat,
+	out: Vec<u8>,
+	// No separator before the first ASCII value of a row.
+	row_start: bool,
+}
+
+impl Writer {
+	fn scalar(&mut self, kind: PlyType, value: f64) {
+		if self.format == PlyFormat::Ascii {
+			if !self.row_start {
+				self.out.push(b' ');
+			}
+			self.row_start = false;
+			let text = match kind {
+				PlyType::F32 => (value as f32).to_string(),
+				PlyType::F64 => value.to_string(),
+				_ => (value as i64).to_string(),
+			};
+			self.out.extend(text.bytes());
+			return;
+		}
+		let mut b = match kind {
+			PlyType::I8 => (value as i8).to_le_bytes().to_vec(),
+			PlyType::U8 => (value as u8).to_le_bytes().to_vec(),
+			PlyType::I16 => (value as i16).to_le_bytes().to_vec(),
+			PlyType::U16 => (value as u16).to_le_bytes().to_vec(),
+			PlyType::I32 => (value as i32).to_le_bytes().to_vec(),
+			PlyType::U32 => (value as u32).to_le_bytes().to_vec(),
+			PlyType::F32 => (value as f32).to_le_bytes().to_vec(),
+			PlyType::F64 => value.to_le_bytes().to_vec(),
+		};
+		if self.format == PlyFormat::BinaryBigEndian {
+			b.reverse();
+		}
+		self.out.extend(b);
+	}
+
+	fn end_row(&mut self) {
+		if self.format == PlyFormat::Ascii {
+			self.out.push(b'\n');
+		}
+		self.row_start = true;
+	}
+}
+
+/// Write `ply` with float positions, normals and `s t` texture coordinates,
+/// `uchar` colours (with alpha only if some colour is translucent), the extra
+/// properties in their own types and triangles as `vertex_indices` lists.
+/// Empty `nor`, `st0` and `colors` arrays are left out.
+pub fn encode_ply(ply: &Ply, format: PlyFormat) -> Vec<u8> {
+	let mesh = &ply.mesh;
+	let alpha = ply.colors.iter().any(|c| c.w < 1.0);
+	let mut header = format!("ply\nformat {} 1.0\n", format.name());
+	for comment in &ply.comments {
+		header += &format!("comment {comment}\n");
+	}
+	header += &format!("element vertex {}\n", mesh.vertex_count());
+	let mut float = |names: &[&str]| {
+		for name in names {
+			header += &format!("property float {name}\n");
+		}
+	};
+	float(&["x", "y", "z"]);
+	if !mesh.nor.is_empty() {
+		float(&["nx", "ny", "nz"]);
+	}
+	if !mesh.st0.is_empty() {
+		float(&["s", "t"]);
+	}
+	if !ply.colors.is_empty() {
+		let names: &[&str] = if alpha {
+			&["red", "green", "blue", "alpha"]
+		} else {
+			&["red", "green", "blue"]
+		};
+		for name in names {
+			header += &format!("property uchar {name}\n");
+		}
+	}
+	for property in &ply.properties {
+		header += &format!("property {} {}\n", property.kind.name(), property.name);
+	}
+	if !mesh.idx.is_empty() {
+		header += &format!(
+			"element face {}\nproperty list uchar uint vertex_indices\n",
+			mesh.triangle_count()
+		);
+	}
+	header += "end_header\n";
+
+	let mut w = Writer {
+		format,
+		out: header.into_bytes(),
+		row_start: true,
+	};
+	for i in 0..mesh.vertex_count() {
+		let mut floats = mesh.vtx[i].to_array().to_vec();
+		if !mesh.nor.is_empty() {
+			floats.extend(mesh.nor[i].to_array());
+		}
+		if let Some(st) = mesh.st0.get(i) {
+			floats.extend([st.x, 1.0 - st.y]);
+		}
+		for x in floats {
+			w.scalar(PlyType::F32, x as f64);
+		}
+		if let Some(color) = ply.colors.get(i) {
+			let channels = if alpha { 4 } else { 3 };
+			for c in &color.to_array()[..channels] {
+				w.scalar(PlyType::U8, (c.clamp(0.0, 1.0) * 255.0).round() as f64);
+			}
+		}
+		for property in &ply.properties {
+			w.scalar(property.kind, property.values[i]);
+		}
+		w.end_row();
+	}
+	for tri in mesh.triangles() {
+		w.scalar(PlyType::U8, 3.0);
+		for i in tri {
+			w.scalar(PlyType::U32, i as f64);
+		}
+		w.end_row();
+	}
+	w.out
+}
+
+pub fn load_ply(path: impl AsRef<Path>) -> io::Result<Ply> {
+	decode_ply(&std::fs::read(path)?)
+}
+
+pub fn save_ply(ply: &Ply, path: impl AsRef<Path>, format: PlyFormat) -> io::Result<()> {
+	std::fs::write(path, encode_ply(ply, format))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::parametric::{create_parametric, Sphere};
+
+	#[test]
+	fn round_trip_in_every_format() {
+		let mut mesh = create_parametric(6, 4, &Sphere);
+		mesh.tan.clear();
+		let n = mesh.vertex_count();
+		let mut ply = Ply::new(mesh);
+		ply.comments.push("made by a test".to_string());
+		// Colours that survive quantization to `uchar`.
+		let byte = |k: usize| (k as f64 / 255.0) as f32;
+		ply.colors = (0..n)
+			.map(|i| Vec4::new(byte(i * 7 % 256), byte(128), 1.0, byte(200 + i % 50)))
+			.collect();
+		ply.properties.push(PlyProperty {
+			name: "quality".to_string(),
+			kind: PlyType::F64,
+			values: (0..n).map(|i| i as f64 * 0.1).collect(),
+		});
+		ply.properties.push(PlyProperty {
+			name: "label".to_string(),
+			kind: PlyType::I16,
+			values: (0..n).map(|i| -(i as f64)).collect(),
+		});
+		for format in [
+			PlyFormat::Ascii,
+			PlyFormat::BinaryLittleEndian,
+			PlyFormat::BinaryBigEndian,
+		] {
+			let back = decode_ply(&encode_ply(&ply, format)).unwrap();
+			assert_eq!(back.mesh.vtx, ply.mesh.vtx, "{format:?}");
+			assert_eq!(back.mesh.nor, ply.mesh.nor);
+			assert_eq!(back.mesh.idx, ply.mesh.idx);
+			for (a, b) in back.mesh.st0.iter().zip(&ply.mesh.st0) {
+				assert!((a.x - b.x).abs() < 1e-6 && (a.y - b.y).abs() < 1e-6);
+			}
+			assert_eq!(back.colors, ply.colors);
+			assert_eq!(back.properties, ply.properties);
+			assert_eq!(back.comments, ply.comments);
+		}
+	}
+
+	#[test]
+	fn point_clouds_polygons_and_other_elements() {
+		let text = "\
+ply
+format ascii 1.0
+comment a quad, a point and a stray element
+element vertex 5
+property float x
+property float y
+property float z
+property float u
+property float v
+property ushort red
+property ushort green
+property ushort blue
+property float intensity
+element face 1
+property uchar flags
+property list uchar int vertex_indices
+element edge 1
+property int vertex1
+property int vertex2
+end_header
+0 0 0 0 0 65535 0 0 0.5
+1 0 0 1 0 0 65535 0 0.5
+1 1 0 1 1 0 0 65535 0.5
+0 1 0 0 1 0 0 0 0.5
+5 5 5 0 0 0 0 0 1
+7 4 0 1 2 3
+0 1
+";
+		let ply = decode_ply(text.as_bytes()).unwrap();
+		let mesh = &ply.mesh;
+		assert_eq!(mesh.vertex_count(), 5);
+		assert_eq!(mesh.triangle_count(), 2);
+		// Normals face the winding; the unused point gets +Y.
+		assert!(mesh.nor[..4].iter().all(|&n| n == Vec3::new(0.0, 0.0, 1.0)));
+		assert_eq!(mesh.nor[4], Vec3::new(0.0, 1.0, 0.0));
+		assert_eq!(mesh.st0[2], Vec2::new(1.0, 0.0));
+		assert_eq!(ply.colors[0], Vec4::new(1.0, 0.0, 0.0, 1.0));
+		assert_eq!(ply.colors[2], Vec4::new(0.0, 0.0, 1.0, 1.0));
+		assert_eq!(ply.properties.len(), 1);
+		assert_eq!(ply.property("intensity").unwrap().values[4], 1.0);
+		assert_eq!(ply.comments, ["a quad, a point and a stray element"]);
+
+		// A bare point cloud keeps no faces.
+		let cloud = "\
+ply
+format binary_little_endian 1.0
+element vertex 1
+property double x
+property double y
+property double z
+end_header
+";
+		let mut bytes = cloud.as_bytes().to_vec();
+		for x in [1.0f64, 2.0, 3.0] {
+			bytes.extend(x.to_le_bytes());
+		}
+		let point = decode_ply(&bytes).unwrap();
+		assert_eq!(point.mesh.vtx, [Vec3::new(1.0, 2.0, 3.0)]);
+		assert!(point.mesh.idx.is_empty());
+
+		bytes.pop();
+		assert_eq!(
+			decode_ply(&bytes).unwrap_err().to_string(),
+			"The PLY data ends early."
+		);
+		let bad = text.replace("4 0 1 2 3", "3 0 1 9");
+		assert_eq!(
+			decode_ply(bad.as_bytes()).unwrap_err().to_string(),
+			"Face index 9 is out of range."
+		);
+		// Row counts are held to the data left, even for rows with no
+		// properties.
+		let endless =
+			b"ply\nformat binary_little_endian 1.0\nelement junk 4294967296\nend_header\n";
+		assert_eq!(
+			decode_ply(endless).unwrap_err().to_string(),
+			"The PLY data is too short for 4294967296 junk rows."
+		);
+		let long = text.replace("element edge 1", "element edge 3");
+		assert!(decode_ply(long.as_bytes()).is_err());
+		assert!(decode_ply(b"ply\nformat ascii 1.0\nelement vertex 0\n").is_err());
+		assert!(decode_ply(b"ply\nformat ascii 1.0\nend_header\n").is_err());
+	}
+}
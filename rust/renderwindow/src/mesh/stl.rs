@@ -0,0 +1,314 @@
+////////////////////////////////////////////////////////////////////////////////
+// STL
+////////////////////////////////////////////////////////////////////////////////
+//
+// STL stores every facet with its own three corners and a facet normal, and
+// nothing else. On import the corners are welded: vertices merge where both the
+// position and the facet normal agree, so flat regions share vertices while
+// creases between facets keep their hard normals. Facet normals are taken from
+// the winding, which the format requires to agree with the stored normal and
+// which many exporters leave as zero.
+//
+// A binary file starts with an 80-byte header that may itself begin with
+// `solid`, so a file is read as binary when its length matches the facet count
+// in the header, and as ASCII only if it is also text that begins that way.
+
+use std::fmt::Write as _;
+use std::io;
+use std::path::Path;
+
+use super::obj::triangulate;
+use super::{MeshGen, WeldOptions};
+use crate::math::{Vec2, Vec3};
+
+/// The two encodings of an STL file.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum StlFormat {
+	Ascii,
+	#[default]
+	Binary,
+}
+
+const HEADER: usize = 80;
+const FACET: usize = 50;
+
+fn invalid(message: &str) -> io::Error {
+	io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+fn invalid_line(line: usize, message: &str) -> io::Error {
+	invalid(&format!("line {line}: {message}"))
+}
+
+fn facet_normal(a: Vec3, b: Vec3, c: Vec3) -> Vec3 {
+	let n = (b - a).cross(c - a);
+	if n.length() > 0.0 {
+		n.normalize()
+	} else {
+		Vec3::default()
+	}
+}
+
+// Unindexed facets as a mesh with per-corner facet normals, before welding.
+#[derive(Default)]
+struct Facets {
+	mesh: MeshGen,
+}
+
+impl Facets {
+	fn push(&mut self, [a, b, c]: [Vec3; 3]) {
+		let n = facet_normal(a, b, c);
+		let next = self.mesh.vtx.len() as u32;
+		self.mesh.vtx.extend([a, b, c]);
+		self.mesh.nor.extend([n; 3]);
+		self.mesh.st0.extend([Vec2::default(); 3]);
+		self.mesh.idx.extend([next, next + 1, next + 2]);
+	}
+
+	fn finish(mut self) -> MeshGen {
+		self.mesh.weld(&WeldOptions::default());
+		self.mesh
+	}
+}
+
+fn decode_binary(bytes: &[u8]) -> io::Result<MeshGen> {
+	let count = u32::from_le_bytes(bytes[HEADER..HEADER + 4].try_into().unwrap()) as usize;
+	let body = &bytes[HEADER + 4..];
+	if body.len() / FACET < count {
+		return Err(invalid("The STL file ends before its last facet."));
+	}
+	let mut facets = Facets::default();
+	for facet in body.chunks_exact(FACET).take(count) {
+		let f = |i: usize| f32::from_le_bytes(facet[4 * i..4 * i + 4].try_into().unwrap());
+		let p = |v: usize| Vec3::new(f(3 + 3 * v), f(4 + 3 * v), f(5 + 3 * v));
+		// The stored normal (floats 0..3) and the attribute word are ignored.
+		facets.push([p(0), p(1), p(2)]);
+	}
+	Ok(facets.finish())
+}
+
+fn decode_ascii(text: &str) -> io::Result<MeshGen> {
+	let mut facets = Facets::default();
+	let mut polygon: Option<Vec<Vec3>> = None;
+	for (i, line) in text.lines().enumerate() {
+		let line_number = i + 1;
+		let mut words = line.split_whitespace();
+		match (words.next(), polygon.as_mut()) {
+			(Some("outer"), None) => polygon = Some(Vec::new()),
+			(Some("vertex"), Some(points)) => {
+				let p = words
+					.map(str::parse::<f32>)
+					.collect::<Result<Vec<_>, _>>()
+					.map_err(|_| invalid_line(line_number, "Expected a number."))?;
+				if p.len() != 3 {
+					return Err(invalid_line(
+						line_number,
+						"A vertex needs three coordinates.",
+					));
+				}
+				points.push(Vec3::new(p[0], p[1], p[2]));
+			}
+			(Some("endloop"), Some(points)) => {
+				if points.len() < 3 {
+					return Err(invalid_line(
+						line_number,
+						"A facet needs at least three vertices.",
+					));
+				}
+				for tri in triangulate(points) {
+					facets.push(tri.map(|k| points[k]));
+				}
+				polygon = None;
+			}
+			(Some("vertex" | "endloop"), None) | (Some("outer"), Some(_)) => {
+				return Err(invalid_line(
+					line_number,
+					"Unexpected statement outside a loop.",
+				));
+			}
+			// `solid`, `facet normal`, `endfacet` and `endsolid` carry nothing
+			// that is kept.
+			_ => {}
+		}
+	}
+	if polygon.is_some() {
+		return Err(invalid("The STL file ends inside a facet."));
+	}
+	Ok(facets.finish())
+}
+
+/// Read an ASCII or binary STL file as a welded mesh with facet normals and
+/// zero texture coordinates. Degenerate facets are dropped, and ASCII facets
+/// with more than three vertices are triangulated.
+pub fn decode_stl(bytes: &[u8]) -> io::Result<MeshGen> {
+	let has_header = bytes.len() >= HEADER + 4;
+	let text = std::str::from_utf8(bytes)
+		.ok()
+		.filter(|text| text.trim_start().starts_with("solid"));
+	if has_header {
+		let count = u32::from_le_bytes(bytes[HEADER..HEADER + 4].try_into().unwrap()) as usize;
+		let exact = (bytes.len() - HEADER - 4) as u64 == count as u64 * FACET as u64;
+		if exact || text.is_none() {
+			return decode_binary(bytes);
+		}
+	}
+	match text {
+		Some(text) => decode_ascii(text),
+		None => Err(invalid("The STL file is too short for a header.")),
+	}
+}
+
+/// Write every triangle of `mesh` as a facet with the normal of its winding.
+pub fn encode_stl(mesh: &MeshGen, format: StlFormat) -> Vec<u8> {
+	let facets = mesh
+		.triangles()
+		.map(|tri| tri.map(|i| mesh.vtx[i as usize]));
+	match format {
+		StlFormat::Ascii => {
+			let mut out = String::from("solid renderwindow\n");
+			for [a, b, c] in facets {
+				let n = facet_normal(a, b, c);
+				let _ = writeln!(out, "facet normal {} {} {}", n.x, n.y, n.z);
+				out.push_str("  outer loop\n");
+				for p in [a, b, c] {
+					let _ = writeln!(out, "    vertex {} {} {}", p.x, p.y, p.z);
+				}
+				out.push_str("  endloop\nendfacet\n");
+			}
+			out.push_str("endsolid renderwindow\n");
+			out.into_bytes()
+		}
+		StlFormat::Binary => {
+			let mut out = vec![b' '; HEADER];
+			let title = b"renderwindow binary STL";
+			out[..title.len()].copy_from_slice(title);
+			out.extend((mesh.triangle_count() as u32).to_le_bytes());
+			for [a, b, c] in facets {
+				for v in [facet_normal(a, b, c), a, b, c] {
+					for x in v.to_array() {
+						out.extend(x.to_le_bytes());
+					}
+				}
+				out.extend([0, 0]);
+			}
+			out
+		}
+	}
+}
+
+pub fn load_stl(path: impl AsRef<Path>) -> io::Result<MeshGen> {
+	decode_stl(&std::fs::read(path)?)
+}
+
+pub fn save_stl(mesh: &MeshGen, path: impl AsRef<Path>, format: StlFormat) -> io::Result<()> {
+	std::fs::write(path, encode_stl(mesh, format))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::parametric::{create_parametric, Torus};
+
+	// A unit cube: 8 corners, 12 outward-facing triangles.
+	fn cube() -> MeshGen {
+		let vtx = (0..8)
+			.map(|i| Vec3::new((i & 1) as f32, (i >> 1 & 1) as f32, (i >> 2 & 1) as f32))
+			.collect();
+		let quads = [
+			[0, 2, 3, 1],
+			[4, 5, 7, 6],
+			[0, 1, 5, 4],
+			[2, 6, 7, 3],
+			[0, 4, 6, 2],
+			[1, 3, 7, 5],
+		];
+		let idx = quads
+			.iter()
+			.flat_map(|&[a, b, c, d]| [a, b, c, a, c, d])
+			.collect();
+		MeshGen {
+			vtx,
+			idx,
+			..MeshGen::default()
+		}
+	}
+
+	#[test]
+	fn cube_welds_per_face() {
+		for format in [StlFormat::Ascii, StlFormat::Binary] {
+			let bytes = encode_stl(&cube(), format);
+			if format == StlFormat::Binary {
+				assert_eq!(bytes.len(), 84 + 12 * 50);
+			}
+			let mesh = decode_stl(&bytes).unwrap();
+			// Each face keeps its own four corners and its hard normal.
+			assert_eq!(mesh.vertex_count(), 24);
+			assert_eq!(mesh.triangle_count(), 12);
+			assert_eq!(mesh.st0.len(), 24);
+			for tri in mesh.triangles() {
+				let [a, b, c] = tri.map(|i| mesh.vtx[i as usize]);
+				let n = facet_normal(a, b, c);
+				assert!(tri.iter().all(|&i| mesh.nor[i as usize] == n));
+				// Outward: the normal points away from the cube's centre.
+				assert!(n.dot(a - Vec3::new(0.5, 0.5, 0.5)) > 0.0);
+			}
+		}
+	}
+
+	#[test]
+	fn binary_header_may_start_with_solid() {
+		let mut mesh = create_parametric(8, 6, &Torus::new(2.0, 0.5));
+		mesh.weld(&WeldOptions::default());
+		let mut bytes = encode_stl(&mesh, StlFormat::Binary);
+		bytes[..5].copy_from_slice(b"solid");
+		let back = decode_stl(&bytes).unwrap();
+		assert_eq!(back.triangle_count(), mesh.triangle_count());
+		// Only coplanar neighbours share corners.
+		assert!(back.vertex_count() < 3 * mesh.triangle_count());
+		for tri in back.triangles() {
+			let [a, b, c] = tri.map(|i| back.vtx[i as usize]);
+			let n = facet_normal(a, b, c);
+			assert!(tri
+				.iter()
+				.all(|&i| (back.nor[i as usize] - n).length() < 1e-3));
+		}
+
+		bytes.truncate(bytes.len() - 1);
+		let err = decode_stl(&bytes).unwrap_err();
+		assert_eq!(err.to_string(), "The STL file ends before its last facet.");
+	}
+
+	#[test]
+	fn ascii_polygons_and_errors() {
+		let text = "\
+solid square
+  facet normal 0 0 0
+    outer loop
+      vertex 0 0 0
+      vertex 1 0 0
+      vertex 1 1 0
+      vertex 0 1 0
+    endloop
+  endfacet
+  facet normal 0 0 1
+    outer loop
+      vertex 0 0 0
+      vertex 0 0 0
+      vertex 1 1 0
+    endloop
+  endfacet
+endsolid square
+";
+		let mesh = decode_stl(text.as_bytes()).unwrap();
+		// The quad splits into two facets sharing a diagonal, and the
+		// degenerate facet is dropped.
+		assert_eq!(mesh.vertex_count(), 4);
+		assert_eq!(mesh.triangle_count(), 2);
+		assert!(mesh.nor.iter().all(|&n| n == Vec3::new(0.0, 0.0, 1.0)));
+
+		let err = decode_stl(b"solid x\nfacet normal 0 0 1\nouter loop\nvertex 0 0\n").unwrap_err();
+		assert_eq!(err.to_string(), "line 4: A vertex needs three coordinates.");
+		assert!(decode_stl(b"solid x\nouter loop\nvertex 0 0 0\n").is_err());
+		assert!(decode_stl(b"not an stl").is_err());
+	}
+}
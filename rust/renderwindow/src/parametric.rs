@@ -0,0 +1,1095 @@
+////////////////////////////////////////////////////////////////////////////////
+// Parametric UV Shapes
+////////////////////////////////////////////////////////////////////////////////
+
+use std::f32::consts::PI;
+
+use crate::dual::{dual_partials, Dual, Real};
+use crate::math::{Vec2, Vec3, Vec4};
+use crate::mesh::{orthonormal_tangent, uv_tangents, MeshGen};
+
+/// A surface defined over u, v in [0, 1]; the `Parametric(Pos, Nor, ST0)`
+/// triple of the Python and JS ports.
+pub trait Parametric {
+	fn pos(&self, u: f32, v: f32) -> Vec3;
+	/// Defaults to [`derived_normal`].
+	fn nor(&self, u: f32, v: f32) -> Vec3 {
+		derived_normal(self, u, v)
+	}
+	fn st0(&self, u: f32, v: f32) -> Vec2 {
+		unit_uv(u, v)
+	}
+	/// `(dpos/du, dpos/dv)`, if known in closed form. Tangents come from the
+	/// triangles' texture coordinates otherwise.
+	fn partials(&self, _u: f32, _v: f32) -> Option<(Vec3, Vec3)> {
+		None
+	}
+}
+
+pub fn unit_uv(u: f32, v: f32) -> Vec2 {
+	Vec2::new(u, v)
+}
+
+/// A unit XZ plane centered on the origin, facing +Y.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Plane;
+
+impl Parametric for Plane {
+	fn pos(&self, u: f32, v: f32) -> Vec3 {
+		Vec3::new(u - 0.5, 0.0, 0.5 - v)
+	}
+
+	fn nor(&self, _u: f32, _v: f32) -> Vec3 {
+		Vec3::new(0.0, 1.0, 0.0)
+	}
+
+	fn partials(&self, _u: f32, _v: f32) -> Option<(Vec3, Vec3)> {
+		Some((Vec3::new(1.0, 0.0, 0.0), Vec3::new(0.0, 0.0, -1.0)))
+	}
+}
+
+/// A unit sphere with its poles on the Y axis.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Sphere;
+
+impl Parametric for Sphere {
+	fn pos(&self, u: f32, v: f32) -> Vec3 {
+		let au = (2.0 * PI) * u;
+		let av = (1.0 * PI) * v;
+		let s = av.sin();
+		Vec3::new(s * au.cos(), av.cos(), s * au.sin())
+	}
+
+	fn nor(&self, u: f32, v: f32) -> Vec3 {
+		self.pos(u, v)
+	}
+
+	fn partials(&self, u: f32, v: f32) -> Option<(Vec3, Vec3)> {
+		let au = (2.0 * PI) * u;
+		let av = (1.0 * PI) * v;
+		let (s, c) = av.sin_cos();
+		let dpdu = Vec3::new(-s * au.sin(), 0.0, s * au.cos()) * (2.0 * PI);
+		let dpdv = Vec3::new(c * au.cos(), -s, c * au.sin()) * PI;
+		Some((dpdu, dpdv))
+	}
+}
+
+/// A torus around the Y axis.
+#[derive(Clone, Copy, Debug)]
+pub struct Torus {
+	pub major: f32,
+	pub minor: f32,
+}
+
+impl Torus {
+	pub fn new(major: f32, minor: f32) -> Self {
+		Self { major, minor }
+	}
+}
+
+impl Parametric for Torus {
+	fn pos(&self, u: f32, v: f32) -> Vec3 {
+		let au = (2.0 * PI) * u;
+		let av = (2.0 * PI) * v;
+		//x = (R + rcos(v))cos(u), y = (R + rcos(v))sin(u), and z = rsin(v).
+		let r = self.major + self.minor * av.cos();
+		Vec3::new(r * au.cos(), self.minor * av.sin(), r * au.sin())
+	}
+
+	fn nor(&self, u: f32, v: f32) -> Vec3 {
+		Torus::new(0.0, 1.0).pos(u, v)
+	}
+
+	fn partials(&self, u: f32, v: f32) -> Option<(Vec3, Vec3)> {
+		let au = (2.0 * PI) * u;
+		let av = (2.0 * PI) * v;
+		let r = self.major + self.minor * av.cos();
+		let dpdu = Vec3::new(-r * au.sin(), 0.0, r * au.cos()) * (2.0 * PI);
+		let m = self.minor * (2.0 * PI);
+		let dpdv = Vec3::new(-av.sin() * au.cos(), av.cos(), -av.sin() * au.sin()) * m;
+		Some((dpdu, dpdv))
+	}
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// Derived Normals
+////////////////////////////////////////////////////////////////////////////////
+//
+// ModelUV.swift's `generateNormal` takes lopsided 0.01 / 0.001 differences and
+// the Python torus passes its own unit-radius position as the normal. Here the
+// normal is the cross product of the partials, exact when they come from
+// `Parametric::partials` or [`AutoDiff`], with edges where one partial
+// vanishes (the poles of a sphere, the apex of a cone) taken in the limit from
+// inside the domain.
+
+// A partial shorter than this fraction of the other is treated as zero.
+const DEGENERATE: f32 = 1e-4;
+const LIMIT_STEP: f32 = 1.0 / 1024.0;
+
+/// `(dpos/du, dpos/dv)` by central differences of `pos`.
+pub fn central_partials<P: Parametric + ?Sized>(shape: &P, u: f32, v: f32) -> (Vec3, Vec3) {
+	let h = LIMIT_STEP;
+	let dpdu = (shape.pos(u + h, v) - shape.pos(u - h, v)) * (0.5 / h);
+	let dpdv = (shape.pos(u, v + h) - shape.pos(u, v - h)) * (0.5 / h);
+	(dpdu, dpdv)
+}
+
+/// `normalize(dpos/du x dpos/dv)`, from [`Parametric::partials`] or else
+/// [`central_partials`]. Where one partial vanishes along an edge of the
+/// domain it is replaced by its value a small step inside, which points the
+/// way the normals converge.
+pub fn derived_normal<P: Parametric + ?Sized>(shape: &P, u: f32, v: f32) -> Vec3 {
+	let partials = |u, v| {
+		shape
+			.partials(u, v)
+			.unwrap_or_else(|| central_partials(shape, u, v))
+	};
+	let inward = |x: f32| {
+		if x < 0.5 {
+			x + LIMIT_STEP
+		} else {
+			x - LIMIT_STEP
+		}
+	};
+	let (mut dpdu, mut dpdv) = partials(u, v);
+	let scale = dpdu.length().max(dpdv.length());
+	if dpdu.length() <= DEGENERATE * scale {
+		dpdu = partials(u, inward(v)).0;
+	} else if dpdv.length() <= DEGENERATE * scale {
+		dpdv = partials(inward(u), v).1;
+	}
+	dpdu.cross(dpdv).normalize()
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// Automatic Differentiation
+////////////////////////////////////////////////////////////////////////////////
+
+/// A surface position written once over any [`Real`] scalar, so that
+/// [`AutoDiff`] can evaluate it on dual numbers for exact partials.
+pub trait Surface {
+	fn point<S: Real>(&self, u: S, v: S) -> [S; 3];
+}
+
+/// A [`Parametric`] shape whose partials, tangents and normals all come from
+/// differentiating a [`Surface`], with [`unit_uv`] texture coordinates.
+/// Normals are `dpos/du x dpos/dv`, or the reverse when `flip` is set.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct AutoDiff<F> {
+	pub surface: F,
+	pub flip: bool,
+}
+
+impl<F: Surface> AutoDiff<F> {
+	pub fn new(surface: F) -> Self {
+		Self {
+			surface,
+			flip: false,
+		}
+	}
+
+	pub fn flipped(self) -> Self {
+		Self {
+			flip: !self.flip,
+			..self
+		}
+	}
+}
+
+impl<F: Surface> Parametric for AutoDiff<F> {
+	fn pos(&self, u: f32, v: f32) -> Vec3 {
+		surface_pos(&self.surface, u, v)
+	}
+
+	fn nor(&self, u: f32, v: f32) -> Vec3 {
+		let n = derived_normal(self, u, v);
+		if self.flip {
+			-n
+		} else {
+			n
+		}
+	}
+
+	fn partials(&self, u: f32, v: f32) -> Option<(Vec3, Vec3)> {
+		Some(surface_partials(&self.surface, u, v))
+	}
+}
+
+pub fn surface_pos<F: Surface>(surface: &F, u: f32, v: f32) -> Vec3 {
+	let [x, y, z] = surface.point(u, v);
+	Vec3::new(x, y, z)
+}
+
+/// `(dpos/du, dpos/dv)` of a [`Surface`] by dual numbers.
+pub fn surface_partials<F: Surface>(surface: &F, u: f32, v: f32) -> (Vec3, Vec3) {
+	let (_, dpdu, dpdv) = dual_partials(surface.point(Dual::u(u), Dual::v(v)));
+	(dpdu, dpdv)
+}
+
+// `Parametric` for shapes written as a `Surface`, without the `AutoDiff`
+// wrapper. Normals are `dpos/du x dpos/dv`.
+macro_rules! parametric_surface {
+	($($t:ty),*) => {$(
+		impl Parametric for $t {
+			fn pos(&self, u: f32, v: f32) -> Vec3 {
+				surface_pos(self, u, v)
+			}
+
+			fn partials(&self, u: f32, v: f32) -> Option<(Vec3, Vec3)> {
+				Some(surface_partials(self, u, v))
+			}
+		}
+	)*};
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// Shape Library
+////////////////////////////////////////////////////////////////////////////////
+//
+// Everything here follows `Sphere`: Y is up, u runs around the Y axis from +X
+// towards +Z, v runs from the top down, and `dpos/du x dpos/dv` faces out.
+
+/// An open tube of `radius` around the Y axis, centered on the origin.
+#[derive(Clone, Copy, Debug)]
+pub struct Cylinder {
+	pub radius: f32,
+	pub height: f32,
+}
+
+impl Cylinder {
+	pub fn new(radius: f32, height: f32) -> Self {
+		Self { radius, height }
+	}
+}
+
+impl Surface for Cylinder {
+	fn point<S: Real>(&self, u: S, v: S) -> [S; 3] {
+		let au = u * (2.0 * PI);
+		[
+			au.cos() * self.radius,
+			(-v + 0.5) * self.height,
+			au.sin() * self.radius,
+		]
+	}
+}
+
+/// An open cone with its apex up at `height / 2` and its base of `radius`
+/// at `-height / 2`.
+#[derive(Clone, Copy, Debug)]
+pub struct Cone {
+	pub radius: f32,
+	pub height: f32,
+}
+
+impl Cone {
+	pub fn new(radius: f32, height: f32) -> Self {
+		Self { radius, height }
+	}
+}
+
+impl Surface for Cone {
+	fn point<S: Real>(&self, u: S, v: S) -> [S; 3] {
+		let au = u * (2.0 * PI);
+		let r = v * self.radius;
+		[r * au.cos(), (-v + 0.5) * self.height, r * au.sin()]
+	}
+}
+
+/// A cylinder of `length` capped with hemispheres, with v proportional to
+/// distance along the profile so texels keep their aspect over the joins.
+#[derive(Clone, Copy, Debug)]
+pub struct Capsule {
+	pub radius: f32,
+	pub length: f32,
+}
+
+impl Capsule {
+	pub fn new(radius: f32, length: f32) -> Self {
+		Self { radius, length }
+	}
+}
+
+impl Surface for Capsule {
+	fn point<S: Real>(&self, u: S, v: S) -> [S; 3] {
+		let (r, half) = (self.radius, self.length * 0.5);
+		let cap = 0.5 * PI * r;
+		let s = v * (2.0 * cap + self.length);
+		let (rho, y) = if s.value() < cap {
+			let a = s / r;
+			(a.sin() * r, a.cos() * r + half)
+		} else if s.value() <= cap + self.length {
+			(S::from(r), -(s - cap) + half)
+		} else {
+			let a = (s - (cap + self.length)) / r + 0.5 * PI;
+			(a.sin() * r, a.cos() * r - half)
+		};
+		let au = u * (2.0 * PI);
+		[rho * au.cos(), y, rho * au.sin()]
+	}
+}
+
+/// A flat ring between `inner` and `outer` facing +Y, a full disk when
+/// `inner` is 0. Texture coordinates are projected from above, matching
+/// `Plane` scaled to the disk's diameter.
+#[derive(Clone, Copy, Debug)]
+pub struct Disk {
+	pub inner: f32,
+	pub outer: f32,
+}
+
+impl Disk {
+	pub fn new(inner: f32, outer: f32) -> Self {
+		Self { inner, outer }
+	}
+}
+
+impl Surface for Disk {
+	fn point<S: Real>(&self, u: S, v: S) -> [S; 3] {
+		let au = u * (2.0 * PI);
+		let rho = v * (self.outer - self.inner) + self.inner;
+		[rho * au.cos(), S::from(0.0), rho * au.sin()]
+	}
+}
+
+impl Parametric for Disk {
+	fn pos(&self, u: f32, v: f32) -> Vec3 {
+		surface_pos(self, u, v)
+	}
+
+	fn st0(&self, u: f32, v: f32) -> Vec2 {
+		let p = surface_pos(self, u, v) * (0.5 / self.outer);
+		Vec2::new(0.5 + p.x, 0.5 - p.z)
+	}
+
+	fn partials(&self, u: f32, v: f32) -> Option<(Vec3, Vec3)> {
+		Some(surface_partials(self, u, v))
+	}
+}
+
+/// A Möbius strip of `width` around a circle of `radius`. One-sided: the
+/// `u = 1` edge meets `u = 0` with v reversed and the normal flipped.
+#[derive(Clone, Copy, Debug)]
+pub struct Mobius {
+	pub radius: f32,
+	pub width: f32,
+}
+
+impl Mobius {
+	pub fn new(radius: f32, width: f32) -> Self {
+		Self { radius, width }
+	}
+}
+
+impl Surface for Mobius {
+	fn point<S: Real>(&self, u: S, v: S) -> [S; 3] {
+		let au = u * (2.0 * PI);
+		let half = au * 0.5;
+		let w = (v - 0.5) * self.width;
+		let r = w * half.cos() + self.radius;
+		[r * au.cos(), w * half.sin(), r * au.sin()]
+	}
+}
+
+/// The figure-eight immersion of the Klein bottle around a circle of
+/// `radius`. Like [`Mobius`], `u = 1` meets `u = 0` with v reversed and the
+/// normal flipped.
+#[derive(Clone, Copy, Debug)]
+pub struct KleinBottle {
+	pub radius: f32,
+}
+
+impl KleinBottle {
+	pub fn new(radius: f32) -> Self {
+		Self { radius }
+	}
+}
+
+impl Surface for KleinBottle {
+	fn point<S: Real>(&self, u: S, v: S) -> [S; 3] {
+		let a = u * (2.0 * PI);
+		let b = v * (2.0 * PI);
+		let (ch, sh) = ((a * 0.5).cos(), (a * 0.5).sin());
+		let (sb, s2b) = (b.sin(), (b * 2.0).sin());
+		let r = ch * sb - sh * s2b + self.radius;
+		[r * a.cos(), sh * sb + ch * s2b, r * a.sin()]
+	}
+}
+
+/// Barr's superellipsoid with unit radii: `e1` shapes the north-south
+/// profile and `e2` the east-west one. 1 gives a sphere, values towards 0 a
+/// cube and 2 an octahedron; exponents above 2 are not supported.
+#[derive(Clone, Copy, Debug)]
+pub struct Superellipsoid {
+	pub e1: f32,
+	pub e2: f32,
+}
+
+impl Superellipsoid {
+	pub fn new(e1: f32, e2: f32) -> Self {
+		Self { e1, e2 }
+	}
+
+	// The position with exponents `e1` and `e2` at latitude `eta` and
+	// longitude `omega`; the normal is the same with `2 - e`.
+	fn eval<S: Real>(eta: S, omega: S, e1: f32, e2: f32) -> [S; 3] {
+		// Rounding leaves cos(pi / 2) at about -4e-8, which small exponents
+		// would blow up into visible gaps at the poles and edges.
+		let p = |x: S, e| {
+			if x.value().abs() < 1e-6 {
+				(x - x.value()).signed_powf(e)
+			} else {
+				x.signed_powf(e)
+			}
+		};
+		let ce = p(eta.cos(), e1);
+		[
+			ce * p(omega.cos(), e2),
+			p(eta.sin(), e1),
+			ce * p(omega.sin(), e2),
+		]
+	}
+}
+
+impl Surface for Superellipsoid {
+	fn point<S: Real>(&self, u: S, v: S) -> [S; 3] {
+		Self::eval((-v + 0.5) * PI, u * (2.0 * PI), self.e1, self.e2)
+	}
+}
+
+impl Parametric for Superellipsoid {
+	fn pos(&self, u: f32, v: f32) -> Vec3 {
+		surface_pos(self, u, v)
+	}
+
+	// The partials are unbounded where a sine or cosine crosses zero with an
+	// exponent below 1, so the normal is taken in closed form.
+	fn nor(&self, u: f32, v: f32) -> Vec3 {
+		let [x, y, z] = Self::eval((0.5 - v) * PI, u * (2.0 * PI), 2.0 - self.e1, 2.0 - self.e2);
+		Vec3::new(x, y, z).normalize()
+	}
+
+	fn partials(&self, u: f32, v: f32) -> Option<(Vec3, Vec3)> {
+		let (dpdu, dpdv) = surface_partials(self, u, v);
+		let finite = |p: Vec3| p.to_array().iter().all(|c| c.is_finite());
+		(finite(dpdu) && finite(dpdv)).then_some((dpdu, dpdv))
+	}
+}
+
+parametric_surface!(Cylinder, Cone, Capsule, Mobius, KleinBottle);
+
+/// A surface of revolution around the Y axis from a polyline `profile` of
+/// `(radius, height)` points, with v proportional to distance along it.
+/// Listing the profile from the top down, as `Sphere` runs, gives outward
+/// normals. Normals are averaged across the profile's corners.
+#[derive(Clone, Debug)]
+pub struct Revolution {
+	profile: Vec<Vec2>,
+	// Distance along the profile to each point.
+	distance: Vec<f32>,
+	// Profile normals at each point, in the (radius, height) plane.
+	normals: Vec<Vec2>,
+}
+
+impl Revolution {
+	/// # Panics
+	///
+	/// If `profile` has fewer than two distinct points.
+	pub fn new(profile: &[Vec2]) -> Self {
+		let mut points: Vec<Vec2> = Vec::with_capacity(profile.len());
+		for &p in profile {
+			if points.last() != Some(&p) {
+				points.push(p);
+			}
+		}
+		assert!(points.len() >= 2, "a profile needs two distinct points");
+		let mut distance = vec![0.0];
+		let mut segments = Vec::with_capacity(points.len() - 1);
+		for pair in points.windows(2) {
+			let d = pair[1] - pair[0];
+			let length = d.length();
+			distance.push(distance.last().unwrap() + length);
+			segments.push(Vec2::new(-d.y, d.x) * (1.0 / length));
+		}
+		let last = points.len() - 1;
+		let normals = (0..points.len())
+			.map(|i| {
+				let before = segments[i.saturating_sub(1)];
+				let after = segments[i.min(last - 1)];
+				let n = before + after;
+				if (i == 0 || i == last) && points[i].x == 0.0 {
+					// A pole: the normal runs along the axis.
+					Vec2::new(0.0, n.y.signum())
+				} else {
+					n * (1.0 / n.length())
+				}
+			})
+			.collect();
+		Self {
+			profile: points,
+			distance,
+			normals,
+		}
+	}
+
+	// The profile segment at `v` and the fraction along it.
+	fn locate(&self, v: f32) -> (usize, f32) {
+		let s = v.clamp(0.0, 1.0) * self.distance.last().unwrap();
+		let i = self.distance[1..]
+			.partition_point(|&d| d < s)
+			.min(self.profile.len() - 2);
+		let f = (s - self.distance[i]) / (self.distance[i + 1] - self.distance[i]);
+		(i, f)
+	}
+
+	fn revolve(p: Vec2, u: f32) -> Vec3 {
+		let au = (2.0 * PI) * u;
+		Vec3::new(p.x * au.cos(), p.y, p.x * au.sin())
+	}
+}
+
+impl Parametric for Revolution {
+	fn pos(&self, u: f32, v: f32) -> Vec3 {
+		let (i, f) = self.locate(v);
+		let p = self.profile[i] + (self.profile[i + 1] - self.profile[i]) * f;
+		Self::revolve(p, u)
+	}
+
+	fn nor(&self, u: f32, v: f32) -> Vec3 {
+		let (i, f) = self.locate(v);
+		let n = self.normals[i] + (self.normals[i + 1] - self.normals[i]) * f;
+		Self::revolve(n, u).normalize()
+	}
+
+	fn partials(&self, u: f32, v: f32) -> Option<(Vec3, Vec3)> {
+		let (i, f) = self.locate(v);
+		let p = self.profile[i] + (self.profile[i + 1] - self.profile[i]) * f;
+		let au = (2.0 * PI) * u;
+		let dpdu = Vec3::new(-au.sin(), 0.0, au.cos()) * (2.0 * PI * p.x);
+		let d = (self.profile[i + 1] - self.profile[i])
+			* (self.distance.last().unwrap() / (self.distance[i + 1] - self.distance[i]));
+		Some((dpdu, Self::revolve(d, u)))
+	}
+}
+
+// Samples of the swept frame along the curve.
+const FRAME_SAMPLES: usize = 512;
+
+/// A tube of `radius` swept along `curve` over t in [0, 1], with v around
+/// the curve. The cross-section follows a rotation-minimizing frame (double
+/// reflection, Wang et al. 2008), so the tube does not twist on its own; on
+/// a closed curve the remaining twist is spread along it so the ends meet.
+#[derive(Clone, Debug)]
+pub struct SweptTube<C> {
+	curve: C,
+	pub radius: f32,
+	closed: bool,
+	// Frame normals at FRAME_SAMPLES + 1 evenly spaced t.
+	normals: Vec<Vec3>,
+}
+
+impl<C: Fn(f32) -> Vec3> SweptTube<C> {
+	pub fn new(curve: C, radius: f32) -> Self {
+		let n = FRAME_SAMPLES;
+		let points: Vec<Vec3> = (0..=n).map(|i| curve(i as f32 / n as f32)).collect();
+		let length: f32 = points.windows(2).map(|p| (p[1] - p[0]).length()).sum();
+		let closed = (points[n] - points[0]).length() <= 1e-4 * length;
+		let mut tube = Self {
+			curve,
+			radius,
+			closed,
+			normals: Vec::with_capacity(n + 1),
+		};
+		let tangents: Vec<Vec3> = (0..=n).map(|i| tube.tangent(i as f32 / n as f32)).collect();
+		let t0 = tangents[0];
+		let axis = if t0.x.abs() < 0.9 {
+			Vec3::new(1.0, 0.0, 0.0)
+		} else {
+			Vec3::new(0.0, 1.0, 0.0)
+		};
+		let mut r = (axis - t0 * t0.dot(axis)).normalize();
+		tube.normals.push(r);
+		for i in 0..n {
+			let v1 = points[i + 1] - points[i];
+			let c1 = v1.dot(v1);
+			let (mut r_l, mut t_l) = (r, tangents[i]);
+			if c1 > 0.0 {
+				r_l -= v1 * (2.0 / c1 * v1.dot(r));
+				t_l -= v1 * (2.0 / c1 * v1.dot(tangents[i]));
+			}
+			let v2 = tangents[i + 1] - t_l;
+			let c2 = v2.dot(v2);
+			r = if c2 > 0.0 {
+				r_l - v2 * (2.0 / c2 * v2.dot(r_l))
+			} else {
+				r_l
+			};
+			tube.normals.push(r);
+		}
+		if closed {
+			let end = tube.normals[n];
+			let twist = end
+				.cross(tube.normals[0])
+				.dot(t0)
+				.atan2(end.dot(tube.normals[0]));
+			for (i, r) in tube.normals.iter_mut().enumerate() {
+				let (s, c) = (twist * i as f32 / n as f32).sin_cos();
+				*r = *r * c + tangents[i].cross(*r) * s;
+			}
+		}
+		tube
+	}
+
+	// Unit tangent by central differences, wrapping around closed curves.
+	fn tangent(&self, t: f32) -> Vec3 {
+		let h = 1.0 / (4 * FRAME_SAMPLES) as f32;
+		let (a, b) = if self.closed {
+			((t - h).rem_euclid(1.0), (t + h).rem_euclid(1.0))
+		} else {
+			let t = t.clamp(h, 1.0 - h);
+			(t - h, t + h)
+		};
+		((self.curve)(b) - (self.curve)(a)).normalize()
+	}
+
+	// Unit direction from the curve to the surface.
+	fn radial(&self, u: f32, v: f32) -> Vec3 {
+		let t = self.tangent(u);
+		let x = u.clamp(0.0, 1.0) * FRAME_SAMPLES as f32;
+		let i = (x as usize).min(FRAME_SAMPLES - 1);
+		let f = x - i as f32;
+		let r = self.normals[i] + (self.normals[i + 1] - self.normals[i]) * f;
+		let n = (r - t * t.dot(r)).normalize();
+		let b = t.cross(n);
+		let (s, c) = ((2.0 * PI) * v).sin_cos();
+		n * c - b * s
+	}
+}
+
+impl SweptTube<fn(f32) -> Vec3> {
+	/// A tube around the trefoil knot, scaled to about a unit radius.
+	pub fn trefoil(radius: f32) -> Self {
+		fn trefoil(t: f32) -> Vec3 {
+			let a = (2.0 * PI) * t;
+			Vec3::new(
+				a.sin() + 2.0 * (2.0 * a).sin(),
+				-(3.0 * a).sin(),
+				a.cos() - 2.0 * (2.0 * a).cos(),
+			) * (1.0 / 3.0)
+		}
+		Self::new(trefoil, radius)
+	}
+}
+
+impl<C: Fn(f32) -> Vec3> Parametric for SweptTube<C> {
+	fn pos(&self, u: f32, v: f32) -> Vec3 {
+		(self.curve)(u) + self.radial(u, v) * self.radius
+	}
+
+	fn nor(&self, u: f32, v: f32) -> Vec3 {
+		self.radial(u, v)
+	}
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// Parametric Buffer Construction
+////////////////////////////////////////////////////////////////////////////////
+
+// Sample `f` on a (usteps + 1) x (vsteps + 1) grid, u varying fastest.
+fn create_parametric_vec<T>(usteps: u32, vsteps: u32, f: impl Fn(f32, f32) -> T) -> Vec<T> {
+	let mut vec = Vec::with_capacity(((usteps + 1) * (vsteps + 1)) as usize);
+	for v in 0..=vsteps {
+		for u in 0..=usteps {
+			vec.push(f(u as f32 / usteps as f32, v as f32 / vsteps as f32));
+		}
+	}
+	vec
+}
+
+/// Generate UV indices to match `create_parametric`, two triangles per quad.
+// The `+ 0` terms are kept so the layout reads the same as module_parametric.py.
+#[allow(clippy::identity_op)]
+pub fn create_parametric_indices(usteps: u32, vsteps: u32) -> Vec<u32> {
+	let us = usteps + 1;
+	let mut idx = Vec::with_capacity((6 * usteps * vsteps) as usize);
+	for v in 0..vsteps {
+		for u in 0..usteps {
+			idx.extend_from_slice(&[
+				(u + 0) + (v + 0) * us,
+				(u + 1) + (v + 0) * us,
+				(u + 1) + (v + 1) * us,
+				(u + 1) + (v + 1) * us,
+				(u + 0) + (v + 1) * us,
+				(u + 0) + (v + 0) * us,
+			]);
+		}
+	}
+	idx
+}
+
+/// Tangent from [`Parametric::partials`], carried from u, v to s, t through the
+/// Jacobian of `st0`. `None` where the partials are unknown or degenerate, as
+/// at the poles of a sphere.
+pub fn parametric_tangent(shape: &dyn Parametric, u: f32, v: f32) -> Option<Vec4> {
+	let (dpdu, dpdv) = shape.partials(u, v)?;
+	// Central differences are exact for the usual affine `st0`.
+	let h = 1.0 / 1024.0;
+	let dstdu = (shape.st0(u + h, v) - shape.st0(u - h, v)) * (0.5 / h);
+	let dstdv = (shape.st0(u, v + h) - shape.st0(u, v - h)) * (0.5 / h);
+	let det = dstdu.x * dstdv.y - dstdv.x * dstdu.y;
+	if det == 0.0 {
+		return None;
+	}
+	let dpds = (dpdu * dstdv.y - dpdv * dstdu.y) * (1.0 / det);
+	let dpdt = (dpdv * dstdu.x - dpdu * dstdv.x) * (1.0 / det);
+	orthonormal_tangent(shape.nor(u, v).normalize(), dpds, dpdt)
+}
+
+/// Tessellate a parametric surface into `usteps` x `vsteps` quads, with
+/// tangents from [`parametric_tangent`] where it has one and from the
+/// triangles' texture coordinates elsewhere. The grid keeps its duplicate
+/// seam and pole vertices; [`MeshGen::weld`] merges or drops them.
+pub fn create_parametric(usteps: u32, vsteps: u32, shape: &dyn Parametric) -> MeshGen {
+	let mut mesh = MeshGen {
+		vtx: create_parametric_vec(usteps, vsteps, |u, v| shape.pos(u, v)),
+		nor: create_parametric_vec(usteps, vsteps, |u, v| shape.nor(u, v)),
+		st0: create_parametric_vec(usteps, vsteps, |u, v| shape.st0(u, v)),
+		tan: Vec::new(),
+		idx: create_parametric_indices(usteps, vsteps),
+	};
+	let analytic = create_parametric_vec(usteps, vsteps, |u, v| parametric_tangent(shape, u, v));
+	mesh.tan = if analytic.iter().all(Option::is_some) {
+		analytic.into_iter().flatten().collect()
+	} else {
+		let fallback = uv_tangents(&mesh);
+		analytic
+			.into_iter()
+			.zip(fallback)
+			.map(|(a, f)| a.unwrap_or(f))
+			.collect()
+	};
+	mesh
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn assert_close(actual: &[f32], expected: &[f32]) {
+		assert_eq!(actual.len(), expected.len());
+		for (i, (a, e)) in actual.iter().zip(expected).enumerate() {
+			assert!((a - e).abs() < 1e-5, "[{i}] {a} != {e}");
+		}
+	}
+
+	// Expected values are the output of `createParametric(3, 2, getParametricTorus(10, 1))`
+	// from python/module_parametric.py.
+	#[test]
+	fn torus_matches_python() {
+		let mesh = create_parametric(3, 2, &Torus::new(10.0, 1.0));
+		let vtx: Vec<f32> = mesh.vtx.iter().flat_map(|v| v.to_array()).collect();
+		let nor: Vec<f32> = mesh.nor.iter().flat_map(|v| v.to_array()).collect();
+		let st0: Vec<f32> = mesh.st0.iter().flat_map(|v| v.to_array()).collect();
+		#[rustfmt::skip]
+		assert_close(&vtx, &[
+			11.0, 0.0, 0.0, -5.5, 0.0, 9.526279, -5.5, 0.0, -9.526279, 11.0, 0.0, -0.0,
+			9.0, 0.0, 0.0, -4.5, 0.0, 7.794229, -4.5, 0.0, -7.794229, 9.0, 0.0, -0.0,
+			11.0, -0.0, 0.0, -5.5, -0.0, 9.526279, -5.5, -0.0, -9.526279, 11.0, -0.0, -0.0,
+		]);
+		#[rustfmt::skip]
+		assert_close(&nor, &[
+			1.0, 0.0, 0.0, -0.5, 0.0, 0.866025, -0.5, 0.0, -0.866025, 1.0, 0.0, -0.0,
+			-1.0, 0.0, -0.0, 0.5, 0.0, -0.866025, 0.5, 0.0, 0.866025, -1.0, 0.0, 0.0,
+			1.0, -0.0, 0.0, -0.5, -0.0, 0.866025, -0.5, -0.0, -0.866025, 1.0, -0.0, -0.0,
+		]);
+		#[rustfmt::skip]
+		assert_close(&st0, &[
+			0.0, 0.0, 0.333333, 0.0, 0.666667, 0.0, 1.0, 0.0,
+			0.0, 0.5, 0.333333, 0.5, 0.666667, 0.5, 1.0, 0.5,
+			0.0, 1.0, 0.333333, 1.0, 0.666667, 1.0, 1.0, 1.0,
+		]);
+		#[rustfmt::skip]
+		assert_eq!(mesh.idx, [
+			0, 1, 5, 5, 4, 0, 1, 2, 6, 6, 5, 1, 2, 3, 7, 7, 6, 2,
+			4, 5, 9, 9, 8, 4, 5, 6, 10, 10, 9, 5, 6, 7, 11, 11, 10, 6,
+		]);
+	}
+
+	#[test]
+	fn sphere_matches_python() {
+		let mesh = create_parametric(2, 2, &Sphere);
+		let vtx: Vec<f32> = mesh.vtx.iter().flat_map(|v| v.to_array()).collect();
+		#[rustfmt::skip]
+		assert_close(&vtx, &[
+			0.0, 1.0, 0.0, -0.0, 1.0, 0.0, 0.0, 1.0, -0.0,
+			1.0, 0.0, 0.0, -1.0, 0.0, 0.0, 1.0, 0.0, -0.0,
+			0.0, -1.0, 0.0, -0.0, -1.0, 0.0, 0.0, -1.0, -0.0,
+		]);
+		assert_eq!(mesh.nor, mesh.vtx);
+	}
+
+	#[test]
+	fn plane_counts() {
+		let mesh = create_parametric(100, 100, &Plane);
+		assert_eq!(mesh.vertex_count(), 101 * 101);
+		assert_eq!(mesh.triangle_count(), 2 * 100 * 100);
+		assert_eq!(mesh.vtx[0], Vec3::new(-0.5, 0.0, 0.5));
+		assert_eq!(mesh.nor[0], Vec3::new(0.0, 1.0, 0.0));
+		assert!(mesh.tan.iter().all(|&t| t == Vec4::new(1.0, 0.0, 0.0, 1.0)));
+	}
+
+	// A shape without `partials` gets the same tangents from its triangles.
+	struct NoPartials<P>(P);
+
+	impl<P: Parametric> Parametric for NoPartials<P> {
+		fn pos(&self, u: f32, v: f32) -> Vec3 {
+			self.0.pos(u, v)
+		}
+
+		fn nor(&self, u: f32, v: f32) -> Vec3 {
+			self.0.nor(u, v)
+		}
+	}
+
+	#[test]
+	fn analytic_tangents_match_uv_gradients() {
+		for (shape, fallback) in [
+			(
+				&Torus::new(10.0, 1.0) as &dyn Parametric,
+				&NoPartials(Torus::new(10.0, 1.0)) as &dyn Parametric,
+			),
+			(&Sphere, &NoPartials(Sphere)),
+		] {
+			let analytic = create_parametric(40, 40, shape);
+			let approx = create_parametric(40, 40, fallback);
+			for (i, (a, b)) in analytic.tan.iter().zip(&approx.tan).enumerate() {
+				assert!(a.xyz().dot(analytic.nor[i]).abs() < 1e-5);
+				assert!((a.xyz().length() - 1.0).abs() < 1e-5);
+				assert_eq!(a.w, b.w, "[{i}]");
+				// Seam vertices only see triangles on one side.
+				assert!(a.xyz().dot(b.xyz()) > 0.99, "[{i}] {a:?} {b:?}");
+			}
+		}
+		// Sphere tangents run along +u, east around the Y axis.
+		let sphere = create_parametric(4, 4, &Sphere);
+		let t = sphere.tan[10];
+		assert!((t.xyz() - Vec3::new(0.0, 0.0, 1.0)).length() < 1e-5 && t.w == 1.0);
+	}
+
+	// `Sphere` and `Torus` written once for `f32` and dual numbers.
+	struct SphereSurface;
+
+	impl Surface for SphereSurface {
+		fn point<S: Real>(&self, u: S, v: S) -> [S; 3] {
+			let au = u * (2.0 * PI);
+			let av = v * PI;
+			let s = av.sin();
+			[s * au.cos(), av.cos(), s * au.sin()]
+		}
+	}
+
+	struct TorusSurface(Torus);
+
+	impl Surface for TorusSurface {
+		fn point<S: Real>(&self, u: S, v: S) -> [S; 3] {
+			let au = u * (2.0 * PI);
+			let av = v * (2.0 * PI);
+			let r = av.cos() * self.0.minor + self.0.major;
+			[r * au.cos(), av.sin() * self.0.minor, r * au.sin()]
+		}
+	}
+
+	// A shape with nothing but a position.
+	struct PosOnly<'a>(&'a dyn Parametric);
+
+	impl Parametric for PosOnly<'_> {
+		fn pos(&self, u: f32, v: f32) -> Vec3 {
+			self.0.pos(u, v)
+		}
+	}
+
+	fn assert_near(a: Vec3, b: Vec3, tolerance: f32) {
+		assert!((a - b).length() <= tolerance, "{a:?} != {b:?}");
+	}
+
+	#[test]
+	fn automatic_partials_match_analytic() {
+		let torus = Torus::new(10.0, 1.0);
+		for (analytic, auto) in [
+			(
+				&Sphere as &dyn Parametric,
+				&AutoDiff::new(SphereSurface) as &dyn Parametric,
+			),
+			(&torus, &AutoDiff::new(TorusSurface(torus)).flipped()),
+		] {
+			for i in 0..=8 {
+				for j in 0..=8 {
+					let (u, v) = (i as f32 / 8.0, j as f32 / 8.0);
+					assert_near(auto.pos(u, v), analytic.pos(u, v), 1e-5);
+					let (a, b) = auto.partials(u, v).unwrap();
+					let (c, d) = analytic.partials(u, v).unwrap();
+					assert_near(a, c, 1e-4);
+					assert_near(b, d, 1e-4);
+					// Including the sphere's poles, where dpos/du vanishes.
+					assert_near(auto.nor(u, v), analytic.nor(u, v).normalize(), 1e-5);
+				}
+			}
+		}
+		assert_near(
+			AutoDiff::new(SphereSurface).nor(0.3, 1.0),
+			Vec3::new(0.0, -1.0, 0.0),
+			1e-6,
+		);
+	}
+
+	#[test]
+	fn normals_from_central_differences() {
+		// The Python torus normal faces out; dpos/du x dpos/dv faces in.
+		for (shape, sign) in [
+			(&Sphere as &dyn Parametric, 1.0),
+			(&Torus::new(2.0, 0.5), -1.0),
+		] {
+			let derived = PosOnly(shape);
+			for i in 0..=8 {
+				for j in 0..=8 {
+					let (u, v) = (i as f32 / 8.0, j as f32 / 8.0);
+					let expected = shape.nor(u, v).normalize();
+					assert_near(derived.nor(u, v) * sign, expected, 1e-3);
+				}
+			}
+		}
+	}
+
+	fn grid(n: u32) -> impl Iterator<Item = (f32, f32)> {
+		(0..=n).flat_map(move |j| (0..=n).map(move |i| (i as f32 / n as f32, j as f32 / n as f32)))
+	}
+
+	fn vase() -> Revolution {
+		Revolution::new(&[
+			Vec2::new(0.0, 1.0),
+			Vec2::new(0.4, 1.0),
+			Vec2::new(0.3, 0.5),
+			Vec2::new(0.6, -0.5),
+			Vec2::new(0.0, -1.0),
+		])
+	}
+
+	#[test]
+	fn library_normals_face_out_and_close_up() {
+		let helix = SweptTube::new(
+			|t: f32| Vec3::new((6.0 * t).cos(), t * 2.0 - 1.0, (6.0 * t).sin()),
+			0.2,
+		);
+		let shapes: [(&str, &dyn Parametric); 10] = [
+			("cylinder", &Cylinder::new(1.0, 2.0)),
+			("cone", &Cone::new(1.0, 2.0)),
+			("capsule", &Capsule::new(0.5, 1.0)),
+			("disk", &Disk::new(0.0, 1.0)),
+			("superellipsoid", &Superellipsoid::new(0.3, 0.6)),
+			("revolution", &vase()),
+			("trefoil", &SweptTube::trefoil(0.2)),
+			("helix", &helix),
+			("mobius", &Mobius::new(1.0, 0.5)),
+			("klein", &KleinBottle::new(2.0)),
+		];
+		for (name, shape) in shapes {
+			let one_sided = matches!(name, "mobius" | "klein");
+			for (u, v) in grid(16) {
+				let n = shape.nor(u, v);
+				assert!((n.length() - 1.0).abs() < 1e-4, "{name} ({u}, {v}): {n:?}");
+				// The seam at u = 1 meets u = 0, reversed on one-sided shapes.
+				if u == 1.0 && name != "helix" {
+					let (v0, sign) = if one_sided { (1.0 - v, -1.0) } else { (v, 1.0) };
+					assert_near(shape.pos(1.0, v), shape.pos(0.0, v0), 1e-4);
+					assert_near(shape.nor(1.0, v) * sign, shape.nor(0.0, v0), 1e-3);
+				}
+				// Normals agree with the partials, including the tubes' own.
+				let (a, b) = shape
+					.partials(u, v)
+					.unwrap_or_else(|| central_partials(shape, u, v));
+				// The vase's normals are smoothed over its corners.
+				let agree = if name == "revolution" { 0.5 } else { 0.99 };
+				let cross = a.cross(b);
+				if cross.length() > 1e-3 * a.length().max(b.length()).powi(2) {
+					assert!(
+						cross.normalize().dot(n) > agree,
+						"{name} ({u}, {v}) {} {a:?} {b:?}",
+						cross.normalize().dot(n)
+					);
+				}
+			}
+			let mesh = create_parametric(16, 16, shape);
+			assert!(mesh
+				.tan
+				.iter()
+				.all(|t| t.to_array().iter().all(|c| c.is_finite())));
+		}
+	}
+
+	#[test]
+	fn library_poles() {
+		let up = Vec3::new(0.0, 1.0, 0.0);
+		for u in [0.0, 0.3, 1.0] {
+			let capsule = Capsule::new(0.5, 1.0);
+			assert_near(capsule.pos(u, 0.0), Vec3::new(0.0, 1.0, 0.0), 1e-6);
+			assert_near(capsule.nor(u, 0.0), up, 1e-5);
+			assert_near(capsule.nor(u, 1.0), -up, 1e-5);
+			assert_near(Disk::new(0.0, 1.0).nor(u, 0.0), up, 1e-6);
+			assert_near(Superellipsoid::new(0.3, 0.6).nor(u, 0.0), up, 1e-6);
+			// At the apex the cone's normal is the limit along each line.
+			let au = (2.0 * PI) * u;
+			let slant = Vec3::new(2.0 * au.cos(), 1.0, 2.0 * au.sin()).normalize();
+			assert_near(Cone::new(1.0, 2.0).nor(u, 0.0), slant, 1e-4);
+		}
+		// Unit exponents are the sphere, and the disk maps like `Plane`.
+		for (u, v) in grid(8) {
+			assert_near(
+				Superellipsoid::new(1.0, 1.0).pos(u, v),
+				Sphere.pos(u, v),
+				1e-5,
+			);
+			assert_near(
+				Superellipsoid::new(1.0, 1.0).nor(u, v),
+				Sphere.nor(u, v),
+				1e-5,
+			);
+		}
+		let disk = Disk::new(0.0, 0.5);
+		assert_eq!(disk.st0(0.0, 1.0), Vec2::new(1.0, 0.5));
+		assert_eq!(disk.st0(0.25, 0.0), Vec2::new(0.5, 0.5));
+	}
+
+	#[test]
+	fn revolution_of_a_semicircle_is_a_sphere() {
+		let profile: Vec<Vec2> = (0..=256)
+			.map(|i| {
+				let a = PI * i as f32 / 256.0;
+				Vec2::new(a.sin().max(0.0), a.cos())
+			})
+			.collect();
+		let sphere = Revolution::new(&profile);
+		for (u, v) in grid(8) {
+			assert_near(sphere.pos(u, v), Sphere.pos(u, v), 1e-4);
+			assert_near(sphere.nor(u, v), Sphere.nor(u, v), 1e-4);
+		}
+		let vase = vase();
+		assert_near(vase.pos(0.0, 0.0), Vec3::new(0.0, 1.0, 0.0), 0.0);
+		assert_near(vase.pos(0.5, 1.0), Vec3::new(0.0, -1.0, 0.0), 0.0);
+	}
+
+	#[test]
+	fn swept_tube_keeps_its_radius() {
+		// Around a circle the tube is a torus, up to where v starts.
+		let ring = SweptTube::new(
+			|t: f32| Vec3::new((2.0 * PI * t).cos() * 3.0, 0.0, (2.0 * PI * t).sin() * 3.0),
+			0.5,
+		);
+		for (u, v) in grid(16) {
+			let p = ring.pos(u, v);
+			let minor = Vec3::new(p.x, 0.0, p.z).length() - 3.0;
+			assert!((minor * minor + p.y * p.y - 0.25).abs() < 1e-4, "{p:?}");
+			let trefoil = SweptTube::trefoil(0.1);
+			let centre = trefoil.pos(u, v) - trefoil.nor(u, v) * 0.1;
+			assert_near(centre, (trefoil.curve)(u), 1e-5);
+		}
+	}
+}
@@ -0,0 +1,516 @@
+////////////////////////////////////////////////////////////////////////////////
+// CPU Rasterizer
+////////////////////////////////////////////////////////////////////////////////
+//
+// Follows the GL conventions the other ports rely on: clip space is
+// `v * modelviewprojection`, triangles are clipped against the near plane,
+// pixels are sampled at their centers with a top-left fill rule, window depth
+// is `z / w * 0.5 + 0.5` and the depth test is `GL_LESS`. Face culling is off,
+// as it is in every port.
+
+use crate::math::{Vec3, Vec4};
+use crate::mesh::MeshGen;
+use crate::shader::{Fragment, FragmentShader, Varyings, VertexInput, VertexShader};
+use crate::uniform::Uniforms;
+
+/// Color and depth buffers. Rows are stored top to bottom.
+#[derive(Clone, Debug)]
+pub struct Framebuffer {
+	width: u32,
+	height: u32,
+	color: Vec<Vec4>,
+	depth: Vec<f32>,
+}
+
+impl Framebuffer {
+	/// A framebuffer cleared to transparent black and depth 1.
+	pub fn new(width: u32, height: u32) -> Self {
+		let size = (width * height) as usize;
+		Self {
+			width,
+			height,
+			color: vec![Vec4::default(); size],
+			depth: vec![1.0; size],
+		}
+	}
+
+	pub fn width(&self) -> u32 {
+		self.width
+	}
+
+	pub fn height(&self) -> u32 {
+		self.height
+	}
+
+	/// The whole framebuffer as a viewport.
+	pub fn viewport(&self) -> Viewport {
+		Viewport::new(0, 0, self.width, self.height)
+	}
+
+	/// `glClearColor(c); glClear(GL_COLOR_BUFFER_BIT)`
+	pub fn clear_color(&mut self, c: Vec4) {
+		self.color.fill(saturate(c));
+	}
+
+	/// `glClearDepth(d); glClear(GL_DEPTH_BUFFER_BIT)`
+	pub fn clear_depth(&mut self, d: f32) {
+		self.depth.fill(d.clamp(0.0, 1.0));
+	}
+
+	/// Color at `(x, y)`, with `y = 0` at the top.
+	pub fn color(&self, x: u32, y: u32) -> Vec4 {
+		self.color[self.offset(x, y)]
+	}
+
+	/// Depth at `(x, y)`, with `y = 0` at the top.
+	pub fn depth(&self, x: u32, y: u32) -> f32 {
+		self.depth[self.offset(x, y)]
+	}
+
+	pub fn color_buffer(&self) -> &[Vec4] {
+		&self.color
+	}
+
+	pub fn depth_buffer(&self) -> &[f32] {
+		&self.depth
+	}
+
+	fn offset(&self, x: u32, y: u32) -> usize {
+		assert!(
+			x < self.width && y < self.height,
+			"({x}, {y}) is outside the framebuffer"
+		);
+		(y * self.width + x) as usize
+	}
+}
+
+// Colors are stored as if in a UNORM target.
+fn saturate(c: Vec4) -> Vec4 {
+	Vec4::new(
+		c.x.clamp(0.0, 1.0),
+		c.y.clamp(0.0, 1.0),
+		c.z.clamp(0.0, 1.0),
+		c.w.clamp(0.0, 1.0),
+	)
+}
+
+/// A `glViewport` rectangle; like GL, `y` counts up from the bottom edge.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Viewport {
+	pub x: u32,
+	pub y: u32,
+	pub width: u32,
+	pub height: u32,
+}
+
+impl Viewport {
+	pub fn new(x: u32, y: u32, width: u32, height: u32) -> Self {
+		Self {
+			x,
+			y,
+			width,
+			height,
+		}
+	}
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// Triangle Setup
+////////////////////////////////////////////////////////////////////////////////
+
+// A clipped vertex, with its barycentric position in the source triangle so
+// attributes can be interpolated from the original three vertices.
+#[derive(Clone, Copy)]
+struct ClipVertex {
+	pos: Vec4,
+	bary: Vec3,
+}
+
+// Sutherland-Hodgman against the near plane, z >= -w.
+fn clip_near(input: [ClipVertex; 3]) -> Vec<ClipVertex> {
+	let distance = |v: &ClipVertex| v.pos.z + v.pos.w;
+	let mut output = Vec::with_capacity(4);
+	for i in 0..3 {
+		let a = input[i];
+		let b = input[(i + 1) % 3];
+		let (da, db) = (distance(&a), distance(&b));
+		if da >= 0.0 {
+			output.push(a);
+		}
+		if (da >= 0.0) != (db >= 0.0) {
+			let t = da / (da - db);
+			output.push(ClipVertex {
+				pos: a.pos + (b.pos - a.pos) * t,
+				bary: a.bary + (b.bary - a.bary) * t,
+			});
+		}
+	}
+	output
+}
+
+// A vertex in framebuffer space: pixels from the top-left corner, window depth
+// and 1/w for perspective-correct interpolation.
+#[derive(Clone, Copy)]
+struct ScreenVertex {
+	x: f32,
+	y: f32,
+	z: f32,
+	inv_w: f32,
+	bary: Vec3,
+}
+
+fn to_screen(fb: &Framebuffer, vp: &Viewport, v: &ClipVertex) -> ScreenVertex {
+	let inv_w = 1.0 / v.pos.w;
+	let ndc = v.pos.xyz() * inv_w;
+	let x = vp.x as f32 + (ndc.x + 1.0) * 0.5 * vp.width as f32;
+	let y = vp.y as f32 + (ndc.y + 1.0) * 0.5 * vp.height as f32;
+	ScreenVertex {
+		x,
+		y: fb.height as f32 - y,
+		z: ndc.z * 0.5 + 0.5,
+		inv_w,
+		bary: v.bary,
+	}
+}
+
+fn edge(a: &ScreenVertex, b: &ScreenVertex, px: f32, py: f32) -> f32 {
+	(b.x - a.x) * (py - a.y) - (b.y - a.y) * (px - a.x)
+}
+
+// With positive area in y-down coordinates, top edges run in +x and left edges
+// run in -y.
+fn is_top_left(a: &ScreenVertex, b: &ScreenVertex) -> bool {
+	let (dx, dy) = (b.x - a.x, b.y - a.y);
+	(dy == 0.0 && dx > 0.0) || dy < 0.0
+}
+
+/// A covered pixel that passed the depth test, with the rest of its 2x2 quad.
+pub(crate) struct Sample {
+	/// Perspective-correct weights of the three input vertices for each lane of
+	/// the quad, in row order from the top-left. Helper lanes outside the
+	/// triangle are extrapolated.
+	pub quad: [[f32; 3]; 4],
+	pub lane: usize,
+	pub frag_coord: Vec4,
+	pub front_facing: bool,
+}
+
+/// Clip and rasterize one triangle given its clip-space positions. `shade`
+/// returns the fragment color, or `None` to discard.
+pub(crate) fn draw_triangle(
+	fb: &mut Framebuffer,
+	vp: &Viewport,
+	clip: [Vec4; 3],
+	shade: &mut dyn FnMut(&Sample) -> Option<Vec4>,
+) {
+	let corners = [
+		Vec3::new(1.0, 0.0, 0.0),
+		Vec3::new(0.0, 1.0, 0.0),
+		Vec3::new(0.0, 0.0, 1.0),
+	];
+	let input = [0, 1, 2].map(|i| ClipVertex {
+		pos: clip[i],
+		bary: corners[i],
+	});
+	let polygon = clip_near(input);
+	if polygon.len() < 3 {
+		return;
+	}
+	let screen: Vec<ScreenVertex> = polygon.iter().map(|v| to_screen(fb, vp, v)).collect();
+	for i in 1..screen.len() - 1 {
+		rasterize(fb, vp, [screen[0], screen[i], screen[i + 1]], shade);
+	}
+}
+
+fn rasterize(
+	fb: &mut Framebuffer,
+	vp: &Viewport,
+	mut v: [ScreenVertex; 3],
+	shade: &mut dyn FnMut(&Sample) -> Option<Vec4>,
+) {
+	let mut area = edge(&v[0], &v[1], v[2].x, v[2].y);
+	if area == 0.0 || !area.is_finite() {
+		return;
+	}
+	// Counter-clockwise in GL's y-up window space is negative here.
+	let front_facing = area < 0.0;
+	if area < 0.0 {
+		v.swap(1, 2);
+		area = -area;
+	}
+	// Bounding box, scissored to the viewport.
+	let top = fb.height - (vp.y + vp.height).min(fb.height);
+	let bottom = fb.height - vp.y.min(fb.height);
+	let min_x = v
+		.iter()
+		.map(|p| p.x)
+		.fold(f32::INFINITY, f32::min)
+		.floor()
+		.max(vp.x as f32) as u32;
+	let max_x = v
+		.iter()
+		.map(|p| p.x)
+		.fold(f32::NEG_INFINITY, f32::max)
+		.ceil()
+		.min((vp.x + vp.width).min(fb.width) as f32) as u32;
+	let min_y = v
+		.iter()
+		.map(|p| p.y)
+		.fold(f32::INFINITY, f32::min)
+		.floor()
+		.max(top as f32) as u32;
+	let max_y = v
+		.iter()
+		.map(|p| p.y)
+		.fold(f32::NEG_INFINITY, f32::max)
+		.ceil()
+		.min(bottom as f32) as u32;
+	let top_left = [
+		is_top_left(&v[1], &v[2]),
+		is_top_left(&v[2], &v[0]),
+		is_top_left(&v[0], &v[1]),
+	];
+	// Shade in 2x2 quads aligned to even pixels so derivatives are available.
+	for qy in (min_y & !1..max_y).step_by(2) {
+		for qx in (min_x & !1..max_x).step_by(2) {
+			let mut quad = [[0.0; 3]; 4];
+			let mut depth = [0.0; 4];
+			let mut inv_w = [0.0; 4];
+			let mut covered = [false; 4];
+			for lane in 0..4 {
+				let (px, py) = (qx + (lane & 1) as u32, qy + (lane >> 1) as u32);
+				let (sx, sy) = (px as f32 + 0.5, py as f32 + 0.5);
+				let e = [
+					edge(&v[1], &v[2], sx, sy),
+					edge(&v[2], &v[0], sx, sy),
+					edge(&v[0], &v[1], sx, sy),
+				];
+				covered[lane] = (min_x..max_x).contains(&px)
+					&& (min_y..max_y).contains(&py)
+					&& (0..3).all(|k| e[k] > 0.0 || (e[k] == 0.0 && top_left[k]));
+				let l = e.map(|e| e / area);
+				depth[lane] = l[0] * v[0].z + l[1] * v[1].z + l[2] * v[2].z;
+				// Perspective-correct weights of the clipped vertices, mapped
+				// back onto the original triangle.
+				let p = [0, 1, 2].map(|k| l[k] * v[k].inv_w);
+				inv_w[lane] = p[0] + p[1] + p[2];
+				let bary = (v[0].bary * p[0] + v[1].bary * p[1] + v[2].bary * p[2]) / inv_w[lane];
+				quad[lane] = [bary.x, bary.y, bary.z];
+			}
+			for lane in (0..4).filter(|&lane| covered[lane]) {
+				let (px, py) = (qx + (lane & 1) as u32, qy + (lane >> 1) as u32);
+				let z = depth[lane];
+				let offset = (py * fb.width + px) as usize;
+				// GL_LESS, with NaN failing as a negated comparison would.
+				if z >= fb.depth[offset] || z.is_nan() {
+					continue;
+				}
+				let sample = Sample {
+					quad,
+					lane,
+					frag_coord: Vec4::new(
+						px as f32 + 0.5,
+						(fb.height - py) as f32 - 0.5,
+						z,
+						inv_w[lane],
+					),
+					front_facing,
+				};
+				if let Some(c) = shade(&sample) {
+					fb.depth[offset] = z;
+					fb.color[offset] = saturate(c);
+				}
+			}
+		}
+	}
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// Mesh Rendering
+////////////////////////////////////////////////////////////////////////////////
+
+/// Draw a mesh with a program: the vertex shader runs once per vertex, the
+/// fragment shader once per covered pixel that passes the depth test. Normals,
+/// texture coordinates and tangents the mesh leaves empty read as zero.
+pub fn draw<VS, FS>(
+	fb: &mut Framebuffer,
+	vp: &Viewport,
+	mesh: &MeshGen,
+	uniforms: &Uniforms,
+	vs: &VS,
+	fs: &FS,
+) where
+	VS: VertexShader,
+	FS: FragmentShader<VS::Varyings>,
+{
+	let vertices: Vec<(Vec4, VS::Varyings)> = (0..mesh.vtx.len())
+		.map(|i| {
+			let input = VertexInput {
+				pos: mesh.vtx[i],
+				nor: mesh.nor.get(i).copied().unwrap_or_default(),
+				st0: mesh.st0.get(i).copied().unwrap_or_default(),
+				tan: mesh.tan.get(i).copied().unwrap_or_default(),
+			};
+			vs.vertex(uniforms, &input)
+		})
+		.collect();
+	for [a, b, c] in mesh.triangles() {
+		let tri = [a, b, c].map(|i| vertices[i as usize]);
+		let varyings = tri.map(|(_, v)| v);
+		draw_triangle(fb, vp, tri.map(|(p, _)| p), &mut |sample| {
+			let quad = sample.quad.map(|w| VS::Varyings::combine(&varyings, w));
+			let fragment = Fragment {
+				varyings: quad[sample.lane],
+				quad,
+				lane: sample.lane,
+				frag_coord: sample.frag_coord,
+				front_facing: sample.front_facing,
+			};
+			fs.fragment(uniforms, &fragment)
+		});
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::cell::Cell;
+
+	use super::*;
+	use crate::math::{mat_translate, Mat4, Vec2};
+	use crate::shader::{ShaderFragmentGl41, ShaderVertex};
+
+	fn draw_normals(fb: &mut Framebuffer, vp: &Viewport, mesh: &MeshGen, mvp: Mat4) {
+		let uniforms = Uniforms {
+			modelviewprojection: mvp,
+			..Uniforms::default()
+		};
+		draw(fb, vp, mesh, &uniforms, &ShaderVertex, &ShaderFragmentGl41);
+	}
+
+	fn quad(z: f32, nor: Vec3) -> MeshGen {
+		MeshGen {
+			vtx: vec![
+				Vec3::new(-1.0, -1.0, z),
+				Vec3::new(1.0, -1.0, z),
+				Vec3::new(1.0, 1.0, z),
+				Vec3::new(-1.0, 1.0, z),
+			],
+			nor: vec![nor; 4],
+			st0: vec![Vec2::default(); 4],
+			tan: Vec::new(),
+			idx: vec![0, 1, 2, 2, 3, 0],
+		}
+	}
+
+	#[test]
+	fn full_screen_quad_covers_every_pixel_once() {
+		let mut fb = Framebuffer::new(16, 16);
+		let vp = fb.viewport();
+		let mut count = vec![0; 256];
+		let mesh = quad(0.0, Vec3::new(1.0, 0.0, 0.0));
+		for [a, b, c] in mesh.triangles() {
+			let clip = [a, b, c].map(|i| mesh.vtx[i as usize].extend(1.0));
+			let counter = &mut count;
+			let mut hit = |s: &Sample| {
+				let x = s.frag_coord.x as usize;
+				let y = 15 - s.frag_coord.y as usize;
+				counter[y * 16 + x] += 1;
+				None
+			};
+			draw_triangle(&mut fb, &vp, clip, &mut hit);
+		}
+		assert!(count.iter().all(|&c| c == 1), "{count:?}");
+	}
+
+	#[test]
+	fn depth_test_is_less() {
+		let mut fb = Framebuffer::new(4, 4);
+		fb.clear_color(Vec4::new(0.0, 0.0, 1.0, 1.0));
+		let vp = fb.viewport();
+		let near = quad(-0.5, Vec3::new(1.0, 0.0, 0.0));
+		let far = quad(0.5, Vec3::new(0.0, 1.0, 0.0));
+		draw_normals(&mut fb, &vp, &near, Mat4::IDENTITY);
+		draw_normals(&mut fb, &vp, &far, Mat4::IDENTITY);
+		assert_eq!(fb.color(1, 1), Vec4::new(1.0, 0.0, 0.0, 1.0));
+		assert_eq!(fb.depth(1, 1), 0.25);
+		// Equal depth fails GL_LESS.
+		let same = quad(-0.5, Vec3::new(0.0, 0.0, 1.0));
+		draw_normals(&mut fb, &vp, &same, Mat4::IDENTITY);
+		assert_eq!(fb.color(1, 1), Vec4::new(1.0, 0.0, 0.0, 1.0));
+	}
+
+	#[test]
+	fn near_plane_clipping() {
+		let mut fb = Framebuffer::new(8, 8);
+		let vp = fb.viewport();
+		// Entirely in front of the near plane: nothing drawn.
+		let mesh = quad(0.0, Vec3::new(0.0, 0.0, 1.0));
+		draw_normals(&mut fb, &vp, &mesh, mat_translate(0.0, 0.0, -2.0));
+		assert!(fb.depth_buffer().iter().all(|&d| d == 1.0));
+	}
+
+	#[test]
+	fn viewport_is_bottom_up() {
+		let mut fb = Framebuffer::new(8, 8);
+		let vp = Viewport::new(4, 0, 4, 4);
+		let mesh = quad(0.0, Vec3::new(0.0, 0.0, 1.0));
+		draw_normals(&mut fb, &vp, &mesh, Mat4::IDENTITY);
+		assert_eq!(fb.color(7, 7), Vec4::new(0.0, 0.0, 1.0, 1.0));
+		assert_eq!(fb.color(0, 0), Vec4::default());
+		assert_eq!(fb.color(7, 3), Vec4::default());
+	}
+
+	// Outputs clip-space w as a varying, with w growing from left to right.
+	struct ClipW;
+
+	impl VertexShader for ClipW {
+		type Varyings = f32;
+		fn vertex(&self, _: &Uniforms, input: &VertexInput) -> (Vec4, f32) {
+			let w = 2.0 + input.pos.x;
+			((input.pos * w).extend(w), w)
+		}
+	}
+
+	// Records the worst error of the interpolated w against gl_FragCoord.w, and
+	// whether any fragment was back-facing.
+	#[derive(Default)]
+	struct CheckW {
+		error: Cell<f32>,
+		back: Cell<bool>,
+	}
+
+	impl FragmentShader<f32> for CheckW {
+		fn fragment(&self, _: &Uniforms, f: &Fragment<f32>) -> Option<Vec4> {
+			let error = (f.varyings * f.frag_coord.w - 1.0).abs();
+			self.error.set(self.error.get().max(error));
+			self.back.set(self.back.get() || !f.front_facing);
+			Some(Vec4::new(1.0, 1.0, 1.0, 1.0))
+		}
+	}
+
+	#[test]
+	fn perspective_correct_varyings() {
+		let mut fb = Framebuffer::new(16, 16);
+		let vp = fb.viewport();
+		let fs = CheckW::default();
+		let mesh = quad(0.0, Vec3::new(0.0, 0.0, 1.0));
+		draw(&mut fb, &vp, &mesh, &Uniforms::default(), &ClipW, &fs);
+		assert!(fs.error.get() < 1e-5, "{}", fs.error.get());
+		assert!(fb.depth_buffer().iter().all(|&d| d < 1.0));
+		// The quad is counter-clockwise.
+		assert!(!fs.back.get());
+		// Positions alone are enough to draw.
+		let bare = MeshGen {
+			vtx: mesh.vtx.clone(),
+			idx: mesh.idx.clone(),
+			..MeshGen::default()
+		};
+		draw(&mut fb, &vp, &bare, &Uniforms::default(), &ClipW, &fs);
+		let mut flipped = mesh.clone();
+		flipped.idx.reverse();
+		let mut fb = Framebuffer::new(4, 4);
+		let vp = fb.viewport();
+		draw(&mut fb, &vp, &flipped, &Uniforms::default(), &ClipW, &fs);
+		assert!(fs.back.get());
+	}
+}
@@ -0,0 +1,494 @@
+////////////////////////////////////////////////////////////////////////////////
+// Relief Mapping
+////////////////////////////////////////////////////////////////////////////////
+//
+// Ports of `glShaderFragment` and `glShaderFragmentRelaxedCone`. Both march the
+// view ray through the `Height` texture in tangent space and shade `Albedo` at
+// the texture coordinate where it first goes below the surface. The GLSL
+// samples inside a data-dependent loop, where implicit derivatives are
+// undefined; here every lookup uses the derivatives of `outST0` instead.
+
+use std::thread;
+
+use crate::math::{Vec2, Vec3, Vec4};
+use crate::shader::{derivative_tangent_frame, Fragment, FragmentShader, StandardVaryings};
+use crate::texture::{Sampler2D, Texture2D};
+use crate::uniform::Uniforms;
+
+/// `glShaderFragment`: linear search through `steps` equal layers.
+#[derive(Clone, Debug)]
+pub struct ShaderFragmentRelief {
+	pub albedo: Sampler2D,
+	pub height: Sampler2D,
+	pub height_scale: f32,
+	pub steps: u32,
+	/// Binary search steps between the last two layers once the linear search
+	/// has hit; 0 matches the GLSL.
+	pub refine_steps: u32,
+}
+
+impl ShaderFragmentRelief {
+	/// The GLSL parameters: `heightScale` 0.1 and 32 steps, no refinement.
+	pub fn new(albedo: Sampler2D, height: Sampler2D) -> Self {
+		Self {
+			albedo,
+			height,
+			height_scale: 0.1,
+			steps: 32,
+			refine_steps: 0,
+		}
+	}
+}
+
+impl FragmentShader<StandardVaryings> for ShaderFragmentRelief {
+	fn fragment(&self, u: &Uniforms, f: &Fragment<StandardVaryings>) -> Option<Vec4> {
+		let ray = RayMarch::new(u, f, &self.height, self.height_scale);
+		let layer_depth = 1.0 / self.steps as f32;
+		let delta = -ray.p / self.steps as f32;
+		let mut current = (f.varyings.st0, 0.0);
+		let mut previous = current;
+		let mut steps = self.steps;
+		while current.1 < ray.depth(current.0) && steps > 0 {
+			previous = current;
+			current = (current.0 + delta, current.1 + layer_depth);
+			steps -= 1;
+		}
+		let st = ray.refine(previous, current, self.refine_steps);
+		Some(ray.shade(&self.albedo, st))
+	}
+}
+
+/// `glShaderFragmentRelaxedCone`: steps shrink from `1 / steps` towards
+/// `min_step` as the ray gets deeper, and move twice as far across the
+/// texture as down into it.
+///
+/// With a `cone_map` from [`relaxed_cone_map`] the steps follow its cone
+/// ratios instead, its red channel replaces `height` and `min_step` is unused.
+#[derive(Clone, Debug)]
+pub struct ShaderFragmentRelaxedCone {
+	pub albedo: Sampler2D,
+	pub height: Sampler2D,
+	pub cone_map: Option<Sampler2D>,
+	pub height_scale: f32,
+	pub steps: u32,
+	pub min_step: f32,
+	/// Binary search steps over the last cone step once the search has hit;
+	/// 0 matches the GLSL.
+	pub refine_steps: u32,
+}
+
+impl ShaderFragmentRelaxedCone {
+	/// The GLSL parameters: `heightScale` 0.1, 64 steps, `minStep` 0.01, no
+	/// refinement.
+	pub fn new(albedo: Sampler2D, height: Sampler2D) -> Self {
+		Self {
+			albedo,
+			height,
+			height_scale: 0.1,
+			steps: 64,
+			min_step: 0.01,
+			refine_steps: 0,
+			cone_map: None,
+		}
+	}
+
+	/// Relaxed cone stepping through `cone_map`. Relaxed cones let the last
+	/// step end below the surface, so this refines with 6 binary search steps.
+	pub fn with_cone_map(albedo: Sampler2D, cone_map: Sampler2D) -> Self {
+		Self {
+			cone_map: Some(cone_map.clone()),
+			refine_steps: 6,
+			..Self::new(albedo, cone_map)
+		}
+	}
+}
+
+impl FragmentShader<StandardVaryings> for ShaderFragmentRelaxedCone {
+	fn fragment(&self, u: &Uniforms, f: &Fragment<StandardVaryings>) -> Option<Vec4> {
+		if let Some(cone_map) = &self.cone_map {
+			return Some(self.cone_step(u, f, cone_map));
+		}
+		let ray = RayMarch::new(u, f, &self.height, self.height_scale);
+		let max_step = 1.0 / self.steps as f32;
+		let delta = -ray.p;
+		let mut current = (f.varyings.st0, 0.0);
+		let mut previous = current;
+		for _ in 0..self.steps {
+			if current.1 >= ray.depth(current.0) {
+				break;
+			}
+			let cone_step = max_step + (self.min_step - max_step) * current.1;
+			previous = current;
+			current = (current.0 + delta * cone_step * 2.0, current.1 + cone_step);
+		}
+		let st = ray.refine(previous, current, self.refine_steps);
+		Some(ray.shade(&self.albedo, st))
+	}
+}
+
+impl ShaderFragmentRelaxedCone {
+	fn cone_step(
+		&self,
+		u: &Uniforms,
+		f: &Fragment<StandardVaryings>,
+		cone_map: &Sampler2D,
+	) -> Vec4 {
+		let ray = RayMarch::new(u, f, cone_map, self.height_scale);
+		let ray_ratio = ray.p.length();
+		let mut current = (f.varyings.st0, 0.0);
+		let mut previous = current;
+		for _ in 0..self.steps {
+			let texel = cone_map.sample_grad(current.0, ray.dstdx, ray.dstdy);
+			let height = texel.x - current.1;
+			if height <= 0.0 {
+				break;
+			}
+			// Move to where the ray leaves this texel's cone.
+			let cone_ratio = texel.y * texel.y;
+			let step = cone_ratio * height / (ray_ratio + cone_ratio);
+			previous = current;
+			current = (current.0 - ray.p * step, current.1 + step);
+		}
+		let st = ray.refine(previous, current, self.refine_steps);
+		ray.shade(&self.albedo, st)
+	}
+}
+
+// The state both searches share: the tangent-space parallax offset and the
+// derivatives every lookup uses.
+struct RayMarch<'a> {
+	height: &'a Sampler2D,
+	p: Vec2,
+	nor: Vec3,
+	dstdx: Vec2,
+	dstdy: Vec2,
+}
+
+impl<'a> RayMarch<'a> {
+	fn new(
+		u: &Uniforms,
+		f: &Fragment<StandardVaryings>,
+		height: &'a Sampler2D,
+		scale: f32,
+	) -> Self {
+		let (tangent, bitangent) = derivative_tangent_frame(f);
+		let normal = f.varyings.nor.normalize();
+		let view = (u.eye - f.varyings.pos).normalize();
+		let view_ts =
+			Vec3::new(tangent.dot(view), bitangent.dot(view), normal.dot(view)).normalize();
+		Self {
+			height,
+			p: Vec2::new(view_ts.x, view_ts.y) * scale,
+			nor: f.varyings.nor,
+			dstdx: f.dfdx(|v| v.st0),
+			dstdy: f.dfdy(|v| v.st0),
+		}
+	}
+
+	fn depth(&self, st: Vec2) -> f32 {
+		self.height.sample_grad(st, self.dstdx, self.dstdy).x
+	}
+
+	// Bisect between a point above the surface and one at or below it. Does
+	// nothing unless the search actually stepped onto the surface.
+	fn refine(&self, mut above: (Vec2, f32), mut below: (Vec2, f32), steps: u32) -> Vec2 {
+		if steps == 0 || above == below || below.1 < self.depth(below.0) {
+			return below.0;
+		}
+		for _ in 0..steps {
+			let mid = ((above.0 + below.0) * 0.5, (above.1 + below.1) * 0.5);
+			if mid.1 < self.depth(mid.0) {
+				above = mid;
+			} else {
+				below = mid;
+			}
+		}
+		below.0
+	}
+
+	// Clamp to the texture and light with the fixed overhead light.
+	fn shade(&self, albedo: &Sampler2D, st: Vec2) -> Vec4 {
+		let st = Vec2::new(st.x.clamp(0.0, 1.0), st.y.clamp(0.0, 1.0));
+		let dot_l = self.nor.dot(Vec3::new(0.0, 1.0, 0.0)).clamp(0.0, 1.0);
+		let ambient = 0.3;
+		let illumination = dot_l.clamp(0.25, 1.0).max(ambient);
+		(albedo.sample_grad(st, self.dstdx, self.dstdy).xyz() * illumination).extend(1.0)
+	}
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// Relaxed Cone Maps
+////////////////////////////////////////////////////////////////////////////////
+//
+// Policarpo and Oliveira, "Relaxed Cone Stepping for Relief Mapping", GPU Gems
+// 3, chapter 18. A texel's relaxed cone is the widest one, with its apex on the
+// surface, that no ray from the top plane can enter, pass below the surface
+// and come back out of. For every other texel, the ray from this texel's top
+// through that texel's surface point is followed until it leaves the surface,
+// and the cone is narrowed to exclude where it comes out.
+
+/// Relaxed cone map of the red channel of `height`, read as depth below the
+/// top plane like the programs above. Red holds the depth and green the square
+/// root of the cone ratio (texture units per unit depth, at most 1) for 8-bit
+/// precision; blue is 0 and alpha 1. The height map is taken to repeat.
+pub fn relaxed_cone_map(height: &Texture2D) -> Texture2D {
+	let threads = thread::available_parallelism().map_or(1, |n| n.get());
+	relaxed_cone_map_threads(height, threads)
+}
+
+/// [`relaxed_cone_map`] split by rows over `threads` threads. The result does
+/// not depend on the thread count.
+pub fn relaxed_cone_map_threads(height: &Texture2D, threads: usize) -> Texture2D {
+	let (width, rows) = (height.width() as usize, height.height() as usize);
+	let depth: Vec<f32> = height
+		.level(0)
+		.texels
+		.iter()
+		.map(|t| t.x.clamp(0.0, 1.0))
+		.collect();
+	// Each offset once within one repeat of the texture, nearest first.
+	let span = |n: usize| -((n as i32 - 1) / 2)..=(n as i32 / 2);
+	let mut offsets: Vec<(i32, i32)> = span(rows)
+		.flat_map(|dy| span(width).map(move |dx| (dx, dy)))
+		.filter(|&o| o != (0, 0))
+		.collect();
+	offsets.sort_by_key(|&(dx, dy)| dx * dx + dy * dy);
+	let map = ConeSearch {
+		depth: &depth,
+		width,
+		rows,
+		offsets: &offsets,
+	};
+	let mut ratios = vec![0.0; width * rows];
+	let chunk = rows.div_ceil(threads.max(1)) * width;
+	thread::scope(|s| {
+		for (i, part) in ratios.chunks_mut(chunk).enumerate() {
+			let map = &map;
+			s.spawn(move || {
+				for (j, ratio) in part.iter_mut().enumerate() {
+					let texel = i * chunk + j;
+					*ratio = map.cone_ratio(texel % width, texel / width);
+				}
+			});
+		}
+	});
+	let texels = depth
+		.iter()
+		.zip(&ratios)
+		.map(|(&d, &r)| Vec4::new(d, r.sqrt(), 0.0, 1.0))
+		.collect();
+	Texture2D::new(width as u32, rows as u32, texels)
+}
+
+struct ConeSearch<'a> {
+	depth: &'a [f32],
+	width: usize,
+	rows: usize,
+	offsets: &'a [(i32, i32)],
+}
+
+impl ConeSearch<'_> {
+	fn at(&self, x: f32, y: f32) -> f32 {
+		let x = (x.floor() as i64).rem_euclid(self.width as i64) as usize;
+		let y = (y.floor() as i64).rem_euclid(self.rows as i64) as usize;
+		self.depth[y * self.width + x]
+	}
+
+	fn cone_ratio(&self, x: usize, y: usize) -> f32 {
+		let apex = self.depth[y * self.width + x];
+		let (x, y) = (x as f32 + 0.5, y as f32 + 0.5);
+		let mut best = 1.0f32;
+		for &(dx, dy) in self.offsets {
+			let (dx, dy) = (dx as f32, dy as f32);
+			let distance = (dx / self.width as f32).hypot(dy / self.rows as f32);
+			// Any exit is at least this far out and at most `apex` higher, and
+			// the offsets only get farther.
+			if distance >= best * apex {
+				break;
+			}
+			// The ray only comes out above the apex if it went in above it; a
+			// surface point on the top plane gives a ray along it.
+			let through = self.at(x + dx, y + dy);
+			if through >= apex || through <= 0.0 {
+				continue;
+			}
+			// The ray is at (x + dx * t, y + dy * t) and depth `through * t`;
+			// step one texel at a time beyond the surface point at t = 1.
+			let dt = 1.0 / dx.abs().max(dy.abs());
+			let mut t = 1.0 + dt;
+			loop {
+				let z = through * t;
+				let ratio = distance * t / (apex - z);
+				if z >= apex || ratio >= best {
+					break;
+				}
+				if self.at(x + dx * t, y + dy * t) > z {
+					best = ratio;
+					break;
+				}
+				t += dt;
+			}
+		}
+		best
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::sync::Arc;
+
+	use crate::mesh::MeshGen;
+	use crate::raster::{draw, Framebuffer};
+	use crate::shader::ShaderVertex;
+	use crate::texture::{Filter, Texture2D};
+
+	// 16x1 albedo whose red channel is the texel column, so the shaded color
+	// shows where the search stopped.
+	fn albedo() -> Sampler2D {
+		let texels = (0..16)
+			.map(|x| Vec4::new(x as f32 / 16.0, 0.0, 0.0, 1.0))
+			.collect();
+		let mut sampler = Sampler2D::new(Arc::new(Texture2D::new(16, 1, texels)));
+		sampler.mag_filter = Filter::Nearest;
+		sampler.min_filter = Filter::Nearest;
+		sampler.mipmap_filter = None;
+		sampler
+	}
+
+	fn constant(depth: f32) -> Sampler2D {
+		Sampler2D::new(Arc::new(Texture2D::new(
+			1,
+			1,
+			vec![Vec4::new(depth, depth, depth, 1.0)],
+		)))
+	}
+
+	// A z = 0 quad over the viewport seen from `eye`, lit from straight above
+	// so the output is the albedo.
+	fn render(fs: &impl FragmentShader<StandardVaryings>, eye: Vec3) -> Framebuffer {
+		let mesh = MeshGen {
+			vtx: vec![
+				Vec3::new(-1.0, -1.0, 0.0),
+				Vec3::new(1.0, -1.0, 0.0),
+				Vec3::new(1.0, 1.0, 0.0),
+				Vec3::new(-1.0, 1.0, 0.0),
+			],
+			nor: vec![Vec3::new(0.0, 1.0, 0.0); 4],
+			st0: vec![
+				Vec2::new(0.0, 0.0),
+				Vec2::new(1.0, 0.0),
+				Vec2::new(1.0, 1.0),
+				Vec2::new(0.0, 1.0),
+			],
+			tan: Vec::new(),
+			idx: vec![0, 1, 2, 2, 3, 0],
+		};
+		let u = Uniforms {
+			eye,
+			..Uniforms::default()
+		};
+		let mut fb = Framebuffer::new(16, 16);
+		let vp = fb.viewport();
+		draw(&mut fb, &vp, &mesh, &u, &ShaderVertex, fs);
+		fb
+	}
+
+	#[test]
+	fn flat_height_needs_no_search() {
+		// Height 0 stops immediately, so the albedo is not displaced even for
+		// a grazing view.
+		let fs = ShaderFragmentRelief::new(albedo(), constant(0.0));
+		let fb = render(&fs, Vec3::new(100.0, 0.0, 1.0));
+		assert_eq!(fb.color(8, 8), Vec4::new(8.0 / 16.0, 0.0, 0.0, 1.0));
+	}
+
+	#[test]
+	fn relief_offsets_along_the_view() {
+		// Depth 0.5 is reached after 16 of 32 layers, half of P = 0.1 away
+		// from the eye along the tangent.
+		let fs = ShaderFragmentRelief::new(albedo(), constant(0.5));
+		let fb = render(&fs, Vec3::new(-1000.0, 0.0, 0.0));
+		let st: f32 = (8.0 + 0.5) / 16.0 + 0.05;
+		assert_eq!(fb.color(8, 8).x, (st * 16.0).floor() / 16.0);
+	}
+
+	#[test]
+	fn refinement_stays_on_the_segment() {
+		let mut fs = ShaderFragmentRelief::new(albedo(), constant(0.3));
+		fs.steps = 2;
+		let coarse = render(&fs, Vec3::new(-1000.0, 0.0, 0.0));
+		fs.refine_steps = 8;
+		let refined = render(&fs, Vec3::new(-1000.0, 0.0, 0.0));
+		// Two layers overshoot to depth 0.5 (offset 0.05 = 0.8 texels);
+		// bisection finds depth 0.3 (0.48 texels), one texel closer.
+		assert_eq!(coarse.color(12, 8).x, 13.0 / 16.0);
+		assert_eq!(refined.color(12, 8).x, 12.0 / 16.0);
+	}
+
+	#[test]
+	fn relaxed_cone_stops_below_the_surface() {
+		let fs = ShaderFragmentRelaxedCone::new(albedo(), constant(0.25));
+		let fb = render(&fs, Vec3::new(-1000.0, 0.0, 0.0));
+		// Each step moves 2 * step * 0.1 in s for `step` in depth, so the
+		// stopping depth just past 0.25 is at s offset 0.2 * depth.
+		let (mut depth, mut offset) = (0.0f32, 0.0f32);
+		while depth < 0.25 {
+			let step = 1.0 / 64.0 + (0.01 - 1.0 / 64.0) * depth;
+			offset += 0.1 * step * 2.0;
+			depth += step;
+		}
+		let st: f32 = (8.0 + 0.5) / 16.0 + offset;
+		assert_eq!(fb.color(8, 8).x, (st * 16.0).floor() / 16.0);
+	}
+
+	#[test]
+	fn cone_ratios() {
+		let texture = |width, depths: &[f32]| {
+			let texels = depths.iter().map(|&d| Vec4::new(d, d, d, 1.0)).collect();
+			Texture2D::new(width, (depths.len() as u32) / width, texels)
+		};
+		// Flat and top texels have the widest cone.
+		let flat = relaxed_cone_map(&texture(2, &[0.5; 4]));
+		assert!(flat
+			.level(0)
+			.texels
+			.iter()
+			.all(|&t| t == Vec4::new(0.5, 1.0, 0.0, 1.0)));
+		// The ray from the top of texel 0 through texel 1 (depth 0.2) reaches
+		// depth 0.4 at texel 2, which is deeper, 2 texels (0.25) out and 0.6
+		// above the apex.
+		let map = relaxed_cone_map(&texture(8, &[1.0, 0.2, 0.5, 0.0, 0.0, 0.0, 0.0, 0.0]));
+		let ratio = map.texel(0, 0, 0).y.powi(2);
+		assert!((ratio - 0.25 / 0.6).abs() < 1e-6, "{ratio}");
+		assert_eq!(map.texel(0, 0, 0).x, 1.0);
+	}
+
+	#[test]
+	fn cone_map_is_independent_of_threads() {
+		let depths = (0..24 * 20)
+			.map(|i| {
+				let (x, y) = ((i % 24) as f32, (i / 24) as f32);
+				(0.5 + 0.5 * (x * 0.7).sin() * (y * 0.4).cos()).clamp(0.0, 1.0)
+			})
+			.map(|d| Vec4::new(d, d, d, 1.0))
+			.collect();
+		let height = Texture2D::new(24, 20, depths);
+		let single = relaxed_cone_map_threads(&height, 1);
+		assert_eq!(single, relaxed_cone_map_threads(&height, 7));
+		assert!(single.level(0).texels.iter().any(|t| t.y < 1.0));
+	}
+
+	#[test]
+	fn cone_stepping_reaches_the_surface() {
+		// Constant depth 0.5 has ratio 1 everywhere, and the steps converge on
+		// the same point the linear search of `relief_offsets_along_the_view`
+		// stops at.
+		let height = Texture2D::new(1, 1, vec![Vec4::new(0.5, 0.5, 0.5, 1.0)]);
+		let cone_map = Sampler2D::new(Arc::new(relaxed_cone_map(&height)));
+		let fs = ShaderFragmentRelaxedCone::with_cone_map(albedo(), cone_map);
+		let fb = render(&fs, Vec3::new(-1000.0, 0.0, 0.0));
+		let st: f32 = (8.0 + 0.5) / 16.0 + 0.05;
+		assert_eq!(fb.color(8, 8).x, (st * 16.0).floor() / 16.0);
+	}
+}
@@ -0,0 +1,420 @@
+////////////////////////////////////////////////////////////////////////////////
+// Scene Description
+////////////////////////////////////////////////////////////////////////////////
+
+use std::f32::consts::PI;
+use std::sync::Arc;
+
+use crate::canvas::{css_color, Canvas, Font, RadialGradient, TextAlign, TextBaseline};
+use crate::math::{mat_look_at, mat_projection, mat_scale, mat_translate, Mat4, Vec2, Vec3, Vec4};
+use crate::mesh::MeshGen;
+use crate::parametric::{
+	create_parametric, Capsule, Cone, Cylinder, Disk, KleinBottle, Mobius, Parametric, Plane,
+	Revolution, Sphere, Superellipsoid, SweptTube, Torus,
+};
+use crate::raster::{draw, Framebuffer, Viewport};
+use crate::shader::{FragmentShader, ShaderFragmentGl41, ShaderVertex, VertexShader};
+use crate::texture::{Sampler2D, Texture2D};
+use crate::uniform::{TransformUniforms, UniformError, Uniforms};
+
+/// PBR metallic-roughness parameters, the material model of glTF. The Metal
+/// port's per-instance `diffuse` color is `base_color`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Material {
+	pub name: String,
+	pub base_color: Vec4,
+	pub base_color_texture: Option<Arc<Texture2D>>,
+	pub metallic: f32,
+	pub roughness: f32,
+	/// Roughness in green and metalness in blue, scaled by the factors.
+	pub metallic_roughness_texture: Option<Arc<Texture2D>>,
+	pub normal_texture: Option<Arc<Texture2D>>,
+	pub emissive: Vec3,
+	pub double_sided: bool,
+}
+
+impl Default for Material {
+	/// The glTF defaults: white, fully metallic and fully rough.
+	fn default() -> Self {
+		Self {
+			name: String::new(),
+			base_color: Vec4::new(1.0, 1.0, 1.0, 1.0),
+			base_color_texture: None,
+			metallic: 1.0,
+			roughness: 1.0,
+			metallic_roughness_texture: None,
+			normal_texture: None,
+			emissive: Vec3::default(),
+			double_sided: false,
+		}
+	}
+}
+
+impl Material {
+	/// A rough dielectric of one color, like `UniformsInstance.diffuse`.
+	pub fn diffuse(color: Vec3) -> Self {
+		Self {
+			base_color: color.extend(1.0),
+			metallic: 0.0,
+			..Self::default()
+		}
+	}
+}
+
+/// A mesh placed in the world, as in `Instance.swift`. Without a material the
+/// program decides the color, as the default programs do.
+#[derive(Clone, Debug)]
+pub struct Instance {
+	pub mesh: Arc<MeshGen>,
+	pub model: Mat4,
+	pub material: Option<Arc<Material>>,
+}
+
+/// `UniformsFrame.light` of the Metal port; `computeAttenuation` fades it out
+/// linearly by `range`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PointLight {
+	pub position: Vec3,
+	pub color: Vec3,
+	pub intensity: f32,
+	pub range: Option<f32>,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Scene {
+	pub instances: Vec<Instance>,
+	pub lights: Vec<PointLight>,
+}
+
+impl Scene {
+	/// The scene every RenderWindow target draws, tessellated as in
+	/// `module_main.js`.
+	pub fn demo() -> Scene {
+		let plane = Arc::new(create_parametric(20, 20, &Plane));
+		let sphere = Arc::new(create_parametric(20, 20, &Sphere));
+		let torus = Arc::new(create_parametric(50, 50, &Torus::new(10.0, 1.0)));
+		let mut scene = Scene::default();
+		// Draw a plane, green as in MTKViewScene.swift
+		scene.add_with_material(
+			&plane,
+			mat_scale(50.0, 1.0, 50.0) * mat_translate(0.0, -6.0, 0.0),
+			&Arc::new(Material::diffuse(Vec3::new(0.0, 1.0, 0.0))),
+		);
+		// Draw some spheres, colored by position
+		for z in (-5..=5).step_by(2) {
+			for y in (-5..=5).step_by(2) {
+				for x in (-5..=5).step_by(2) {
+					let [r, g, b] = [x, y, z].map(|c| (c as f32 + 5.0) / 10.0);
+					scene.add_with_material(
+						&sphere,
+						mat_translate(x as f32, y as f32, z as f32),
+						&Arc::new(Material::diffuse(Vec3::new(r, g, b))),
+					);
+				}
+			}
+		}
+		// Draw a big sphere on top
+		scene.add(
+			&sphere,
+			mat_scale(5.0, 5.0, 5.0) * mat_translate(0.0, 10.0, 0.0),
+		);
+		// Draw a torus
+		scene.add(&torus, mat_translate(0.0, 1.0, 0.0));
+		scene.lights.push(PointLight {
+			position: demo_light(0.0),
+			color: Vec3::new(1.0, 1.0, 1.0),
+			intensity: 1.0,
+			range: Some(50.0),
+		});
+		scene
+	}
+
+	/// Every shape of the parametric library in a ring around the demo's
+	/// camera target, above the demo's floor, with poles, seams and one-sided
+	/// surfaces in view.
+	pub fn primitives() -> Scene {
+		let vase = Revolution::new(&[
+			Vec2::new(0.0, 1.0),
+			Vec2::new(0.4, 1.0),
+			Vec2::new(0.3, 0.5),
+			Vec2::new(0.6, -0.5),
+			Vec2::new(0.0, -1.0),
+		]);
+		let helix = SweptTube::new(
+			|t: f32| Vec3::new((4.0 * PI * t).cos(), t * 2.0 - 1.0, (4.0 * PI * t).sin()) * 0.8,
+			0.15,
+		);
+		let shapes: [&dyn Parametric; 10] = [
+			&Cylinder::new(0.8, 2.0),
+			&Cone::new(0.8, 2.0),
+			&Capsule::new(0.6, 0.8),
+			&Disk::new(0.3, 1.0),
+			&Mobius::new(0.8, 0.6),
+			&KleinBottle::new(2.0),
+			&Superellipsoid::new(0.3, 0.6),
+			&SweptTube::trefoil(0.15),
+			&vase,
+			&helix,
+		];
+		let plane = Arc::new(create_parametric(20, 20, &Plane));
+		let mut scene = Scene::default();
+		scene.add(
+			&plane,
+			mat_scale(50.0, 1.0, 50.0) * mat_translate(0.0, -6.0, 0.0),
+		);
+		for (i, shape) in shapes.into_iter().enumerate() {
+			let mesh = Arc::new(create_parametric(40, 40, shape));
+			let a = (2.0 * PI) * i as f32 / shapes.len() as f32;
+			// The Klein bottle is about three times the size of the rest.
+			let scale = if i == 5 { 0.7 } else { 2.0 };
+			scene.add(
+				&mesh,
+				mat_scale(scale, scale, scale) * mat_translate(7.0 * a.cos(), 0.0, 7.0 * a.sin()),
+			);
+		}
+		scene
+	}
+
+	pub fn add(&mut self, mesh: &Arc<MeshGen>, model: Mat4) {
+		self.instances.push(Instance {
+			mesh: Arc::clone(mesh),
+			model,
+			material: None,
+		});
+	}
+
+	pub fn add_with_material(
+		&mut self,
+		mesh: &Arc<MeshGen>,
+		model: Mat4,
+		material: &Arc<Material>,
+	) {
+		self.instances.push(Instance {
+			mesh: Arc::clone(mesh),
+			model,
+			material: Some(Arc::clone(material)),
+		});
+	}
+
+	/// Draw every instance with one program, like `glRenderScene`. The view
+	/// and projection must be set; the model is set per instance.
+	pub fn render<VS, FS>(
+		&self,
+		fb: &mut Framebuffer,
+		vp: &Viewport,
+		uniforms: &mut TransformUniforms,
+		vs: &VS,
+		fs: &FS,
+	) -> Result<(), UniformError>
+	where
+		VS: VertexShader,
+		FS: FragmentShader<VS::Varyings>,
+	{
+		for instance in &self.instances {
+			uniforms.set_model(instance.model);
+			let block = Uniforms::from_transforms(uniforms)?;
+			draw(fb, vp, &instance.mesh, &block, vs, fs);
+		}
+		Ok(())
+	}
+}
+
+/// Every port advances time by 0.01 per frame.
+pub fn demo_time(frame: u32) -> f32 {
+	frame as f32 * 0.01
+}
+
+/// The orbiting camera.
+pub fn demo_view(time: f32) -> Mat4 {
+	let eye = Vec3::new(
+		25.0 * time.cos(),
+		10.0 * (1.0 - (time * 0.2).cos()),
+		10.0 * time.sin(),
+	);
+	mat_look_at(eye, Vec3::default(), Vec3::new(0.0, 1.0, 0.0))
+}
+
+/// The point light of MTKViewScene.swift, orbiting out of step with the
+/// camera.
+pub fn demo_light(time: f32) -> Vec3 {
+	Vec3::new(
+		25.0 * (time * 0.73).cos(),
+		10.0 * (1.0 - (time * 0.16).cos()),
+		10.0 * (time * 0.73).sin(),
+	)
+}
+
+pub fn demo_projection() -> Mat4 {
+	mat_projection(90.0, 0.001, 100.0)
+}
+
+/// Render frame `frame` of the demo, see [`render_demo`].
+pub fn render_demo_frame(scene: &Scene, width: u32, height: u32, frame: u32) -> Framebuffer {
+	render_demo(scene, width, height, demo_time(frame))
+}
+
+/// Render the demo at `time` the way `renderwindow_gl41.py` does: clear to
+/// blue and depth 1, then draw with the normal-colored default program.
+pub fn render_demo(scene: &Scene, width: u32, height: u32, time: f32) -> Framebuffer {
+	render_demo_with(
+		scene,
+		width,
+		height,
+		time,
+		&ShaderVertex,
+		&ShaderFragmentGl41,
+	)
+}
+
+/// Render the demo at `time` with any program, after the same clears as
+/// [`render_demo`].
+pub fn render_demo_with<VS, FS>(
+	scene: &Scene,
+	width: u32,
+	height: u32,
+	time: f32,
+	vs: &VS,
+	fs: &FS,
+) -> Framebuffer
+where
+	VS: VertexShader,
+	FS: FragmentShader<VS::Varyings>,
+{
+	let mut fb = Framebuffer::new(width, height);
+	fb.clear_color(Vec4::new(0.0, 0.0, 1.0, 1.0));
+	fb.clear_depth(1.0);
+	let vp = fb.viewport();
+	let mut uniforms = TransformUniforms::new();
+	uniforms.set_projection(demo_projection());
+	uniforms.set_view(demo_view(time));
+	uniforms.time = time;
+	scene
+		.render(&mut fb, &vp, &mut uniforms, vs, fs)
+		.expect("view and projection are set");
+	fb
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// Demo Textures
+////////////////////////////////////////////////////////////////////////////////
+
+/// The albedo canvas of `module_main.js`: a green square in a red border,
+/// under a 16 pixel black grid with both diagonals and the word "Texture".
+pub fn demo_albedo() -> Canvas {
+	let mut ctx = Canvas::new(256, 256);
+	ctx.set_fill_color("red");
+	ctx.fill_rect(0.0, 0.0, 256.0, 256.0);
+	ctx.set_fill_color("green");
+	ctx.fill_rect(16.0, 16.0, 256.0 - 32.0, 256.0 - 32.0);
+	ctx.set_fill_color("black");
+	for x in (0..256).step_by(16).map(|x| x as f32) {
+		ctx.move_to(0.0, x);
+		ctx.line_to(256.0, x);
+		ctx.move_to(x, 0.0);
+		ctx.line_to(x, 256.0);
+	}
+	ctx.move_to(0.0, 0.0);
+	ctx.line_to(256.0, 256.0);
+	ctx.move_to(256.0, 0.0);
+	ctx.line_to(0.0, 256.0);
+	ctx.stroke();
+	ctx.font = Font::parse("bold 48px serif").expect("valid font");
+	ctx.text_align = TextAlign::Center;
+	ctx.text_baseline = TextBaseline::Middle;
+	ctx.fill_text("Texture", 128.0, 128.0);
+	ctx
+}
+
+/// The height canvas of `module_main.js`: white-to-black radial blobs on
+/// black, in three rings of corners and four larger ones around the center.
+pub fn demo_height() -> Canvas {
+	let mut ctx = Canvas::new(256, 256);
+	ctx.set_fill_color("black");
+	ctx.fill_rect(0.0, 0.0, 256.0, 256.0);
+	draw_box_blob(&mut ctx, 256.0, 32.0, 16.0);
+	draw_box_blob(&mut ctx, 256.0, 64.0, 16.0);
+	draw_box_blob(&mut ctx, 256.0, 96.0, 16.0);
+	draw_blob(&mut ctx, 128.0, 48.0, 32.0);
+	draw_blob(&mut ctx, 128.0, 256.0 - 48.0, 32.0);
+	draw_blob(&mut ctx, 48.0, 128.0, 32.0);
+	draw_blob(&mut ctx, 256.0 - 48.0, 128.0, 32.0);
+	ctx
+}
+
+/// Upload a canvas the way `module_main.js` does: mipmapped, REPEAT and
+/// LINEAR_MIPMAP_LINEAR.
+pub fn demo_sampler(canvas: &Canvas) -> Sampler2D {
+	let mut texture = canvas.to_texture();
+	texture.generate_mipmaps();
+	Sampler2D::new(Arc::new(texture))
+}
+
+fn draw_blob(ctx: &mut Canvas, x: f32, y: f32, radius: f32) {
+	let mut gr = RadialGradient::new(x, y, 0.0, x, y, radius);
+	gr.add_color_stop(0.0, css_color("#FFFFFF").expect("valid color"));
+	gr.add_color_stop(1.0, css_color("#000000").expect("valid color"));
+	// The JS never restores the old style (`ctx,fillStyle = oldStyle`).
+	ctx.fill_style = gr.into();
+	ctx.begin_path();
+	ctx.arc(x, y, radius, 0.0, 2.0 * PI);
+	ctx.fill();
+}
+
+fn draw_box_blob(ctx: &mut Canvas, size: f32, inset: f32, radius: f32) {
+	draw_blob(ctx, inset, inset, radius);
+	draw_blob(ctx, size - inset, inset, radius);
+	draw_blob(ctx, inset, size - inset, radius);
+	draw_blob(ctx, size - inset, size - inset, radius);
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn demo_instances() {
+		let scene = Scene::demo();
+		// Plane, 6x6x6 spheres, big sphere, torus.
+		assert_eq!(scene.instances.len(), 1 + 216 + 1 + 1);
+		let big = &scene.instances[217];
+		assert_eq!(
+			big.model.transform_point(Vec3::new(0.0, 1.0, 0.0)),
+			Vec3::new(0.0, 15.0, 0.0)
+		);
+		let corner = scene.instances[1].material.as_ref().unwrap();
+		assert_eq!(corner.base_color, Vec4::new(0.0, 0.0, 0.0, 1.0));
+		assert!(big.material.is_none());
+		assert_eq!(scene.lights[0].position, Vec3::new(25.0, 0.0, 0.0));
+	}
+
+	#[test]
+	fn render_frame() {
+		let scene = Scene::demo();
+		let fb = render_demo_frame(&scene, 64, 64, 0);
+		let drawn = fb.depth_buffer().iter().filter(|&&d| d < 1.0).count();
+		assert!(drawn > 64 * 64 / 2, "only {drawn} pixels drawn");
+		assert!(fb.depth_buffer().iter().all(|&d| (0.0..=1.0).contains(&d)));
+		// Deterministic.
+		let again = render_demo_frame(&scene, 64, 64, 0);
+		assert_eq!(fb.color_buffer(), again.color_buffer());
+		assert_eq!(fb.depth_buffer(), again.depth_buffer());
+	}
+
+	#[test]
+	fn demo_textures() {
+		let albedo = demo_albedo();
+		let pixel = |c: &Canvas, x, y| c.pixel(x, y).to_array().map(|v| (v * 255.0).round() as u8);
+		assert_eq!(pixel(&albedo, 24, 20), [0, 128, 0, 255]);
+		assert_eq!(pixel(&albedo, 8, 4), [255, 0, 0, 255]);
+		// The first grid line is centered on the canvas edge.
+		assert_eq!(pixel(&albedo, 8, 0), [128, 0, 0, 255]);
+		assert_eq!(pixel(&albedo, 128, 128), [0, 0, 0, 255]);
+		let height = demo_height();
+		assert_eq!(pixel(&height, 0, 0), [0, 0, 0, 255]);
+		assert!(pixel(&height, 32, 32)[0] > 240);
+		assert!(pixel(&height, 128, 128)[0] == 0);
+		// Byte-for-byte reproducible.
+		assert_eq!(albedo.to_rgba8(), demo_albedo().to_rgba8());
+		assert_eq!(height.to_rgba8(), demo_height().to_rgba8());
+	}
+}
@@ -0,0 +1,454 @@
+////////////////////////////////////////////////////////////////////////////////
+// Shader Handling
+////////////////////////////////////////////////////////////////////////////////
+
+use std::ops::{Add, Mul, Sub};
+
+use crate::math::{Vec2, Vec3, Vec4};
+use crate::uniform::Uniforms;
+
+/// Default vertex shader, shared with `python/module_shader_gl41.py`.
+pub const GL_SHADER_VERTEX: &str = "#version 410 core
+precision highp float;
+
+layout(location = 0) in vec3 inPos;
+layout(location = 1) in vec3 inNor;
+layout(location = 2) in vec2 inST0;
+out vec3 outPos;
+out vec3 outNor;
+out vec2 outST0;
+
+uniform mat4 model;
+uniform mat4 view;
+uniform mat4 projection;
+uniform mat4 modelview;
+uniform mat4 viewprojection;
+uniform mat4 modelviewprojection;
+
+void main(void) {
+    gl_Position = modelviewprojection * vec4(inPos, 1.0);
+    outNor = inNor; //normalize(mat3(model) * inNor);
+    outST0 = inST0;
+}
+";
+
+/// Default fragment shader, shared with `python/module_shader_gl41.py`.
+pub const GL_SHADER_FRAGMENT: &str = "#version 410 core
+precision highp float;
+
+in vec3 outPos;
+in vec3 outNor;
+in vec2 outST0;
+out vec4 outCol;
+
+void main(void) {
+    outCol = vec4(outNor, 1.0);
+}
+";
+
+////////////////////////////////////////////////////////////////////////////////
+// CPU Shaders
+////////////////////////////////////////////////////////////////////////////////
+//
+// A program is a `VertexShader` whose `Varyings` feed a `FragmentShader`, both
+// reading the same `Uniforms` block. Anything else a GLSL program would bind
+// (samplers, constants) lives in the shader structs themselves.
+
+/// The vertex attributes `inPos`, `inNor` and `inST0`, plus the tangent of
+/// [`MeshGen::tan`](crate::mesh::MeshGen::tan) (zero for meshes without one).
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct VertexInput {
+	pub pos: Vec3,
+	pub nor: Vec3,
+	pub st0: Vec2,
+	pub tan: Vec4,
+}
+
+/// Values written by the vertex stage and interpolated for the fragment stage.
+pub trait Varyings: Copy {
+	/// Combine three vertices' values with weights that sum to one.
+	fn combine(v: &[Self; 3], w: [f32; 3]) -> Self;
+}
+
+/// Weighted sum of three values, for implementing [`Varyings::combine`].
+pub fn combine3<T: Copy + Add<Output = T> + Mul<f32, Output = T>>(v: &[T; 3], w: [f32; 3]) -> T {
+	v[0] * w[0] + v[1] * w[1] + v[2] * w[2]
+}
+
+macro_rules! impl_varyings {
+	($($t:ty),+) => {
+		$(impl Varyings for $t {
+			fn combine(v: &[Self; 3], w: [f32; 3]) -> Self {
+				combine3(v, w)
+			}
+		})+
+	};
+}
+
+impl_varyings!(f32, Vec2, Vec3, Vec4);
+
+impl Varyings for () {
+	fn combine(_: &[Self; 3], _: [f32; 3]) -> Self {}
+}
+
+/// One fragment: the interpolated varyings plus `gl_FragCoord` (window x, y
+/// from the bottom-left, depth and 1/w) and `gl_FrontFacing`.
+#[derive(Clone, Copy, Debug)]
+pub struct Fragment<V> {
+	pub varyings: V,
+	/// Varyings of the 2x2 quad in row order from the top-left; `quad[lane]`
+	/// is this fragment. Helper lanes outside the triangle are extrapolated.
+	pub quad: [V; 4],
+	pub lane: usize,
+	pub frag_coord: Vec4,
+	pub front_facing: bool,
+}
+
+impl<V> Fragment<V> {
+	/// `dFdx` of any function of the varyings, across this fragment's row.
+	pub fn dfdx<T: Sub<Output = T>>(&self, f: impl Fn(&V) -> T) -> T {
+		let row = self.lane & 2;
+		f(&self.quad[row + 1]) - f(&self.quad[row])
+	}
+
+	/// `dFdy` of any function of the varyings, up this fragment's column as
+	/// window y counts up in GL.
+	pub fn dfdy<T: Sub<Output = T>>(&self, f: impl Fn(&V) -> T) -> T {
+		let column = self.lane & 1;
+		f(&self.quad[column]) - f(&self.quad[column + 2])
+	}
+}
+
+pub trait VertexShader {
+	type Varyings: Varyings;
+	/// Return `gl_Position` and the varyings for one vertex.
+	fn vertex(&self, uniforms: &Uniforms, input: &VertexInput) -> (Vec4, Self::Varyings);
+}
+
+pub trait FragmentShader<V> {
+	/// Return the color, or `None` to `discard`.
+	fn fragment(&self, uniforms: &Uniforms, fragment: &Fragment<V>) -> Option<Vec4>;
+}
+
+/// `outPos`, `outNor` and `outST0`.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct StandardVaryings {
+	pub pos: Vec3,
+	pub nor: Vec3,
+	pub st0: Vec2,
+}
+
+impl Varyings for StandardVaryings {
+	fn combine(v: &[Self; 3], w: [f32; 3]) -> Self {
+		Self {
+			pos: combine3(&v.map(|v| v.pos), w),
+			nor: combine3(&v.map(|v| v.nor), w),
+			st0: combine3(&v.map(|v| v.st0), w),
+		}
+	}
+}
+
+/// `glShaderVertex`: world-space position and normal, texture coordinates.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ShaderVertex;
+
+impl VertexShader for ShaderVertex {
+	type Varyings = StandardVaryings;
+	fn vertex(&self, u: &Uniforms, input: &VertexInput) -> (Vec4, StandardVaryings) {
+		let position = input.pos.extend(1.0) * u.modelviewprojection;
+		let varyings = StandardVaryings {
+			pos: u.model.transform_point(input.pos),
+			nor: u.model.transform_vector(input.nor).normalize(),
+			st0: input.st0,
+		};
+		(position, varyings)
+	}
+}
+
+/// [`StandardVaryings`] plus the world-space tangent, handedness in w.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct TangentVaryings {
+	pub pos: Vec3,
+	pub nor: Vec3,
+	pub tan: Vec4,
+	pub st0: Vec2,
+}
+
+impl Varyings for TangentVaryings {
+	fn combine(v: &[Self; 3], w: [f32; 3]) -> Self {
+		Self {
+			pos: combine3(&v.map(|v| v.pos), w),
+			nor: combine3(&v.map(|v| v.nor), w),
+			tan: combine3(&v.map(|v| v.tan), w),
+			st0: combine3(&v.map(|v| v.st0), w),
+		}
+	}
+}
+
+/// [`ShaderVertex`] passing the vertex tangent through, so fragment programs
+/// need no derivatives for their tangent frame.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ShaderVertexTangent;
+
+impl VertexShader for ShaderVertexTangent {
+	type Varyings = TangentVaryings;
+	fn vertex(&self, u: &Uniforms, input: &VertexInput) -> (Vec4, TangentVaryings) {
+		let (position, v) = ShaderVertex.vertex(u, input);
+		let varyings = TangentVaryings {
+			pos: v.pos,
+			nor: v.nor,
+			tan: u
+				.model
+				.transform_vector(input.tan.xyz())
+				.normalize()
+				.extend(input.tan.w),
+			st0: v.st0,
+		};
+		(position, varyings)
+	}
+}
+
+/// `GL_SHADER_FRAGMENT`: the interpolated normal as the color.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ShaderFragmentGl41;
+
+impl FragmentShader<StandardVaryings> for ShaderFragmentGl41 {
+	fn fragment(&self, _: &Uniforms, f: &Fragment<StandardVaryings>) -> Option<Vec4> {
+		Some(f.varyings.nor.extend(1.0))
+	}
+}
+
+/// Tangent and bitangent rebuilt from screen-space derivatives of `outPos` and
+/// `outST0`, as the WebGL fragment shaders do.
+pub fn derivative_tangent_frame(f: &Fragment<StandardVaryings>) -> (Vec3, Vec3) {
+	let dpdx = f.dfdx(|v| v.pos);
+	let dpdy = f.dfdy(|v| v.pos);
+	let dstdx = f.dfdx(|v| v.st0);
+	let dstdy = f.dfdy(|v| v.st0);
+	let tangent = (dpdx * dstdy.y - dpdy * dstdx.y).normalize();
+	let bitangent = (-dpdx * dstdy.x + dpdy * dstdx.x).normalize();
+	(tangent, bitangent)
+}
+
+/// Tangent and bitangent from the interpolated vertex tangent, re-orthogonalized
+/// against the interpolated normal.
+pub fn vertex_tangent_frame(f: &Fragment<TangentVaryings>) -> (Vec3, Vec3) {
+	let normal = f.varyings.nor.normalize();
+	let t = f.varyings.tan.xyz();
+	let tangent = (t - normal * normal.dot(t)).normalize();
+	let handedness = if f.varyings.tan.w < 0.0 { -1.0 } else { 1.0 };
+	(tangent, normal.cross(tangent) * handedness)
+}
+
+// Map a unit vector from [-1, 1] to a displayable color.
+fn encode_unit(v: Vec3) -> Vec4 {
+	((v + Vec3::new(1.0, 1.0, 1.0)) * 0.5).extend(1.0)
+}
+
+/// `glShaderFragmentTangent`
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ShaderFragmentTangent;
+
+impl FragmentShader<StandardVaryings> for ShaderFragmentTangent {
+	fn fragment(&self, _: &Uniforms, f: &Fragment<StandardVaryings>) -> Option<Vec4> {
+		Some(encode_unit(derivative_tangent_frame(f).0))
+	}
+}
+
+impl FragmentShader<TangentVaryings> for ShaderFragmentTangent {
+	fn fragment(&self, _: &Uniforms, f: &Fragment<TangentVaryings>) -> Option<Vec4> {
+		Some(encode_unit(vertex_tangent_frame(f).0))
+	}
+}
+
+/// `glShaderFragmentBitangent`
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ShaderFragmentBitangent;
+
+impl FragmentShader<StandardVaryings> for ShaderFragmentBitangent {
+	fn fragment(&self, _: &Uniforms, f: &Fragment<StandardVaryings>) -> Option<Vec4> {
+		Some(encode_unit(derivative_tangent_frame(f).1))
+	}
+}
+
+impl FragmentShader<TangentVaryings> for ShaderFragmentBitangent {
+	fn fragment(&self, _: &Uniforms, f: &Fragment<TangentVaryings>) -> Option<Vec4> {
+		Some(encode_unit(vertex_tangent_frame(f).1))
+	}
+}
+
+/// `glShaderFragmentNormal`
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ShaderFragmentNormal;
+
+impl FragmentShader<StandardVaryings> for ShaderFragmentNormal {
+	fn fragment(&self, _: &Uniforms, f: &Fragment<StandardVaryings>) -> Option<Vec4> {
+		Some(encode_unit(f.varyings.nor))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::math::{mat_translate, Mat4};
+	use crate::mesh::MeshGen;
+	use crate::parametric::{create_parametric, Plane};
+	use crate::raster::{draw, Framebuffer};
+
+	#[test]
+	fn combine_weights() {
+		let v = [
+			Vec2::new(1.0, 0.0),
+			Vec2::new(0.0, 1.0),
+			Vec2::new(0.0, 0.0),
+		];
+		assert_eq!(Vec2::combine(&v, [0.25, 0.5, 0.25]), Vec2::new(0.25, 0.5));
+	}
+
+	#[test]
+	fn shader_vertex() {
+		let model = mat_translate(1.0, 2.0, 3.0);
+		let u = Uniforms {
+			model,
+			modelviewprojection: model,
+			..Uniforms::default()
+		};
+		let input = VertexInput {
+			pos: Vec3::new(1.0, 1.0, 1.0),
+			nor: Vec3::new(0.0, 2.0, 0.0),
+			st0: Vec2::new(0.5, 0.25),
+			tan: Vec4::default(),
+		};
+		let (position, v) = ShaderVertex.vertex(&u, &input);
+		assert_eq!(position, Vec4::new(2.0, 3.0, 4.0, 1.0));
+		assert_eq!(v.pos, Vec3::new(2.0, 3.0, 4.0));
+		assert_eq!(v.nor, Vec3::new(0.0, 1.0, 0.0));
+		assert_eq!(v.st0, input.st0);
+	}
+
+	#[test]
+	fn derivatives() {
+		// A full-screen quad in the z = 0 plane with st0 spanning [0, 1].
+		let mesh = MeshGen {
+			vtx: vec![
+				Vec3::new(-1.0, -1.0, 0.0),
+				Vec3::new(1.0, -1.0, 0.0),
+				Vec3::new(1.0, 1.0, 0.0),
+				Vec3::new(-1.0, 1.0, 0.0),
+			],
+			nor: vec![Vec3::new(0.0, 0.0, 1.0); 4],
+			st0: vec![
+				Vec2::new(0.0, 0.0),
+				Vec2::new(1.0, 0.0),
+				Vec2::new(1.0, 1.0),
+				Vec2::new(0.0, 1.0),
+			],
+			tan: Vec::new(),
+			idx: vec![0, 1, 2, 2, 3, 0],
+		};
+		struct Check;
+		impl FragmentShader<StandardVaryings> for Check {
+			fn fragment(&self, _: &Uniforms, f: &Fragment<StandardVaryings>) -> Option<Vec4> {
+				assert_eq!(f.dfdx(|v| v.st0), Vec2::new(1.0 / 16.0, 0.0));
+				assert_eq!(f.dfdy(|v| v.st0), Vec2::new(0.0, 1.0 / 16.0));
+				assert_eq!(f.dfdx(|v| v.pos), Vec3::new(2.0 / 16.0, 0.0, 0.0));
+				None
+			}
+		}
+		let u = Uniforms::default();
+		let mut fb = Framebuffer::new(16, 16);
+		let vp = fb.viewport();
+		draw(&mut fb, &vp, &mesh, &u, &ShaderVertex, &Check);
+		draw(
+			&mut fb,
+			&vp,
+			&mesh,
+			&u,
+			&ShaderVertex,
+			&ShaderFragmentTangent,
+		);
+		draw(
+			&mut fb,
+			&vp,
+			&mesh,
+			&u,
+			&ShaderVertex,
+			&ShaderFragmentBitangent,
+		);
+		assert!(fb
+			.color_buffer()
+			.iter()
+			.all(|&c| c == Vec4::new(1.0, 0.5, 0.5, 1.0)));
+		fb.clear_depth(1.0);
+		draw(
+			&mut fb,
+			&vp,
+			&mesh,
+			&u,
+			&ShaderVertex,
+			&ShaderFragmentBitangent,
+		);
+		assert!(fb
+			.color_buffer()
+			.iter()
+			.all(|&c| c == Vec4::new(0.5, 1.0, 0.5, 1.0)));
+	}
+
+	#[test]
+	fn vertex_tangents_match_derivatives() {
+		// The XZ plane filling the viewport, +z down the screen.
+		let mesh = create_parametric(1, 1, &Plane);
+		let u = Uniforms {
+			modelviewprojection: Mat4::from_rows([
+				[2.0, 0.0, 0.0, 0.0],
+				[0.0, 0.0, 0.0, 0.0],
+				[0.0, -2.0, 0.0, 0.0],
+				[0.0, 0.0, 0.0, 1.0],
+			]),
+			..Uniforms::default()
+		};
+		for program in [0, 1] {
+			let mut derived = Framebuffer::new(8, 8);
+			let mut vertex = Framebuffer::new(8, 8);
+			let vp = derived.viewport();
+			if program == 0 {
+				draw(
+					&mut derived,
+					&vp,
+					&mesh,
+					&u,
+					&ShaderVertex,
+					&ShaderFragmentTangent,
+				);
+				draw(
+					&mut vertex,
+					&vp,
+					&mesh,
+					&u,
+					&ShaderVertexTangent,
+					&ShaderFragmentTangent,
+				);
+			} else {
+				draw(
+					&mut derived,
+					&vp,
+					&mesh,
+					&u,
+					&ShaderVertex,
+					&ShaderFragmentBitangent,
+				);
+				draw(
+					&mut vertex,
+					&vp,
+					&mesh,
+					&u,
+					&ShaderVertexTangent,
+					&ShaderFragmentBitangent,
+				);
+			}
+			for (a, b) in derived.color_buffer().iter().zip(vertex.color_buffer()) {
+				assert!((a.xyz() - b.xyz()).length() < 1e-5, "{a:?} != {b:?}");
+			}
+		}
+	}
+}
@@ -0,0 +1,348 @@
+////////////////////////////////////////////////////////////////////////////////
+// Texture Handling
+////////////////////////////////////////////////////////////////////////////////
+//
+// Sampling follows the GL ES 3.0 rules: LOD is log2 of the larger screen-space
+// footprint in texels, `mag_filter` applies at LOD <= 0, and the mipmap filter
+// picks or blends the levels either side of the LOD.
+
+use std::sync::Arc;
+
+use crate::math::{Vec2, Vec4};
+use crate::shader::Fragment;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct MipLevel {
+	pub width: u32,
+	pub height: u32,
+	/// Texels in row order, from `t = 0`.
+	pub texels: Vec<Vec4>,
+}
+
+/// An RGBA float texture with an optional mip chain.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Texture2D {
+	levels: Vec<MipLevel>,
+}
+
+impl Texture2D {
+	pub fn new(width: u32, height: u32, texels: Vec<Vec4>) -> Self {
+		assert!(width > 0 && height > 0, "texture must not be empty");
+		assert_eq!(
+			texels.len(),
+			(width * height) as usize,
+			"texel count mismatch"
+		);
+		Self {
+			levels: vec![MipLevel {
+				width,
+				height,
+				texels,
+			}],
+		}
+	}
+
+	/// From RGBA8 bytes, as `texImage2D(..., RGBA, UNSIGNED_BYTE, ...)`.
+	pub fn from_rgba8(width: u32, height: u32, rgba: &[u8]) -> Self {
+		let texels = rgba
+			.chunks(4)
+			.map(|c| Vec4::new(c[0] as f32, c[1] as f32, c[2] as f32, c[3] as f32) / 255.0)
+			.collect();
+		Self::new(width, height, texels)
+	}
+
+	/// `glGenerateMipmap`: replace any existing chain with 2x2 box-filtered
+	/// levels down to 1x1. Odd sizes round down and reuse the edge texel.
+	pub fn generate_mipmaps(&mut self) {
+		self.levels.truncate(1);
+		loop {
+			let src = self.levels.last().unwrap();
+			if src.width == 1 && src.height == 1 {
+				break;
+			}
+			let (width, height) = ((src.width / 2).max(1), (src.height / 2).max(1));
+			let mut texels = Vec::with_capacity((width * height) as usize);
+			for y in 0..height {
+				for x in 0..width {
+					let x0 = (2 * x).min(src.width - 1);
+					let x1 = (2 * x + 1).min(src.width - 1);
+					let y0 = (2 * y).min(src.height - 1);
+					let y1 = (2 * y + 1).min(src.height - 1);
+					let texel = |x: u32, y: u32| src.texels[(y * src.width + x) as usize];
+					texels.push(
+						(texel(x0, y0) + texel(x1, y0) + texel(x0, y1) + texel(x1, y1)) * 0.25,
+					);
+				}
+			}
+			self.levels.push(MipLevel {
+				width,
+				height,
+				texels,
+			});
+		}
+	}
+
+	pub fn width(&self) -> u32 {
+		self.levels[0].width
+	}
+
+	pub fn height(&self) -> u32 {
+		self.levels[0].height
+	}
+
+	pub fn level_count(&self) -> usize {
+		self.levels.len()
+	}
+
+	pub fn level(&self, level: usize) -> &MipLevel {
+		&self.levels[level]
+	}
+
+	pub fn texel(&self, level: usize, x: u32, y: u32) -> Vec4 {
+		let l = &self.levels[level];
+		l.texels[(y * l.width + x) as usize]
+	}
+
+	/// Level 0 as RGBA8, the inverse of [`Texture2D::from_rgba8`].
+	pub fn to_rgba8(&self) -> Vec<u8> {
+		self.levels[0]
+			.texels
+			.iter()
+			.flat_map(|t| {
+				t.to_array()
+					.map(|c| (c.clamp(0.0, 1.0) * 255.0).round() as u8)
+			})
+			.collect()
+	}
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Wrap {
+	#[default]
+	Repeat,
+	ClampToEdge,
+	MirroredRepeat,
+}
+
+impl Wrap {
+	/// Map an integer texel coordinate into `0..size`.
+	pub fn apply(self, i: i64, size: u32) -> u32 {
+		let n = size as i64;
+		let i = match self {
+			Wrap::Repeat => i.rem_euclid(n),
+			Wrap::ClampToEdge => i.clamp(0, n - 1),
+			Wrap::MirroredRepeat => {
+				let m = i.rem_euclid(2 * n);
+				if m >= n {
+					2 * n - 1 - m
+				} else {
+					m
+				}
+			}
+		};
+		i as u32
+	}
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Filter {
+	Nearest,
+	#[default]
+	Linear,
+}
+
+/// A texture bound with its sampling state, like a GLSL `sampler2D`.
+/// `mipmap_filter: None` samples level 0 only, so `min_filter: Linear` with
+/// `mipmap_filter: Some(Linear)` is `LINEAR_MIPMAP_LINEAR`.
+#[derive(Clone, Debug)]
+pub struct Sampler2D {
+	pub texture: Arc<Texture2D>,
+	pub wrap_s: Wrap,
+	pub wrap_t: Wrap,
+	pub min_filter: Filter,
+	pub mag_filter: Filter,
+	pub mipmap_filter: Option<Filter>,
+}
+
+impl Sampler2D {
+	/// The state `module_main.js` uses: REPEAT, LINEAR_MIPMAP_LINEAR, LINEAR.
+	pub fn new(texture: Arc<Texture2D>) -> Self {
+		Self {
+			texture,
+			wrap_s: Wrap::Repeat,
+			wrap_t: Wrap::Repeat,
+			min_filter: Filter::Linear,
+			mag_filter: Filter::Linear,
+			mipmap_filter: Some(Filter::Linear),
+		}
+	}
+
+	/// `textureLod`
+	pub fn sample_lod(&self, uv: Vec2, lod: f32) -> Vec4 {
+		if lod <= 0.0 || lod.is_nan() {
+			return self.sample_level(0, self.mag_filter, uv);
+		}
+		let max_level = (self.texture.level_count() - 1) as f32;
+		match self.mipmap_filter {
+			None => self.sample_level(0, self.min_filter, uv),
+			Some(Filter::Nearest) => {
+				// GL rounds half down: ceil(lod + 0.5) - 1.
+				let level = ((lod + 0.5).ceil() - 1.0).clamp(0.0, max_level);
+				self.sample_level(level as usize, self.min_filter, uv)
+			}
+			Some(Filter::Linear) => {
+				let lod = lod.min(max_level);
+				let base = lod.floor();
+				let a = self.sample_level(base as usize, self.min_filter, uv);
+				if base == lod {
+					return a;
+				}
+				let b = self.sample_level(base as usize + 1, self.min_filter, uv);
+				a + (b - a) * (lod - base)
+			}
+		}
+	}
+
+	/// `textureGrad`
+	pub fn sample_grad(&self, uv: Vec2, dx: Vec2, dy: Vec2) -> Vec4 {
+		self.sample_lod(uv, self.lod(dx, dy))
+	}
+
+	/// `texture`: `uv` is evaluated on every lane of the fragment's quad so
+	/// that its derivatives choose the LOD.
+	pub fn sample<V>(&self, fragment: &Fragment<V>, uv: impl Fn(&V) -> Vec2) -> Vec4 {
+		let dx = fragment.dfdx(&uv);
+		let dy = fragment.dfdy(&uv);
+		self.sample_grad(uv(&fragment.varyings), dx, dy)
+	}
+
+	/// Level of detail for texture coordinate derivatives.
+	pub fn lod(&self, dx: Vec2, dy: Vec2) -> f32 {
+		let size = Vec2::new(self.texture.width() as f32, self.texture.height() as f32);
+		let scale = |d: Vec2| Vec2::new(d.x * size.x, d.y * size.y).length();
+		scale(dx).max(scale(dy)).log2()
+	}
+
+	fn sample_level(&self, level: usize, filter: Filter, uv: Vec2) -> Vec4 {
+		let l = self.texture.level(level);
+		let (u, v) = (uv.x * l.width as f32, uv.y * l.height as f32);
+		let texel = |x: i64, y: i64| {
+			let x = self.wrap_s.apply(x, l.width);
+			let y = self.wrap_t.apply(y, l.height);
+			l.texels[(y * l.width + x) as usize]
+		};
+		match filter {
+			Filter::Nearest => texel(u.floor() as i64, v.floor() as i64),
+			Filter::Linear => {
+				let (u, v) = (u - 0.5, v - 0.5);
+				let (x0, y0) = (u.floor(), v.floor());
+				let (fx, fy) = (u - x0, v - y0);
+				let (x0, y0) = (x0 as i64, y0 as i64);
+				let top = texel(x0, y0) * (1.0 - fx) + texel(x0 + 1, y0) * fx;
+				let bottom = texel(x0, y0 + 1) * (1.0 - fx) + texel(x0 + 1, y0 + 1) * fx;
+				top * (1.0 - fy) + bottom * fy
+			}
+		}
+	}
+}
+
+/// `texture(sampler, uv)`
+pub fn texture<V>(sampler: &Sampler2D, fragment: &Fragment<V>, uv: impl Fn(&V) -> Vec2) -> Vec4 {
+	sampler.sample(fragment, uv)
+}
+
+/// `textureLod(sampler, uv, lod)`
+pub fn texture_lod(sampler: &Sampler2D, uv: Vec2, lod: f32) -> Vec4 {
+	sampler.sample_lod(uv, lod)
+}
+
+/// `textureGrad(sampler, uv, dx, dy)`
+pub fn texture_grad(sampler: &Sampler2D, uv: Vec2, dx: Vec2, dy: Vec2) -> Vec4 {
+	sampler.sample_grad(uv, dx, dy)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn gray(v: f32) -> Vec4 {
+		Vec4::new(v, v, v, 1.0)
+	}
+
+	// 4x2 texture with texel values 0..8 in row order.
+	fn ramp() -> Sampler2D {
+		let mut texture = Texture2D::new(4, 2, (0..8).map(|i| gray(i as f32)).collect());
+		texture.generate_mipmaps();
+		Sampler2D::new(Arc::new(texture))
+	}
+
+	#[test]
+	fn mip_chain() {
+		let s = ramp();
+		let t = &s.texture;
+		assert_eq!(t.level_count(), 3);
+		assert_eq!((t.level(1).width, t.level(1).height), (2, 1));
+		assert_eq!(t.texel(1, 0, 0), gray((0.0 + 1.0 + 4.0 + 5.0) / 4.0));
+		assert_eq!(t.texel(1, 1, 0), gray((2.0 + 3.0 + 6.0 + 7.0) / 4.0));
+		assert_eq!(t.texel(2, 0, 0), gray(3.5));
+	}
+
+	#[test]
+	fn wrap_modes() {
+		let wrapped = |w: Wrap| [-5, -1, 0, 3, 4, 7].map(|i| w.apply(i, 4));
+		assert_eq!(wrapped(Wrap::Repeat), [3, 3, 0, 3, 0, 3]);
+		assert_eq!(wrapped(Wrap::ClampToEdge), [0, 0, 0, 3, 3, 3]);
+		assert_eq!(wrapped(Wrap::MirroredRepeat), [3, 0, 0, 3, 3, 0]);
+	}
+
+	#[test]
+	fn filtering() {
+		let mut s = ramp();
+		// Texel centers sample exactly.
+		assert_eq!(s.sample_lod(Vec2::new(0.375, 0.25), 0.0), gray(1.0));
+		// Halfway between texels 1 and 2.
+		assert_eq!(s.sample_lod(Vec2::new(0.5, 0.25), 0.0), gray(1.5));
+		// Repeat blends the last and first texels across the edge.
+		assert_eq!(s.sample_lod(Vec2::new(0.0, 0.25), 0.0), gray(1.5));
+		s.wrap_s = Wrap::ClampToEdge;
+		assert_eq!(s.sample_lod(Vec2::new(0.0, 0.25), 0.0), gray(0.0));
+		s.mag_filter = Filter::Nearest;
+		assert_eq!(s.sample_lod(Vec2::new(0.49, 0.25), 0.0), gray(1.0));
+	}
+
+	#[test]
+	fn trilinear() {
+		let mut s = ramp();
+		let uv = Vec2::new(0.5, 0.5);
+		assert_eq!(s.sample_lod(uv, 2.0), gray(3.5));
+		assert_eq!(s.sample_lod(uv, 10.0), gray(3.5));
+		let level0 = s.sample_lod(uv, 0.0);
+		let level1 = s.sample_lod(uv, 1.0);
+		assert_eq!(s.sample_lod(uv, 0.5), (level0 + level1) * 0.5);
+		s.mipmap_filter = Some(Filter::Nearest);
+		assert_eq!(s.sample_lod(uv, 0.5), level0);
+		assert_eq!(s.sample_lod(uv, 0.6), level1);
+		s.mipmap_filter = None;
+		assert_eq!(s.sample_lod(uv, 5.0), level0);
+	}
+
+	#[test]
+	fn lod_from_derivatives() {
+		let s = ramp();
+		// One texel per pixel, then two texels per pixel horizontally.
+		assert_eq!(s.lod(Vec2::new(0.25, 0.0), Vec2::new(0.0, 0.5)), 0.0);
+		assert_eq!(s.lod(Vec2::new(0.5, 0.0), Vec2::new(0.0, 0.5)), 1.0);
+		let uv = |x: f32| Vec2::new(x, 0.5);
+		let fragment = Fragment {
+			varyings: 0.0,
+			quad: [0.0, 0.5, 0.0, 0.5],
+			lane: 0,
+			frag_coord: Vec4::default(),
+			front_facing: true,
+		};
+		assert_eq!(
+			texture(&s, &fragment, |&x| uv(x)),
+			texture_lod(&s, uv(0.0), 1.0)
+		);
+	}
+}
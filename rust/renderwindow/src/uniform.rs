@@ -0,0 +1,230 @@
+////////////////////////////////////////////////////////////////////////////////
+// Uniform Handling
+////////////////////////////////////////////////////////////////////////////////
+
+use std::fmt;
+
+use crate::math::{mat_invert, Mat4, Vec3};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum UniformError {
+	ModelNotSet,
+	ViewNotSet,
+	ProjectionNotSet,
+	ViewNotInvertible,
+}
+
+impl fmt::Display for UniformError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			UniformError::ModelNotSet => write!(f, "Model matrix not set."),
+			UniformError::ViewNotSet => write!(f, "View matrix not set."),
+			UniformError::ProjectionNotSet => write!(f, "Projection matrix not set."),
+			UniformError::ViewNotInvertible => write!(f, "View matrix is not invertible."),
+		}
+	}
+}
+
+impl std::error::Error for UniformError {}
+
+/// Port of the `getTransform*`/`setTransform*` functions of
+/// `module_uniform_webgl2.js`. The products are computed on first use and
+/// dropped whenever one of their factors is set.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TransformUniforms {
+	model: Option<Mat4>,
+	view: Option<Mat4>,
+	projection: Option<Mat4>,
+	modelview: Option<Mat4>,
+	viewprojection: Option<Mat4>,
+	modelviewprojection: Option<Mat4>,
+	eye: Option<Vec3>,
+	pub time: f32,
+}
+
+impl TransformUniforms {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	pub fn model(&self) -> Result<Mat4, UniformError> {
+		self.model.ok_or(UniformError::ModelNotSet)
+	}
+
+	pub fn view(&self) -> Result<Mat4, UniformError> {
+		self.view.ok_or(UniformError::ViewNotSet)
+	}
+
+	pub fn projection(&self) -> Result<Mat4, UniformError> {
+		self.projection.ok_or(UniformError::ProjectionNotSet)
+	}
+
+	pub fn modelview(&mut self) -> Result<Mat4, UniformError> {
+		if let Some(m) = self.modelview {
+			return Ok(m);
+		}
+		let m = self.model()? * self.view()?;
+		Ok(*self.modelview.insert(m))
+	}
+
+	pub fn viewprojection(&mut self) -> Result<Mat4, UniformError> {
+		if let Some(m) = self.viewprojection {
+			return Ok(m);
+		}
+		let m = self.view()? * self.projection()?;
+		Ok(*self.viewprojection.insert(m))
+	}
+
+	pub fn modelviewprojection(&mut self) -> Result<Mat4, UniformError> {
+		// The JS returns `viewprojection` from this branch.
+		if let Some(m) = self.modelviewprojection {
+			return Ok(m);
+		}
+		let m = self.model()? * self.viewprojection()?;
+		Ok(*self.modelviewprojection.insert(m))
+	}
+
+	/// Camera position in world space: the translation row of the inverse view.
+	pub fn eye(&mut self) -> Result<Vec3, UniformError> {
+		if let Some(eye) = self.eye {
+			return Ok(eye);
+		}
+		let inverse = mat_invert(&self.view()?).ok_or(UniformError::ViewNotInvertible)?;
+		Ok(*self.eye.insert(inverse.row(3).xyz()))
+	}
+
+	pub fn set_model(&mut self, model: Mat4) {
+		self.model = Some(model);
+		self.modelview = None;
+		self.modelviewprojection = None;
+	}
+
+	pub fn set_view(&mut self, view: Mat4) {
+		self.view = Some(view);
+		self.modelview = None;
+		self.viewprojection = None;
+		self.modelviewprojection = None;
+		self.eye = None;
+	}
+
+	pub fn set_projection(&mut self, projection: Mat4) {
+		self.projection = Some(projection);
+		self.viewprojection = None;
+		self.modelviewprojection = None;
+	}
+}
+
+/// The uniform block every program sees, filled the way `glSetUniforms` does.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Uniforms {
+	pub model: Mat4,
+	pub view: Mat4,
+	pub projection: Mat4,
+	pub modelview: Mat4,
+	pub viewprojection: Mat4,
+	pub modelviewprojection: Mat4,
+	pub eye: Vec3,
+	pub time: f32,
+}
+
+impl Uniforms {
+	pub fn from_transforms(t: &mut TransformUniforms) -> Result<Self, UniformError> {
+		Ok(Self {
+			model: t.model()?,
+			view: t.view()?,
+			projection: t.projection()?,
+			modelview: t.modelview()?,
+			viewprojection: t.viewprojection()?,
+			modelviewprojection: t.modelviewprojection()?,
+			eye: t.eye()?,
+			time: t.time,
+		})
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::math::{mat_look_at, mat_projection, mat_scale, mat_translate};
+
+	fn assert_mat_near(a: &Mat4, b: &Mat4) {
+		for (x, y) in a.to_array().iter().zip(b.to_array()) {
+			assert!((x - y).abs() < 1e-5, "{a:?} != {b:?}");
+		}
+	}
+
+	fn uniforms() -> TransformUniforms {
+		let mut u = TransformUniforms::new();
+		u.set_model(mat_translate(1.0, 2.0, 3.0));
+		u.set_view(mat_look_at(
+			Vec3::new(4.0, 5.0, 6.0),
+			Vec3::default(),
+			Vec3::new(0.0, 1.0, 0.0),
+		));
+		u.set_projection(mat_projection(90.0, 0.001, 100.0));
+		u
+	}
+
+	#[test]
+	fn modelviewprojection_is_correct_when_cached() {
+		let mut u = uniforms();
+		let expected = u.model().unwrap() * u.view().unwrap() * u.projection().unwrap();
+		let first = u.modelviewprojection().unwrap();
+		let cached = u.modelviewprojection().unwrap();
+		assert_mat_near(&first, &expected);
+		assert_eq!(cached, first);
+		assert_ne!(cached, u.viewprojection().unwrap());
+	}
+
+	#[test]
+	fn setters_invalidate() {
+		let mut u = uniforms();
+		u.modelview().unwrap();
+		u.modelviewprojection().unwrap();
+		u.set_model(mat_scale(2.0, 2.0, 2.0));
+		let expected = mat_scale(2.0, 2.0, 2.0) * u.view().unwrap();
+		assert_mat_near(&u.modelview().unwrap(), &expected);
+		assert_mat_near(
+			&u.modelviewprojection().unwrap(),
+			&(expected * u.projection().unwrap()),
+		);
+		u.set_projection(Mat4::IDENTITY);
+		assert_mat_near(&u.viewprojection().unwrap(), &u.view().unwrap());
+		assert_mat_near(&u.modelviewprojection().unwrap(), &expected);
+	}
+
+	#[test]
+	fn eye() {
+		let mut u = uniforms();
+		let eye = u.eye().unwrap();
+		assert!((eye - Vec3::new(4.0, 5.0, 6.0)).length() < 1e-4, "{eye:?}");
+		u.set_view(mat_translate(-1.0, 0.0, 0.0));
+		assert!((u.eye().unwrap() - Vec3::new(1.0, 0.0, 0.0)).length() < 1e-6);
+		u.set_view(mat_scale(0.0, 1.0, 1.0));
+		assert_eq!(u.eye(), Err(UniformError::ViewNotInvertible));
+	}
+
+	#[test]
+	fn uniform_block() {
+		let mut u = uniforms();
+		u.time = 1.5;
+		let block = Uniforms::from_transforms(&mut u).unwrap();
+		assert_eq!(block.modelviewprojection, u.modelviewprojection().unwrap());
+		assert_eq!(block.eye, u.eye().unwrap());
+		assert_eq!(block.time, 1.5);
+	}
+
+	#[test]
+	fn errors() {
+		let mut u = TransformUniforms::new();
+		assert_eq!(u.modelviewprojection(), Err(UniformError::ModelNotSet));
+		u.set_model(Mat4::IDENTITY);
+		assert_eq!(u.modelviewprojection(), Err(UniformError::ViewNotSet));
+		u.set_view(Mat4::IDENTITY);
+		assert_eq!(u.modelviewprojection(), Err(UniformError::ProjectionNotSet));
+		assert_eq!(
+			UniformError::ModelNotSet.to_string(),
+			"Model matrix not set."
+		);
+	}
+}
@@ -0,0 +1,567 @@
+////////////////////////////////////////////////////////////////////////////////
+// Vertex Layouts
+////////////////////////////////////////////////////////////////////////////////
+//
+// The GL and WebGL ports upload one tightly packed buffer per attribute
+// (`glVertexAttribPointer(1, 3, GL_FLOAT, GL_FALSE, 12, 0)`), while
+// Material.swift's `getVertexDescriptor` interleaves position and normal in one
+// `Shader.Vertex` whose `simd_float3` members are 16 bytes apart. A
+// `VertexLayout` describes either, checks it once, and moves `MeshGen` data in
+// and out of little-endian byte buffers laid out that way.
+
+use std::fmt;
+
+use crate::math::{Vec2, Vec3, Vec4};
+use crate::mesh::MeshGen;
+
+/// Which `MeshGen` array an attribute carries.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Semantic {
+	Position,
+	Normal,
+	TexCoord0,
+	Tangent,
+}
+
+impl Semantic {
+	/// Components in the mesh: 3, 3, 2 and 4.
+	pub fn components(self) -> usize {
+		match self {
+			Semantic::Position | Semantic::Normal => 3,
+			Semantic::TexCoord0 => 2,
+			Semantic::Tangent => 4,
+		}
+	}
+
+	// Padding for components the mesh does not have: 1 for the w of a
+	// point, 0 otherwise.
+	fn fill(self, component: usize) -> f32 {
+		if self == Semantic::Position && component == 3 {
+			1.0
+		} else {
+			0.0
+		}
+	}
+}
+
+impl fmt::Display for Semantic {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		let name = match self {
+			Semantic::Position => "position",
+			Semantic::Normal => "normal",
+			Semantic::TexCoord0 => "texcoord0",
+			Semantic::Tangent => "tangent",
+		};
+		f.write_str(name)
+	}
+}
+
+/// How an attribute is stored, as `MTLVertexFormat` names them.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Format {
+	Float2,
+	Float3,
+	Float4,
+}
+
+impl Format {
+	pub fn components(self) -> usize {
+		match self {
+			Format::Float2 => 2,
+			Format::Float3 => 3,
+			Format::Float4 => 4,
+		}
+	}
+
+	pub fn size(self) -> usize {
+		self.components() * 4
+	}
+
+	/// The narrowest format for `semantic`.
+	pub fn packed(semantic: Semantic) -> Format {
+		match semantic.components() {
+			2 => Format::Float2,
+			3 => Format::Float3,
+			_ => Format::Float4,
+		}
+	}
+}
+
+/// One attribute: where in which buffer it lives.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct VertexAttribute {
+	pub semantic: Semantic,
+	pub format: Format,
+	/// Byte offset within each vertex.
+	pub offset: usize,
+	/// Buffer slot, `bufferIndex` in Metal.
+	pub buffer: usize,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LayoutError {
+	DuplicateSemantic(Semantic),
+	/// The format has fewer components than the semantic needs.
+	FormatTooNarrow {
+		semantic: Semantic,
+		format: Format,
+	},
+	MissingStride {
+		buffer: usize,
+	},
+	/// Offsets must be multiples of 4 for float data.
+	Misaligned {
+		semantic: Semantic,
+		offset: usize,
+	},
+	/// Strides must be multiples of 4 too, or every vertex after the first is
+	/// misaligned.
+	MisalignedStride {
+		buffer: usize,
+		stride: usize,
+	},
+	OutsideStride {
+		semantic: Semantic,
+		stride: usize,
+	},
+	Overlap(Semantic, Semantic),
+	/// The mesh has no data for an attribute of the layout.
+	MissingAttribute(Semantic),
+	BufferCount {
+		expected: usize,
+		actual: usize,
+	},
+	BufferTooShort {
+		buffer: usize,
+		len: usize,
+		needed: usize,
+	},
+}
+
+impl fmt::Display for LayoutError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			LayoutError::DuplicateSemantic(s) => write!(f, "Attribute {s} appears twice."),
+			LayoutError::FormatTooNarrow { semantic, format } => {
+				write!(f, "Format {format:?} is too narrow for {semantic}.")
+			}
+			LayoutError::MissingStride { buffer } => {
+				write!(f, "Buffer {buffer} has no stride.")
+			}
+			LayoutError::Misaligned { semantic, offset } => {
+				write!(
+					f,
+					"Attribute {semantic} at offset {offset} is not 4-byte aligned."
+				)
+			}
+			LayoutError::MisalignedStride { buffer, stride } => {
+				write!(
+					f,
+					"Buffer {buffer} has a stride of {stride}, which is not 4-byte aligned."
+				)
+			}
+			LayoutError::OutsideStride { semantic, stride } => {
+				write!(
+					f,
+					"Attribute {semantic} does not fit in a stride of {stride}."
+				)
+			}
+			LayoutError::Overlap(a, b) => write!(f, "Attributes {a} and {b} overlap."),
+			LayoutError::MissingAttribute(s) => write!(f, "The mesh has no {s} data."),
+			LayoutError::BufferCount { expected, actual } => {
+				write!(f, "Expected {expected} buffers, got {actual}.")
+			}
+			LayoutError::BufferTooShort {
+				buffer,
+				len,
+				needed,
+			} => write!(f, "Buffer {buffer} has {len} bytes, {needed} needed."),
+		}
+	}
+}
+
+impl std::error::Error for LayoutError {}
+
+/// A checked set of vertex attributes and the stride of each buffer slot,
+/// stepped once per vertex.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct VertexLayout {
+	attributes: Vec<VertexAttribute>,
+	strides: Vec<usize>,
+}
+
+impl VertexLayout {
+	pub fn new(attributes: Vec<VertexAttribute>, strides: Vec<usize>) -> Result<Self, LayoutError> {
+		for (i, a) in attributes.iter().enumerate() {
+			if attributes[..i].iter().any(|b| b.semantic == a.semantic) {
+				return Err(LayoutError::DuplicateSemantic(a.semantic));
+			}
+			if a.format.components() < a.semantic.components() {
+				return Err(LayoutError::FormatTooNarrow {
+					semantic: a.semantic,
+					format: a.format,
+				});
+			}
+			let stride = match strides.get(a.buffer) {
+				Some(&s) if s > 0 => s,
+				_ => return Err(LayoutError::MissingStride { buffer: a.buffer }),
+			};
+			if !stride.is_multiple_of(4) {
+				return Err(LayoutError::MisalignedStride {
+					buffer: a.buffer,
+					stride,
+				});
+			}
+			if !a.offset.is_multiple_of(4) {
+				return Err(LayoutError::Misaligned {
+					semantic: a.semantic,
+					offset: a.offset,
+				});
+			}
+			if a.offset + a.format.size() > stride {
+				return Err(LayoutError::OutsideStride {
+					semantic: a.semantic,
+					stride,
+				});
+			}
+			let end = a.offset + a.format.size();
+			if let Some(b) = attributes[..i].iter().find(|b| {
+				b.buffer == a.buffer && a.offset < b.offset + b.format.size() && b.offset < end
+			}) {
+				return Err(LayoutError::Overlap(b.semantic, a.semantic));
+			}
+		}
+		Ok(Self {
+			attributes,
+			strides,
+		})
+	}
+
+	/// One tightly packed buffer per attribute, in order, as the GL ports
+	/// upload them.
+	pub fn planar(semantics: &[Semantic]) -> Result<Self, LayoutError> {
+		let attributes = semantics
+			.iter()
+			.enumerate()
+			.map(|(buffer, &semantic)| VertexAttribute {
+				semantic,
+				format: Format::packed(semantic),
+				offset: 0,
+				buffer,
+			})
+			.collect::<Vec<_>>();
+		let strides = attributes.iter().map(|a| a.format.size()).collect();
+		Self::new(attributes, strides)
+	}
+
+	/// All attributes packed back to back in buffer 0.
+	pub fn interleaved(semantics: &[Semantic]) -> Result<Self, LayoutError> {
+		let mut offset = 0;
+		let attributes = semantics
+			.iter()
+			.map(|&semantic| {
+				let format = Format::packed(semantic);
+				let a = VertexAttribute {
+					semantic,
+					format,
+					offset,
+					buffer: 0,
+				};
+				offset += format.size();
+				a
+			})
+			.collect();
+		Self::new(attributes, vec![offset])
+	}
+
+	pub fn attributes(&self) -> &[VertexAttribute] {
+		&self.attributes
+	}
+
+	pub fn buffer_count(&self) -> usize {
+		self.strides.len()
+	}
+
+	pub fn stride(&self, buffer: usize) -> usize {
+		self.strides[buffer]
+	}
+
+	pub fn attribute(&self, semantic: Semantic) -> Option<&VertexAttribute> {
+		self.attributes.iter().find(|a| a.semantic == semantic)
+	}
+
+	/// Whether every attribute lives in one buffer.
+	pub fn is_interleaved(&self) -> bool {
+		self.strides.len() == 1
+	}
+
+	/// The vertices of `mesh` as one byte buffer per slot. Padding bytes are
+	/// zero; components a wide format adds are 1 for a position's w and 0
+	/// elsewhere.
+	pub fn write(&self, mesh: &MeshGen) -> Result<Vec<Vec<u8>>, LayoutError> {
+		let n = mesh.vertex_count();
+		let mut buffers: Vec<Vec<u8>> = self.strides.iter().map(|s| vec![0; s * n]).collect();
+		for a in &self.attributes {
+			let len = match a.semantic {
+				Semantic::Position => mesh.vtx.len(),
+				Semantic::Normal => mesh.nor.len(),
+				Semantic::TexCoord0 => mesh.st0.len(),
+				Semantic::Tangent => mesh.tan.len(),
+			};
+			if len != n {
+				return Err(LayoutError::MissingAttribute(a.semantic));
+			}
+			let stride = self.strides[a.buffer];
+			for i in 0..n {
+				let values = mesh_components(mesh, a.semantic, i);
+				let start = i * stride + a.offset;
+				let bytes = &mut buffers[a.buffer][start..start + a.format.size()];
+				for (c, chunk) in bytes.chunks_exact_mut(4).enumerate() {
+					let value = values.get(c).copied().unwrap_or(a.semantic.fill(c));
+					chunk.copy_from_slice(&value.to_le_bytes());
+				}
+			}
+		}
+		Ok(buffers)
+	}
+
+	/// `vertex_count` vertices from `buffers`, into a mesh with no indices.
+	/// Semantics the layout lacks are left empty.
+	pub fn read<B: AsRef<[u8]>>(
+		&self,
+		buffers: &[B],
+		vertex_count: usize,
+	) -> Result<MeshGen, LayoutError> {
+		if buffers.len() != self.strides.len() {
+			return Err(LayoutError::BufferCount {
+				expected: self.strides.len(),
+				actual: buffers.len(),
+			});
+		}
+		for (buffer, (b, &stride)) in buffers.iter().zip(&self.strides).enumerate() {
+			// The last vertex only needs to reach the end of its attributes. A
+			// size past `usize::MAX` is more than any buffer holds.
+			let end = self
+				.attributes
+				.iter()
+				.filter(|a| a.buffer == buffer)
+				.map(|a| a.offset + a.format.size())
+				.max()
+				.unwrap_or(0);
+			let needed = match vertex_count {
+				0 => 0,
+				n => (n - 1)
+					.checked_mul(stride)
+					.and_then(|n| n.checked_add(end))
+					.unwrap_or(usize::MAX),
+			};
+			if b.as_ref().len() < needed {
+				return Err(LayoutError::BufferTooShort {
+					buffer,
+					len: b.as_ref().len(),
+					needed,
+				});
+			}
+		}
+		let mut mesh = MeshGen::default();
+		for a in &self.attributes {
+			let bytes = buffers[a.buffer].as_ref();
+			let stride = self.strides[a.buffer];
+			let get = |i: usize, c: usize| {
+				let at = i * stride + a.offset + c * 4;
+				f32::from_le_bytes(bytes[at..at + 4].try_into().unwrap())
+			};
+			for i in 0..vertex_count {
+				match a.semantic {
+					Semantic::Position => mesh.vtx.push(Vec3::new(get(i, 0), get(i, 1), get(i, 2))),
+					Semantic::Normal => mesh.nor.push(Vec3::new(get(i, 0), get(i, 1), get(i, 2))),
+					Semantic::TexCoord0 => mesh.st0.push(Vec2::new(get(i, 0), get(i, 1))),
+					Semantic::Tangent => {
+						mesh.tan
+							.push(Vec4::new(get(i, 0), get(i, 1), get(i, 2), get(i, 3)))
+					}
+				}
+			}
+		}
+		Ok(mesh)
+	}
+
+	/// Re-lay `vertex_count` vertices from this layout into `to`, such as
+	/// planar buffers into one interleaved buffer.
+	pub fn convert<B: AsRef<[u8]>>(
+		&self,
+		buffers: &[B],
+		vertex_count: usize,
+		to: &VertexLayout,
+	) -> Result<Vec<Vec<u8>>, LayoutError> {
+		to.write(&self.read(buffers, vertex_count)?)
+	}
+}
+
+fn mesh_components(mesh: &MeshGen, semantic: Semantic, i: usize) -> Vec<f32> {
+	match semantic {
+		Semantic::Position => mesh.vtx[i].to_array().to_vec(),
+		Semantic::Normal => mesh.nor[i].to_array().to_vec(),
+		Semantic::TexCoord0 => mesh.st0[i].to_array().to_vec(),
+		Semantic::Tangent => mesh.tan[i].to_array().to_vec(),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::parametric::{create_parametric, Torus};
+
+	const GL41: [Semantic; 3] = [Semantic::Position, Semantic::Normal, Semantic::TexCoord0];
+
+	#[test]
+	fn presets() {
+		// module_mesh_gl41.py: slots 0, 1 and 2 with strides 12, 12 and 8.
+		let planar = VertexLayout::planar(&GL41).unwrap();
+		assert_eq!(planar.buffer_count(), 3);
+		assert_eq!(
+			(0..3).map(|b| planar.stride(b)).collect::<Vec<_>>(),
+			[12, 12, 8]
+		);
+		assert!(!planar.is_interleaved());
+		let interleaved = VertexLayout::interleaved(&GL41).unwrap();
+		assert!(interleaved.is_interleaved());
+		assert_eq!(interleaved.stride(0), 32);
+		assert_eq!(
+			interleaved.attribute(Semantic::TexCoord0).unwrap().offset,
+			24
+		);
+		assert_eq!(interleaved.attribute(Semantic::Tangent), None);
+	}
+
+	// Material.swift: `simd_float3` has a stride of 16, so the normal sits at
+	// 16 and a `Shader.Vertex` is 32 bytes.
+	fn metal() -> Vec<VertexAttribute> {
+		vec![
+			VertexAttribute {
+				semantic: Semantic::Position,
+				format: Format::Float3,
+				offset: 0,
+				buffer: 0,
+			},
+			VertexAttribute {
+				semantic: Semantic::Normal,
+				format: Format::Float3,
+				offset: 16,
+				buffer: 0,
+			},
+		]
+	}
+
+	#[test]
+	fn validation() {
+		assert!(VertexLayout::new(metal(), vec![32]).is_ok());
+		let mut attributes = metal();
+		attributes[1].offset = 8;
+		assert_eq!(
+			VertexLayout::new(attributes.clone(), vec![32]),
+			Err(LayoutError::Overlap(Semantic::Position, Semantic::Normal))
+		);
+		attributes[1].offset = 18;
+		assert_eq!(
+			VertexLayout::new(attributes.clone(), vec![32]),
+			Err(LayoutError::Misaligned {
+				semantic: Semantic::Normal,
+				offset: 18
+			})
+		);
+		assert_eq!(
+			VertexLayout::new(metal(), vec![34]),
+			Err(LayoutError::MisalignedStride {
+				buffer: 0,
+				stride: 34
+			})
+		);
+		attributes[1].offset = 24;
+		assert_eq!(
+			VertexLayout::new(attributes.clone(), vec![32]),
+			Err(LayoutError::OutsideStride {
+				semantic: Semantic::Normal,
+				stride: 32
+			})
+		);
+		attributes[1].buffer = 1;
+		assert_eq!(
+			VertexLayout::new(attributes.clone(), vec![32]),
+			Err(LayoutError::MissingStride { buffer: 1 })
+		);
+		attributes[1].format = Format::Float2;
+		assert_eq!(
+			VertexLayout::new(attributes, vec![32, 12]),
+			Err(LayoutError::FormatTooNarrow {
+				semantic: Semantic::Normal,
+				format: Format::Float2
+			})
+		);
+		assert_eq!(
+			VertexLayout::planar(&[Semantic::Normal, Semantic::Normal]),
+			Err(LayoutError::DuplicateSemantic(Semantic::Normal))
+		);
+	}
+
+	#[test]
+	fn planar_to_interleaved_and_back() {
+		let mut mesh = create_parametric(4, 3, &Torus::new(2.0, 0.5));
+		let planar = VertexLayout::planar(&GL41).unwrap();
+		let interleaved = VertexLayout::interleaved(&GL41).unwrap();
+		let buffers = planar.write(&mesh).unwrap();
+		assert_eq!(buffers[2].len(), 20 * 8);
+		let packed = planar.convert(&buffers, 20, &interleaved).unwrap();
+		assert_eq!(packed.len(), 1);
+		assert_eq!(packed[0].len(), 20 * 32);
+		// Vertex 1's texture coordinate follows its position and normal.
+		let s = f32::from_le_bytes(packed[0][32 + 24..32 + 28].try_into().unwrap());
+		assert_eq!(s, mesh.st0[1].x);
+		let back = interleaved.read(&packed, 20).unwrap();
+		mesh.idx.clear();
+		mesh.tan.clear();
+		assert_eq!(back, mesh);
+		assert_eq!(
+			interleaved.read(&[&packed[0][..100]], 20),
+			Err(LayoutError::BufferTooShort {
+				buffer: 0,
+				len: 100,
+				needed: 640
+			})
+		);
+		assert_eq!(
+			interleaved.read(&packed, usize::MAX),
+			Err(LayoutError::BufferTooShort {
+				buffer: 0,
+				len: 640,
+				needed: usize::MAX
+			})
+		);
+		let tangents = VertexLayout::planar(&[Semantic::Tangent]).unwrap();
+		assert_eq!(
+			tangents.write(&mesh),
+			Err(LayoutError::MissingAttribute(Semantic::Tangent))
+		);
+	}
+
+	#[test]
+	fn padding_and_wide_formats() {
+		// The Metal layout leaves 4 bytes of padding after each member and a
+		// Float4 position gains w = 1.
+		let mut attributes = metal();
+		attributes[0].format = Format::Float4;
+		let layout = VertexLayout::new(attributes, vec![32]).unwrap();
+		let mesh = MeshGen {
+			vtx: vec![Vec3::new(1.0, 2.0, 3.0)],
+			nor: vec![Vec3::new(0.0, 1.0, 0.0)],
+			..MeshGen::default()
+		};
+		let bytes = &layout.write(&mesh).unwrap()[0];
+		let floats: Vec<f32> = bytes
+			.chunks_exact(4)
+			.map(|c| f32::from_le_bytes(c.try_into().unwrap()))
+			.collect();
+		assert_eq!(floats, [1.0, 2.0, 3.0, 1.0, 0.0, 1.0, 0.0, 0.0]);
+		assert_eq!(layout.read(&[bytes], 1).unwrap(), mesh);
+	}
+}
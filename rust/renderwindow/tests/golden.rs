@@ -0,0 +1,252 @@
+// Golden-image regression tests: render named scenes at fixed times and compare
+// them with the references in tests/golden/.
+//
+// Run with RENDERWINDOW_BLESS=1 to (re)write the references after an intended
+// change. On failure the actual and diff images are written next to the test
+// binary's scratch directory and their paths are printed.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use renderwindow::bump::{normal_map, NormalMapOptions, ShaderFragmentLitNormalMap};
+use renderwindow::golden::{compare_rgb8, Tolerance};
+use renderwindow::image::{encode_ppm, framebuffer_rgb8, load_pnm, save_ppm};
+use renderwindow::raster::Framebuffer;
+use renderwindow::relief::{relaxed_cone_map, ShaderFragmentRelaxedCone, ShaderFragmentRelief};
+use renderwindow::scene::{
+	demo_albedo, demo_height, demo_sampler, render_demo, render_demo_with, Scene,
+};
+use renderwindow::shader::{
+	ShaderFragmentBitangent, ShaderFragmentTangent, ShaderVertex, ShaderVertexTangent,
+};
+use renderwindow::texture::{Sampler2D, Texture2D};
+
+const SIZE: u32 = 128;
+
+struct Golden {
+	name: &'static str,
+	time: f32,
+	render: fn(u32, u32, f32) -> Framebuffer,
+}
+
+fn demo(width: u32, height: u32, time: f32) -> Framebuffer {
+	render_demo(&Scene::demo(), width, height, time)
+}
+
+fn tangent(width: u32, height: u32, time: f32) -> Framebuffer {
+	render_demo_with(
+		&Scene::demo(),
+		width,
+		height,
+		time,
+		&ShaderVertex,
+		&ShaderFragmentTangent,
+	)
+}
+
+fn bitangent(width: u32, height: u32, time: f32) -> Framebuffer {
+	render_demo_with(
+		&Scene::demo(),
+		width,
+		height,
+		time,
+		&ShaderVertex,
+		&ShaderFragmentBitangent,
+	)
+}
+
+// The same view from the meshes' own tangents instead of derivatives. The torus
+// differs: its texture space is mirrored (w = -1), and the derivative tangent
+// is not divided by the sign of the texture coordinate Jacobian.
+fn vertex_tangent(width: u32, height: u32, time: f32) -> Framebuffer {
+	render_demo_with(
+		&Scene::demo(),
+		width,
+		height,
+		time,
+		&ShaderVertexTangent,
+		&ShaderFragmentTangent,
+	)
+}
+
+fn relief(width: u32, height: u32, time: f32) -> Framebuffer {
+	let fs = ShaderFragmentRelief::new(demo_sampler(&demo_albedo()), demo_sampler(&demo_height()));
+	render_demo_with(&Scene::demo(), width, height, time, &ShaderVertex, &fs)
+}
+
+fn relief_refined(width: u32, height: u32, time: f32) -> Framebuffer {
+	let mut fs =
+		ShaderFragmentRelief::new(demo_sampler(&demo_albedo()), demo_sampler(&demo_height()));
+	fs.refine_steps = 6;
+	render_demo_with(&Scene::demo(), width, height, time, &ShaderVertex, &fs)
+}
+
+fn relaxed_cone(width: u32, height: u32, time: f32) -> Framebuffer {
+	let fs =
+		ShaderFragmentRelaxedCone::new(demo_sampler(&demo_albedo()), demo_sampler(&demo_height()));
+	render_demo_with(&Scene::demo(), width, height, time, &ShaderVertex, &fs)
+}
+
+// The cone map is built from the 128x128 mip level to keep debug runs quick.
+fn relaxed_cone_map_128(width: u32, height: u32, time: f32) -> Framebuffer {
+	let mut texture = demo_height().to_texture();
+	texture.generate_mipmaps();
+	let half = texture.level(1);
+	let half = Texture2D::new(half.width, half.height, half.texels.clone());
+	let cone_map = Sampler2D::new(Arc::new(relaxed_cone_map(&half)));
+	let fs = ShaderFragmentRelaxedCone::with_cone_map(demo_sampler(&demo_albedo()), cone_map);
+	render_demo_with(&Scene::demo(), width, height, time, &ShaderVertex, &fs)
+}
+
+// The relief programs read the height canvas as depth, so the blobs are pits.
+fn lit_normal_map(width: u32, height: u32, time: f32) -> Framebuffer {
+	let options = NormalMapOptions {
+		strength: -8.0,
+		..NormalMapOptions::default()
+	};
+	let mut normals = normal_map(&demo_height().to_texture(), &options);
+	normals.generate_mipmaps();
+	let fs = ShaderFragmentLitNormalMap {
+		albedo: demo_sampler(&demo_albedo()),
+		normal_map: Sampler2D::new(Arc::new(normals)),
+	};
+	render_demo_with(&Scene::demo(), width, height, time, &ShaderVertex, &fs)
+}
+
+fn primitives(width: u32, height: u32, time: f32) -> Framebuffer {
+	render_demo(&Scene::primitives(), width, height, time)
+}
+
+const GOLDENS: &[Golden] = &[
+	Golden {
+		name: "demo_t000",
+		time: 0.0,
+		render: demo,
+	},
+	Golden {
+		name: "demo_t100",
+		time: 1.0,
+		render: demo,
+	},
+	Golden {
+		name: "demo_t250",
+		time: 2.5,
+		render: demo,
+	},
+	Golden {
+		name: "demo_t1570",
+		time: 15.7,
+		render: demo,
+	},
+	Golden {
+		name: "tangent_t100",
+		time: 1.0,
+		render: tangent,
+	},
+	Golden {
+		name: "bitangent_t100",
+		time: 1.0,
+		render: bitangent,
+	},
+	Golden {
+		name: "vertex_tangent_t100",
+		time: 1.0,
+		render: vertex_tangent,
+	},
+	Golden {
+		name: "relief_t100",
+		time: 1.0,
+		render: relief,
+	},
+	Golden {
+		name: "relief_refined_t100",
+		time: 1.0,
+		render: relief_refined,
+	},
+	Golden {
+		name: "relaxed_cone_t100",
+		time: 1.0,
+		render: relaxed_cone,
+	},
+	Golden {
+		name: "relaxed_cone_map_t100",
+		time: 1.0,
+		render: relaxed_cone_map_128,
+	},
+	Golden {
+		name: "lit_normal_map_t100",
+		time: 1.0,
+		render: lit_normal_map,
+	},
+	Golden {
+		name: "primitives_t100",
+		time: 1.0,
+		render: primitives,
+	},
+	Golden {
+		name: "primitives_t1570",
+		time: 15.7,
+		render: primitives,
+	},
+];
+
+fn reference_dir() -> PathBuf {
+	Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/golden")
+}
+
+fn output_dir() -> PathBuf {
+	let dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join("golden");
+	fs::create_dir_all(&dir).unwrap();
+	dir
+}
+
+fn check(golden: &Golden) -> Result<(), String> {
+	let fb = (golden.render)(SIZE, SIZE, golden.time);
+	let reference = reference_dir().join(format!("{}.ppm", golden.name));
+	if std::env::var_os("RENDERWINDOW_BLESS").is_some() {
+		save_ppm(&fb, &reference).unwrap();
+		return Ok(());
+	}
+	let expected = load_pnm(&reference).map_err(|e| {
+		format!(
+			"{}: cannot read {} ({e}); run with RENDERWINDOW_BLESS=1 to create it",
+			golden.name,
+			reference.display()
+		)
+	})?;
+	if (expected.width, expected.height, expected.channels) != (SIZE, SIZE, 3) {
+		return Err(format!(
+			"{}: reference is {}x{}x{}, expected {SIZE}x{SIZE}x3",
+			golden.name, expected.width, expected.height, expected.channels
+		));
+	}
+	let tolerance = Tolerance::default();
+	let actual = framebuffer_rgb8(&fb);
+	let result = compare_rgb8(SIZE, SIZE, &expected.data, &actual, &tolerance);
+	if result.passes(&tolerance) {
+		return Ok(());
+	}
+	let dir = output_dir();
+	let actual_path = dir.join(format!("{}_actual.ppm", golden.name));
+	let diff_path = dir.join(format!("{}_diff.ppm", golden.name));
+	fs::write(&actual_path, encode_ppm(SIZE, SIZE, &actual)).unwrap();
+	fs::write(&diff_path, encode_ppm(SIZE, SIZE, &result.diff)).unwrap();
+	Err(format!(
+		"{}: {} pixels over ±{}, max channel diff {}, mean ΔE {:.3}, max ΔE {:.2}\n  actual: {}\n  diff:   {}",
+		golden.name,
+		result.outliers,
+		tolerance.channel,
+		result.max_channel_diff,
+		result.mean_delta_e,
+		result.max_delta_e,
+		actual_path.display(),
+		diff_path.display()
+	))
+}
+
+#[test]
+fn golden_images() {
+	let failures: Vec<String> = GOLDENS.iter().filter_map(|g| check(g).err()).collect();
+	assert!(failures.is_empty(), "\n{}", failures.join("\n"));
+}